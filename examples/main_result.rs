@@ -0,0 +1,26 @@
+use extension_eyre::{
+    builtin::{ExitCode, Severity},
+    ensure_ext, eyre,
+    eyre::{Report, WrapErr},
+    ExtensionExt, MainResult,
+};
+
+fn main() -> MainResult {
+    app().into()
+}
+
+fn app() -> Result<(), Report> {
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "fake_file".to_owned());
+
+    // this crate's macros install its hooks on first use, so `install()` isn't needed
+    let config = std::fs::read_to_string(&path)
+        .map_err(|error| eyre!(error))
+        .wrap_err_with(|| format!("Unable to read {}", path))
+        .extension(ExitCode(66))?;
+    ensure_ext!(!config.is_empty(), Severity::Fatal, "{} is empty", path);
+
+    println!("{}", config);
+    Ok(())
+}
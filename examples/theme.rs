@@ -0,0 +1,44 @@
+use extension_eyre::{
+    builtin::ErrorCode,
+    config::{ExtensionStyles, HookBuilder, Theme},
+    eyre::{eyre, Report},
+    owo_colors::Style,
+    Extension, ExtensionExt, Section,
+};
+
+pub struct Route(&'static str);
+
+impl std::fmt::Display for Route {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+fn main() -> Result<(), Report> {
+    let theme = Theme::dark().error(Style::new().bright_red().bold());
+    let styles = ExtensionStyles::dark()
+        .extension_header(Style::new().bold().underline())
+        .extension_key(Style::new().bright_blue())
+        .extension_value(Style::new().green())
+        .type_value::<u16>(Style::new().bright_red())
+        .error_code(Style::new().yellow());
+
+    HookBuilder::default()
+        .theme(theme)
+        .extension_styles(styles)
+        .install()?;
+
+    fetch_prices()
+}
+
+fn fetch_prices() -> Result<(), Report> {
+    let mut report = eyre!("upstream unavailable")
+        .extension(ErrorCode("E42"))
+        .suggestion("try again later");
+
+    let extensions = report.extensions_mut().unwrap();
+    extensions.insert_display(Route("GET /prices"));
+    extensions.insert_display(502u16);
+
+    Err(report)
+}
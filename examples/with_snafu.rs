@@ -1,4 +1,4 @@
-use extension_eyre::{eyre::Report, eyre::WrapErr, Extension, ExtensionExt};
+use extension_eyre::prelude::*;
 use snafu::Snafu;
 use std::process::Command;
 use tracing::instrument;
@@ -6,7 +6,7 @@ use tracing::instrument;
 pub struct Retry(bool);
 
 #[instrument]
-fn main() -> Result<(), Report> {
+fn main() -> Result<()> {
     install_tracing();
     extension_eyre::install()?;
 
@@ -21,22 +21,22 @@ fn main() -> Result<(), Report> {
 }
 
 #[instrument]
-fn read_config() -> Result<String, Report> {
+fn read_config() -> Result<String> {
     read_file("fake_file").wrap_err("Unable to read config")
 }
 
 #[instrument]
-fn read_file(path: &str) -> Result<String, Report> {
+fn read_file(path: &str) -> Result<String> {
     Command::new("cat").arg(path).output2()
 }
 
 trait Output {
-    fn output2(&mut self) -> Result<String, Report>;
+    fn output2(&mut self) -> Result<String>;
 }
 
 impl Output for Command {
     #[instrument]
-    fn output2(&mut self) -> Result<String, Report> {
+    fn output2(&mut self) -> Result<String> {
         let output = self.output()?;
 
         let stdout = String::from_utf8_lossy(&output.stdout);
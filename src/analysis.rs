@@ -0,0 +1,169 @@
+//! Measuring how large an error report is, see [`analyze`]
+use std::any::Any;
+use std::borrow::Cow;
+use std::fmt::{self, Write as _};
+use std::io;
+
+use crate::chain::Chain;
+use crate::eyre::Report;
+use crate::extensions::Extension;
+
+/// Size and content breakdown of an error report, produced by [`analyze`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportAnalysis {
+    /// Length in bytes of the report's `{:?}` rendering.
+    pub rendered_len: usize,
+    /// Length in bytes of the report serialized as JSON, when the `serde` feature is enabled.
+    pub json_len: Option<usize>,
+    /// Number of extensions attached to the report.
+    pub extension_count: usize,
+    /// Estimated memory held by each attached extension, keyed by type name, largest first.
+    pub per_extension_estimated_bytes: Vec<(&'static str, usize)>,
+    /// Number of errors in the report's source chain.
+    pub chain_depth: usize,
+}
+
+impl ReportAnalysis {
+    /// The largest of the rendered length, the JSON length and the total estimated size of the
+    /// attached extensions.
+    pub fn payload_len(&self) -> usize {
+        let extensions = self
+            .per_extension_estimated_bytes
+            .iter()
+            .map(|(_, bytes)| bytes)
+            .sum();
+
+        self.rendered_len
+            .max(self.json_len.unwrap_or(0))
+            .max(extensions)
+    }
+}
+
+/// Measures how large an error report is once rendered or exported, and which extensions
+/// contribute to it.
+///
+/// # Details
+///
+/// Output lengths are measured by formatting into counting writers, so the rendered report and
+/// its JSON are never held in memory. Extension sizes are best-effort estimates: the size of the
+/// value itself plus the heap buffer of common string and byte containers.
+///
+/// # Examples
+///
+/// ```rust
+/// use extension_eyre::{eyre::eyre, ExtensionExt};
+///
+/// pub struct Retry;
+///
+/// extension_eyre::install().unwrap();
+///
+/// let response_body = "x".repeat(64 * 1024);
+/// let report = eyre!("upstream returned 502")
+///     .extension(response_body)
+///     .extension(Retry);
+///
+/// let analysis = extension_eyre::analyze(&report);
+/// assert_eq!(analysis.extension_count, 2);
+/// assert_eq!(analysis.chain_depth, 1);
+/// assert!(analysis.rendered_len > 0);
+///
+/// let (largest, bytes) = analysis.per_extension_estimated_bytes[0];
+/// assert_eq!(largest, "alloc::string::String");
+/// assert!(bytes >= 64 * 1024);
+/// ```
+pub fn analyze(report: &Report) -> ReportAnalysis {
+    let mut rendered = ByteCounter::default();
+    let _ = write!(rendered, "{:?}", report);
+
+    let mut per_extension_estimated_bytes = report
+        .extensions_ref()
+        .into_iter()
+        .flat_map(|extensions| extensions.iter())
+        .map(|(type_name, value)| (type_name, estimated_size(value)))
+        .collect::<Vec<_>>();
+    per_extension_estimated_bytes.sort_by_key(|&(_, bytes)| std::cmp::Reverse(bytes));
+
+    ReportAnalysis {
+        rendered_len: rendered.0,
+        json_len: json_len(report),
+        extension_count: per_extension_estimated_bytes.len(),
+        per_extension_estimated_bytes,
+        chain_depth: Chain::of_report(report).count(),
+    }
+}
+
+#[cfg(feature = "serde")]
+fn json_len(report: &Report) -> Option<usize> {
+    use io::Write as _;
+
+    // written piece by piece so the message, chain and extensions are streamed into the counter
+    // instead of being collected first
+    let mut counter = ByteCounter::default();
+    counter.write_all(b"{\"message\":").ok()?;
+    serde_json::to_writer(&mut counter, &format_args!("{}", report)).ok()?;
+
+    counter.write_all(b",\"chain\":[").ok()?;
+    for (i, error) in Chain::of_report(report).enumerate() {
+        if i > 0 {
+            counter.write_all(b",").ok()?;
+        }
+        serde_json::to_writer(&mut counter, &format_args!("{}", error)).ok()?;
+    }
+
+    counter.write_all(b"],\"extensions\":").ok()?;
+    match report.extensions_ref() {
+        Some(extensions) => extensions.to_json_writer(&mut counter).ok()?,
+        None => counter.write_all(b"null").ok()?,
+    }
+    counter.write_all(b"}").ok()?;
+
+    Some(counter.0)
+}
+
+#[cfg(not(feature = "serde"))]
+fn json_len(_: &Report) -> Option<usize> {
+    None
+}
+
+/// Estimates the memory held by an extension value.
+fn estimated_size(value: &(dyn Any + Send + Sync)) -> usize {
+    let value = value as &dyn Any;
+    let heap = if let Some(s) = value.downcast_ref::<String>() {
+        s.capacity()
+    } else if let Some(s) = value.downcast_ref::<Cow<'static, str>>() {
+        match s {
+            Cow::Owned(s) => s.capacity(),
+            Cow::Borrowed(_) => 0,
+        }
+    } else if let Some(s) = value.downcast_ref::<Box<str>>() {
+        s.len()
+    } else if let Some(bytes) = value.downcast_ref::<Vec<u8>>() {
+        bytes.capacity()
+    } else {
+        0
+    };
+
+    std::mem::size_of_val(value) + heap
+}
+
+/// Writer that only counts the bytes written to it.
+#[derive(Default)]
+struct ByteCounter(usize);
+
+impl fmt::Write for ByteCounter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+impl io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,144 @@
+//! Converting [`anyhow::Error`]s into reports, see [`from_anyhow`]
+use std::error::Error;
+use std::fmt;
+
+use crate::extensions::ExtensionExt;
+use crate::eyre::Report;
+
+/// Converts an [`anyhow::Error`] into a [`Report`], keeping its source chain.
+///
+/// # Details
+///
+/// The report is created like any other, so the installed hook attaches its defaults and runs
+/// its extension hooks.
+///
+/// The outermost error of `error` becomes the report's error, and the errors below it become its
+/// sources with their own types: [`chain`](Report::chain) walks all of them and
+/// `downcast_ref` works on each. The outermost error itself can't be downcast once converted, so
+/// downcast it with [`anyhow::Error::downcast`] beforehand if needed.
+///
+/// ### Example
+///
+/// ```rust
+/// use anyhow::Context;
+/// use extension_eyre::{AnyhowExt, Extension};
+/// use std::io;
+///
+/// pub struct Retry(bool);
+///
+/// fn read_config() -> anyhow::Result<String> {
+///     std::fs::read_to_string("fake_file").context("Unable to read config")
+/// }
+///
+/// extension_eyre::install().unwrap();
+///
+/// let report = extension_eyre::from_anyhow(read_config().unwrap_err());
+/// let messages: Vec<_> = report.chain().map(ToString::to_string).collect();
+/// assert_eq!(messages[0], "Unable to read config");
+///
+/// let io_error = report.chain().find_map(|error| error.downcast_ref::<io::Error>());
+/// assert_eq!(io_error.unwrap().kind(), io::ErrorKind::NotFound);
+///
+/// let report = read_config().extension(Retry(true)).unwrap_err();
+/// assert!(matches!(report.extension_ref(), Some(Retry(true))));
+/// ```
+#[track_caller]
+pub fn from_anyhow(error: anyhow::Error) -> Report {
+    Report::new(AnyhowError(error))
+}
+
+/// Attaches extensions to [`anyhow::Error`]s, converting them into reports with [`from_anyhow`].
+///
+/// The methods behave like their [`ExtensionExt`] counterparts.
+///
+/// This trait is implemented for `anyhow::Error` and `Result<T, anyhow::Error>`.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::{AnyhowExt, Extension};
+///
+/// pub struct Retry(bool);
+///
+/// extension_eyre::install().unwrap();
+///
+/// let report = anyhow::anyhow!("connection reset").extension(Retry(true));
+/// assert_eq!(report.to_string(), "connection reset");
+/// assert!(matches!(report.extension_ref(), Some(Retry(true))));
+/// ```
+pub trait AnyhowExt: sealed::Sealed {
+    #[allow(missing_docs)]
+    type Return;
+
+    /// Converts the error into a report and attaches `extension` to it.
+    ///
+    /// See [`ExtensionExt::extension`].
+    fn extension<T: Send + Sync + 'static>(self, extension: T) -> Self::Return;
+
+    /// Converts the error into a report and attaches the value computed by `f` to it.
+    ///
+    /// See [`ExtensionExt::with_extension`].
+    fn with_extension<T: Send + Sync + 'static, F: FnOnce() -> T>(self, f: F) -> Self::Return;
+}
+
+impl AnyhowExt for anyhow::Error {
+    type Return = Report;
+
+    #[track_caller]
+    fn extension<T: Send + Sync + 'static>(self, extension: T) -> Self::Return {
+        from_anyhow(self).extension(extension)
+    }
+
+    #[track_caller]
+    fn with_extension<T: Send + Sync + 'static, F: FnOnce() -> T>(self, f: F) -> Self::Return {
+        from_anyhow(self).with_extension(f)
+    }
+}
+
+impl<V> AnyhowExt for Result<V, anyhow::Error> {
+    type Return = Result<V, Report>;
+
+    #[track_caller]
+    fn extension<T: Send + Sync + 'static>(self, extension: T) -> Self::Return {
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => Err(error.extension(extension)),
+        }
+    }
+
+    #[track_caller]
+    fn with_extension<T: Send + Sync + 'static, F: FnOnce() -> T>(self, f: F) -> Self::Return {
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => Err(error.with_extension(f)),
+        }
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for anyhow::Error {}
+    impl<V> Sealed for Result<V, anyhow::Error> {}
+}
+
+// Stands for the outermost error of an `anyhow::Error`, handing out the ones below it as sources.
+struct AnyhowError(anyhow::Error);
+
+impl fmt::Debug for AnyhowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for AnyhowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl Error for AnyhowError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.0.source()
+    }
+}
@@ -0,0 +1,1095 @@
+//! Extension types with a meaning to this crate
+//!
+//! Any type can be attached to a report, but the types here are understood by the crate itself,
+//! e.g. when turning a report into a message for end users with [`user_message`].
+use std::borrow::Cow;
+use std::fmt::{self, Write as _};
+use std::ops::BitOr;
+use std::panic::Location;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::extensions::Extension;
+use crate::eyre::Report;
+
+/// A message that is safe to show to the end users of an application.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::builtin::{self, UserMessage};
+/// use extension_eyre::{eyre::eyre, ExtensionExt};
+///
+/// extension_eyre::install().unwrap();
+///
+/// let report = eyre!("connection to 10.0.0.3:5432 refused")
+///     .extension(UserMessage("The service is unavailable, try again later".into()));
+///
+/// assert_eq!(
+///     builtin::user_message(&report).as_deref(),
+///     Some("The service is unavailable, try again later")
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserMessage(pub Cow<'static, str>);
+
+/// The exit code the process should end with when the error reaches `main`.
+///
+/// Used by [`run`](crate::run), [`MainResult`](crate::MainResult) and [`Extension::exit_code`].
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::builtin::ExitCode;
+/// use extension_eyre::{eyre::eyre, Extension, ExtensionExt};
+///
+/// extension_eyre::install().unwrap();
+///
+/// let report = eyre!("config file missing").extension(ExitCode(78));
+/// assert_eq!(report.exit_code(), 78);
+///
+/// // reports without an exit code fail with 1
+/// assert_eq!(eyre!("unexpected failure").exit_code(), 1);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ExitCode(pub u8);
+
+/// How severe the error of a report is.
+///
+/// Reports carrying a severity start with a header naming it, styled after the severity: yellow
+/// for warnings, red for errors and bold bright red for fatal errors by default, configurable with
+/// [`HookBuilder::severity_style`](crate::config::HookBuilder::severity_style). Reports without
+/// one are rendered as before.
+///
+/// ### Example
+///
+/// ```rust
+/// # #[cfg(feature = "color-eyre")] {
+/// use extension_eyre::builtin::Severity;
+/// use extension_eyre::{eyre::eyre, ExtensionExt};
+///
+/// extension_eyre::install().unwrap();
+///
+/// let bar = |len| "━".repeat(len);
+///
+/// let report = eyre!("disk almost full").extension(Severity::Warning);
+/// let header = format!("\u{1b}[33m  {} WARNING {}\u{1b}[0m\n", bar(35), bar(36));
+/// assert!(format!("{:?}", report).starts_with(&header));
+///
+/// let report = eyre!("disk full").extension(Severity::Fatal);
+/// let header = format!("\u{1b}[91;1m  {} FATAL {}\u{1b}[0m\n", bar(36), bar(37));
+/// assert!(format!("{:?}", report).starts_with(&header));
+///
+/// // no header without a severity
+/// assert!(format!("{:?}", eyre!("disk full")).trim_start().starts_with("0: "));
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Severity {
+    /// Something went wrong, but the program can go on.
+    Warning,
+    /// An operation failed.
+    Error,
+    /// The program can't go on.
+    Fatal,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Warning => "Warning",
+            Severity::Error => "Error",
+            Severity::Fatal => "Fatal",
+        })
+    }
+}
+
+/// A documented code identifying the kind of error, like `E0123`.
+///
+/// Shown as an `Error code:` line in the report, followed by a link to its documentation when a
+/// base URL is set with
+/// [`HookBuilder::error_code_base_url`](crate::config::HookBuilder::error_code_base_url). Read
+/// back with [`Extension::error_code`].
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::builtin::ErrorCode;
+/// use extension_eyre::{eyre::eyre, ExtensionExt};
+///
+/// extension_eyre::install().unwrap();
+///
+/// let report = eyre!("unknown flag --colour").extension(ErrorCode("E0002"));
+/// assert!(format!("{:?}", report).contains("Error code: E0002\n"));
+///
+/// // the code attached last is shown
+/// let report = report.extension(ErrorCode("E0003"));
+/// let output = format!("{:?}", report);
+/// assert!(output.contains("Error code: E0003"));
+/// assert!(!output.contains("E0002"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ErrorCode(pub &'static str);
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+/// A unique id for a report, to correlate what users see with the logs.
+///
+/// When enabled with
+/// [`HookBuilder::generate_report_ids`](crate::config::HookBuilder::generate_report_ids), the
+/// hook generates one for every report, unless one was already attached while creating the
+/// report, e.g. by a [`scope`](crate::scope) carrying the id of an upstream request. It is shown
+/// at the top of the report's `Debug` output.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::builtin::ReportId;
+/// use extension_eyre::{eyre::eyre, extensions::Extensions, Extension};
+/// use uuid::Uuid;
+///
+/// extension_eyre::config::HookBuilder::default()
+///     .generate_report_ids(true)
+///     .install()
+///     .unwrap();
+///
+/// let report = eyre!("connection reset");
+/// let id = report.extension_ref::<ReportId>().unwrap().0;
+///
+/// // the id stays the same however the report is shown
+/// assert!(format!("{:?}", report).contains(&format!("Report id: {}", id)));
+/// assert!(format!("{:#?}", report).contains("connection reset"));
+/// assert!(format!("{:?}", report).contains(&format!("Report id: {}", id)));
+/// assert_eq!(report.extension_ref(), Some(&ReportId(id)));
+///
+/// // an id attached by a scope is kept
+/// let upstream = Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+/// let mut request = Extensions::new();
+/// request.insert_cloneable(ReportId(upstream));
+/// let _scope = extension_eyre::scope(request);
+///
+/// let report = eyre!("connection reset");
+/// assert_eq!(report.extension_ref(), Some(&ReportId(upstream)));
+/// ```
+#[cfg(feature = "uuid")]
+#[cfg_attr(docsrs, doc(cfg(feature = "uuid")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ReportId(pub uuid::Uuid);
+
+#[cfg(feature = "uuid")]
+impl fmt::Display for ReportId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+/// The thread, and with the `tokio` feature the task, a report was created on.
+///
+/// Captured by the hook when enabled with
+/// [`HookBuilder::capture_thread_info`](crate::config::HookBuilder::capture_thread_info), and
+/// shown as a `Thread:` line in the report.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::builtin::ThreadInfo;
+/// use extension_eyre::{eyre::eyre, Extension};
+///
+/// extension_eyre::config::HookBuilder::default()
+///     .capture_thread_info(true)
+///     .install()
+///     .unwrap();
+///
+/// let worker = std::thread::Builder::new().name("worker-3".into());
+/// let report = worker
+///     .spawn(|| eyre!("job queue closed"))
+///     .unwrap()
+///     .join()
+///     .unwrap();
+///
+/// let thread = report.extension_ref::<ThreadInfo>().unwrap();
+/// assert_eq!(thread.name.as_deref(), Some("worker-3"));
+///
+/// let line = format!("Thread: worker-3 ({})", thread.id);
+/// assert!(format!("{:?}", report).contains(&line));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ThreadInfo {
+    /// The name of the thread, if it has one.
+    pub name: Option<String>,
+    /// The id of the thread, as formatted by its `Debug` impl, like `ThreadId(7)`.
+    pub id: String,
+    /// The id of the tokio task, if the report was created inside one.
+    #[cfg(feature = "tokio")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+    pub task_id: Option<tokio::task::Id>,
+}
+
+impl ThreadInfo {
+    /// Returns the info of the current thread and task.
+    pub fn current() -> Self {
+        let thread = std::thread::current();
+
+        ThreadInfo {
+            name: thread.name().map(ToOwned::to_owned),
+            id: format!("{:?}", thread.id()),
+            #[cfg(feature = "tokio")]
+            task_id: tokio::task::try_id(),
+        }
+    }
+}
+
+impl fmt::Display for ThreadInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self.name.as_deref().unwrap_or("<unnamed>");
+        write!(f, "{} ({})", name, self.id)?;
+
+        #[cfg(feature = "tokio")]
+        if let Some(task_id) = self.task_id {
+            write!(f, ", task {}", task_id)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The backtrace of where a report was created, for code that handles it.
+///
+/// Captured by the hook when enabled with
+/// [`HookBuilder::capture_backtrace_extension`](crate::config::HookBuilder::capture_backtrace_extension),
+/// and read with [`Extension::captured_backtrace`](crate::Extension::captured_backtrace). It
+/// doesn't change how the report renders its backtrace.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::{eyre::eyre, eyre::Report, Extension};
+///
+/// extension_eyre::config::HookBuilder::default()
+///     .capture_backtrace_extension(true)
+///     .force_backtrace_extension(true)
+///     .install()
+///     .unwrap();
+///
+/// fn fetch_prices() -> Report {
+///     eyre!("connection reset")
+/// }
+///
+/// let report = fetch_prices();
+/// let backtrace = report.captured_backtrace().unwrap().to_string();
+///
+/// // the frames are only named when debug symbols are available
+/// if backtrace.contains("rust_out") {
+///     assert!(backtrace.contains("fetch_prices"));
+/// }
+/// ```
+#[derive(Debug)]
+pub struct CapturedBacktrace(pub std::backtrace::Backtrace);
+
+impl CapturedBacktrace {
+    /// Captures the backtrace of the current thread, or returns `None` if it is disabled.
+    ///
+    /// Unless `force` is set, capturing is enabled by the `RUST_LIB_BACKTRACE` and
+    /// `RUST_BACKTRACE` environment variables, like for [`Backtrace::capture`].
+    ///
+    /// [`Backtrace::capture`]: std::backtrace::Backtrace::capture
+    pub(crate) fn capture(force: bool) -> Option<Self> {
+        let backtrace = if force {
+            std::backtrace::Backtrace::force_capture()
+        } else {
+            std::backtrace::Backtrace::capture()
+        };
+
+        match backtrace.status() {
+            std::backtrace::BacktraceStatus::Captured => Some(CapturedBacktrace(backtrace)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CapturedBacktrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+/// The values of chosen environment variables when a report was created.
+///
+/// Captured by the hook for the variables named with
+/// [`HookBuilder::capture_env_vars`](crate::config::HookBuilder::capture_env_vars), in that
+/// order, with `None` for variables that weren't set or weren't valid unicode. Reports show them
+/// on an `Environment:` line, with the values of the variables matching
+/// [`HookBuilder::redact_env_vars`](crate::config::HookBuilder::redact_env_vars) masked. The
+/// values held here aren't masked.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::builtin::CapturedEnv;
+/// use extension_eyre::{eyre::eyre, Extension};
+///
+/// std::env::set_var("DEPLOY_ENV", "staging");
+/// std::env::set_var("DEPLOY_TOKEN", "hunter2");
+/// std::env::remove_var("REGION");
+///
+/// extension_eyre::config::HookBuilder::default()
+///     .capture_env_vars(&["DEPLOY_ENV", "REGION", "DEPLOY_TOKEN"])
+///     .redact_env_vars(&["*_TOKEN"])
+///     .install()
+///     .unwrap();
+///
+/// let report = eyre!("deployment failed");
+/// std::env::set_var("DEPLOY_ENV", "production");
+///
+/// let env = report.extension_ref::<CapturedEnv>().unwrap();
+/// assert_eq!(env.get("DEPLOY_ENV"), Some("staging"));
+/// assert_eq!(env.get("REGION"), None);
+/// assert_eq!(env.get("DEPLOY_TOKEN"), Some("hunter2"));
+///
+/// let rendered = format!("{:?}", report);
+/// assert!(rendered
+///     .contains("Environment: DEPLOY_ENV=staging, REGION=<unset>, DEPLOY_TOKEN=<redacted>"));
+/// assert!(!rendered.contains("hunter2"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CapturedEnv(pub Vec<(String, Option<String>)>);
+
+impl CapturedEnv {
+    /// Returns the current values of the variables named `names`.
+    pub fn capture<I>(names: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let vars = names.into_iter().map(|name| {
+            let name = name.as_ref();
+            (name.to_owned(), std::env::var(name).ok())
+        });
+
+        CapturedEnv(vars.collect())
+    }
+
+    /// The captured value of the variable `name`, if it was set.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(var, _)| var == name)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    /// Renders the variables like `Display`, masking the values of those matching `patterns`.
+    pub(crate) fn masked<'a>(&'a self, patterns: &'a [String]) -> MaskedEnv<'a> {
+        MaskedEnv {
+            env: self,
+            patterns,
+        }
+    }
+}
+
+impl fmt::Display for CapturedEnv {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.masked(&[]), f)
+    }
+}
+
+pub(crate) struct MaskedEnv<'a> {
+    env: &'a CapturedEnv,
+    patterns: &'a [String],
+}
+
+impl fmt::Display for MaskedEnv<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut separator = "";
+        for (name, value) in &self.env.0 {
+            write!(f, "{}{}=", separator, name)?;
+            separator = ", ";
+
+            let masked = || {
+                self.patterns
+                    .iter()
+                    .any(|pattern| glob_match(pattern, name))
+            };
+            match value {
+                None => f.write_str("<unset>")?,
+                Some(_) if masked() => f.write_str("<redacted>")?,
+                Some(value) => f.write_str(value)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Whether `name` matches `pattern`, where `*` stands for any run of characters.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            let name = match name.strip_prefix(prefix) {
+                Some(name) => name,
+                None => return false,
+            };
+            (0..=name.len())
+                .filter(|&at| name.is_char_boundary(at))
+                .any(|at| glob_match(rest, &name[at..]))
+        }
+    }
+}
+
+/// The process a report was created in: its id, executable and arguments.
+///
+/// Captured once by the hook when enabled with
+/// [`HookBuilder::capture_process_info`](crate::config::HookBuilder::capture_process_info), and
+/// attached to every report. Reports show it as a `Process:` line. Arguments matching
+/// [`HookBuilder::redact_process_args`](crate::config::HookBuilder::redact_process_args) are
+/// replaced with `<redacted>` when captured.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::builtin::ProcessInfo;
+/// use extension_eyre::{eyre::eyre, Extension};
+///
+/// extension_eyre::config::HookBuilder::default()
+///     .capture_process_info(true)
+///     .redact_process_args(|arg| arg.starts_with("--token="))
+///     .install()
+///     .unwrap();
+///
+/// let report = eyre!("job queue closed");
+///
+/// let process = report.extension_ref::<ProcessInfo>().unwrap();
+/// assert_eq!(process.pid, std::process::id());
+/// assert_eq!(process.exe, std::env::current_exe().ok());
+///
+/// let line = format!("Process: {}", process);
+/// assert!(line.ends_with(&format!("(pid {})", std::process::id())));
+/// assert!(format!("{:?}", report).contains(&line));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProcessInfo {
+    /// The id of the process.
+    pub pid: u32,
+    /// The path of the executable, if it could be found.
+    pub exe: Option<PathBuf>,
+    /// The arguments the process was started with, without the program name. Arguments that
+    /// aren't valid unicode are converted lossily.
+    pub args: Vec<String>,
+}
+
+impl ProcessInfo {
+    /// Returns the info of the current process.
+    pub fn current() -> Self {
+        ProcessInfo {
+            pid: std::process::id(),
+            exe: std::env::current_exe().ok(),
+            args: std::env::args_os()
+                .skip(1)
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for ProcessInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.exe {
+            Some(exe) => write!(f, "{}", exe.display())?,
+            None => f.write_str("<unknown>")?,
+        }
+        for arg in &self.args {
+            write!(f, " {}", arg)?;
+        }
+
+        write!(f, " (pid {})", self.pid)
+    }
+}
+
+/// The name, target and fields of the innermost span a report was created in.
+///
+/// Captured by the hook when enabled with
+/// [`HookBuilder::capture_span_context`](crate::config::HookBuilder::capture_span_context). The
+/// span is read from the current subscriber, which needs a
+/// [`tracing_error::ErrorLayer`]; without one, or outside of any span, nothing is captured.
+///
+/// Field values are the ones recorded by the layer's formatter, parsed back into name/value
+/// pairs. String values are unquoted; other values are kept as formatted, usually with `Debug`.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::builtin::SpanContext;
+/// use extension_eyre::{eyre::eyre, eyre::Report, Extension};
+/// use tracing_error::ErrorLayer;
+/// use tracing_subscriber::prelude::*;
+///
+/// #[tracing::instrument]
+/// fn load_user(user_id: u64, name: &str) -> Report {
+///     eyre!("user not found")
+/// }
+///
+/// tracing_subscriber::registry().with(ErrorLayer::default()).init();
+///
+/// extension_eyre::config::HookBuilder::default()
+///     .capture_span_context(true)
+///     .install()
+///     .unwrap();
+///
+/// let report = load_user(42, "bob smith");
+/// let span = report.extension_ref::<SpanContext>().unwrap();
+///
+/// assert_eq!(span.span_name, "load_user");
+/// assert_eq!(span.target, module_path!());
+/// assert_eq!(span.field("user_id"), Some("42"));
+/// assert_eq!(span.field("name"), Some("bob smith"));
+///
+/// // outside of any span
+/// assert_eq!(eyre!("no span").extension_ref::<SpanContext>(), None);
+/// ```
+#[cfg(feature = "capture-spantrace")]
+#[cfg_attr(docsrs, doc(cfg(feature = "capture-spantrace")))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SpanContext {
+    /// The name of the span.
+    pub span_name: String,
+    /// The target of the span, usually the module it was created in.
+    pub target: String,
+    /// The fields recorded on the span, in order.
+    pub fields: Vec<(String, String)>,
+}
+
+#[cfg(feature = "capture-spantrace")]
+impl SpanContext {
+    /// Returns the context of the innermost current span, if any.
+    pub fn current() -> Option<Self> {
+        let mut context = None;
+
+        tracing_error::SpanTrace::capture().with_spans(|metadata, fields| {
+            context = Some(SpanContext {
+                span_name: metadata.name().to_owned(),
+                target: metadata.target().to_owned(),
+                fields: parse_fields(fields),
+            });
+            false
+        });
+
+        context
+    }
+
+    /// Returns the value of the field `name`, if it was recorded.
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+// Parses fields formatted like `a=1 b="two words" c=Some(3)`.
+#[cfg(feature = "capture-spantrace")]
+fn parse_fields(formatted: &str) -> Vec<(String, String)> {
+    let mut fields = Vec::new();
+    let mut rest = formatted.trim();
+
+    while let Some(eq) = rest.find('=') {
+        let name = rest[..eq].trim().to_owned();
+        let value = &rest[eq + 1..];
+
+        let (value, next) = match value.strip_prefix('"') {
+            Some(quoted) => {
+                let (value, len) = unquote(quoted);
+                (value, &quoted[len..])
+            }
+            None => {
+                let end = next_field(value).unwrap_or(value.len());
+                (value[..end].to_owned(), &value[end..])
+            }
+        };
+
+        fields.push((name, value));
+        rest = next.trim_start();
+    }
+
+    fields
+}
+
+// Returns the string up to the closing quote, unescaped, and the length consumed.
+#[cfg(feature = "capture-spantrace")]
+fn unquote(quoted: &str) -> (String, usize) {
+    let mut value = String::new();
+    let mut chars = quoted.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return (value, i + 1),
+            '\\' => match chars.next() {
+                Some((_, 'n')) => value.push('\n'),
+                Some((_, 't')) => value.push('\t'),
+                Some((_, 'r')) => value.push('\r'),
+                Some((_, c)) => value.push(c),
+                None => break,
+            },
+            c => value.push(c),
+        }
+    }
+
+    (value, quoted.len())
+}
+
+// Returns where the next `name=` begins, for values that may contain spaces like `Debug` output.
+#[cfg(feature = "capture-spantrace")]
+fn next_field(value: &str) -> Option<usize> {
+    value.match_indices(' ').map(|(i, _)| i).find(|&i| {
+        let rest = &value[i + 1..];
+        let name_len = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+            .unwrap_or(rest.len());
+        name_len > 0 && rest[name_len..].starts_with('=')
+    })
+}
+
+/// The time a report was created at, in UTC.
+///
+/// Captured by the hook when enabled with
+/// [`HookBuilder::capture_timestamps`](crate::config::HookBuilder::capture_timestamps), and shown
+/// in RFC 3339 format as a `Created at:` line in the report. Read back with
+/// [`Extension::created_at`].
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::builtin::CreatedAt;
+/// use extension_eyre::{eyre::eyre, Extension};
+/// use std::time::Duration;
+///
+/// extension_eyre::config::HookBuilder::default()
+///     .capture_timestamps(true)
+///     .install()
+///     .unwrap();
+///
+/// let before = time::OffsetDateTime::now_utc();
+/// let report = eyre!("batch job failed");
+/// std::thread::sleep(Duration::from_millis(50));
+///
+/// // the time the report was created, not the time it was shown
+/// let created_at = report.created_at().unwrap();
+/// assert!(created_at >= before);
+/// assert!(time::OffsetDateTime::now_utc() - created_at >= Duration::from_millis(50));
+///
+/// let rendered = CreatedAt(created_at).to_string();
+/// assert!(format!("{:?}", report).contains(&format!("Created at: {}", rendered)));
+/// ```
+#[cfg(feature = "time")]
+#[cfg_attr(docsrs, doc(cfg(feature = "time")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CreatedAt(pub time::OffsetDateTime);
+
+#[cfg(feature = "time")]
+impl fmt::Display for CreatedAt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rendered = self
+            .0
+            .format(&time::format_description::well_known::Rfc3339)
+            .map_err(|_| fmt::Error)?;
+        f.write_str(&rendered)
+    }
+}
+
+/// Where a report was created.
+///
+/// Recorded for every report with the `track-caller` feature, and read with
+/// [`extension_ref`](crate::Extension::extension_ref). It isn't stored in the report's
+/// [`Extensions`](crate::extensions::Extensions), so it isn't listed in the `Extensions:` section
+/// either. Override it with [`ExtensionExt::location`](crate::ExtensionExt::location).
+///
+/// ### Example
+///
+/// ```rust
+/// # #[cfg(feature = "color-eyre")] {
+/// use extension_eyre::builtin::ReportLocation;
+/// use extension_eyre::{eyre::eyre, Extension};
+///
+/// extension_eyre::install().unwrap();
+///
+/// let line = line!() + 1;
+/// let report = eyre!("connection reset");
+///
+/// let ReportLocation(location) = report.extension_ref().copied().unwrap();
+/// assert_eq!(location.file(), file!());
+/// assert_eq!(location.line(), line);
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ReportLocation(pub &'static Location<'static>);
+
+impl fmt::Display for ReportLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.0, f)
+    }
+}
+
+/// The HTTP status code to respond with when the error reaches a web framework.
+///
+/// Attached with [`ExtensionExt::status`](crate::ExtensionExt::status). With the `axum` feature,
+/// [`WebReport`](crate::web::WebReport) responds with it, and with 500 when there is none.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HttpStatus(pub u16);
+
+/// The gRPC status code to respond with when the error reaches a tonic service.
+///
+/// Used by [`grpc::report_to_status`](crate::grpc::report_to_status), which falls back to
+/// `Code::Internal` when there is none.
+#[cfg(feature = "tonic")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tonic")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GrpcCode(pub tonic::Code);
+
+/// Marks a report whose `Debug` output should include a backtrace and span trace, even when
+/// they are disabled for other reports.
+///
+/// The traces are captured when the report is created, for every report, unless disabled with
+/// [`HookBuilder::capture_verbose_traces`](crate::config::HookBuilder::capture_verbose_traces).
+/// [`Quiet`] wins when a report carries both.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::builtin::Verbose;
+/// use extension_eyre::{eyre::eyre, ExtensionExt};
+///
+/// // backtraces are off for reports
+/// std::env::set_var("RUST_LIB_BACKTRACE", "0");
+///
+/// extension_eyre::install().unwrap();
+///
+/// let report = eyre!("connection reset");
+/// assert!(!format!("{:?}", report).contains(" BACKTRACE "));
+///
+/// let report = eyre!("ledger out of balance").extension(Verbose);
+/// let output = format!("{:?}", report);
+/// assert!(output.contains("ledger out of balance"));
+/// assert!(output.contains(" BACKTRACE "));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Verbose;
+
+/// Marks a report whose `Debug` output should leave out the backtrace and span trace, even when
+/// they are enabled for other reports.
+///
+/// Works like [`Suppress`] with the span trace, the backtrace and the environment hints.
+///
+/// ### Example
+///
+/// ```rust
+/// # #[cfg(feature = "color-eyre")] {
+/// use extension_eyre::builtin::Quiet;
+/// use extension_eyre::{eyre::eyre, ExtensionExt};
+///
+/// // backtraces are on for reports
+/// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+///
+/// extension_eyre::install().unwrap();
+///
+/// let report = eyre!("connection reset");
+/// assert!(format!("{:?}", report).contains(" BACKTRACE "));
+///
+/// let report = eyre!("user not found").extension(Quiet);
+/// let output = format!("{:?}", report);
+/// assert!(output.contains("user not found"));
+/// assert!(output.contains("Location:"));
+/// assert!(!output.contains(" BACKTRACE "));
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Quiet;
+
+/// A set of the sections color-eyre adds to a report's `Debug` output.
+///
+/// Combine kinds with `|`. Used by [`Suppress`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct SectionKind(u8);
+
+impl SectionKind {
+    /// The hints on the environment variables that control the output.
+    pub const ENV: SectionKind = SectionKind(1);
+    /// The location the report was created at.
+    pub const LOCATION: SectionKind = SectionKind(1 << 1);
+    /// The span trace.
+    pub const SPANTRACE: SectionKind = SectionKind(1 << 2);
+    /// The backtrace.
+    pub const BACKTRACE: SectionKind = SectionKind(1 << 3);
+
+    /// The empty set.
+    pub const fn empty() -> SectionKind {
+        SectionKind(0)
+    }
+
+    /// Whether the set holds no kinds.
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Whether the set holds every kind in `other`.
+    pub const fn contains(self, other: SectionKind) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for SectionKind {
+    type Output = SectionKind;
+
+    fn bitor(self, other: SectionKind) -> SectionKind {
+        SectionKind(self.0 | other.0)
+    }
+}
+
+/// Leaves the given sections out of the report's `Debug` output.
+///
+/// Unlike [`HookBuilder::display_env_section`](crate::config::HookBuilder::display_env_section),
+/// which applies to every report, this only applies to the report it is attached to. The report
+/// is then rendered by extension-eyre section by section instead of by color-eyre: the error chain
+/// is always shown, and traces aren't filtered.
+///
+/// ### Example
+///
+/// ```rust
+/// # #[cfg(feature = "color-eyre")] {
+/// use extension_eyre::builtin::Suppress;
+/// use extension_eyre::{eyre::eyre, ExtensionExt};
+///
+/// std::env::set_var("RUST_LIB_BACKTRACE", "0");
+///
+/// extension_eyre::install().unwrap();
+///
+/// let unexpected = format!("{:?}", eyre!("config.tmol not found"));
+/// let expected = format!(
+///     "{:?}",
+///     eyre!("config.tmol not found").extension(Suppress::ENV | Suppress::LOCATION)
+/// );
+///
+/// assert!(unexpected.contains("config.tmol not found"));
+/// assert!(unexpected.contains("Location:"));
+/// assert!(unexpected.contains("Backtrace omitted"));
+///
+/// assert!(expected.contains("config.tmol not found"));
+/// assert!(!expected.contains("Location:"));
+/// assert!(!expected.contains("Backtrace omitted"));
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Suppress(pub SectionKind);
+
+impl Suppress {
+    /// Suppresses the hints on the environment variables that control the output.
+    pub const ENV: Suppress = Suppress(SectionKind::ENV);
+    /// Suppresses the location the report was created at.
+    pub const LOCATION: Suppress = Suppress(SectionKind::LOCATION);
+    /// Suppresses the span trace.
+    pub const SPANTRACE: Suppress = Suppress(SectionKind::SPANTRACE);
+    /// Suppresses the backtrace.
+    pub const BACKTRACE: Suppress = Suppress(SectionKind::BACKTRACE);
+}
+
+impl BitOr for Suppress {
+    type Output = Suppress;
+
+    fn bitor(self, other: Suppress) -> Suppress {
+        Suppress(self.0 | other.0)
+    }
+}
+
+/// Marks the error as transient, to be retried by [`retry`](crate::retry) with the default
+/// [`RetryPolicy`].
+///
+/// A `RetryPolicy` attached to the same report takes precedence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Retry;
+
+/// How [`retry`](crate::retry) should retry the operation that failed with the error.
+///
+/// `max_attempts` counts the first attempt, so a policy with `max_attempts: 1` never retries.
+/// The default policy makes 3 attempts, 100 milliseconds apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RetryPolicy {
+    /// The number of attempts to make before giving up.
+    pub max_attempts: u32,
+    /// How long to wait before the next attempt.
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            backoff: Duration::from_millis(100),
+        }
+    }
+}
+
+/// The number of attempts [`retry`](crate::retry) made before returning the error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Attempts(pub u32);
+
+/// A hint on how to resolve the error, displayed as a `Suggestion:` line in the report.
+///
+/// Attached by [`Section::suggestion`](crate::Section::suggestion); a report can carry several,
+/// which are read back with [`Extensions::get_all`](crate::extensions::Extensions::get_all).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion(pub Cow<'static, str>);
+
+impl fmt::Display for Suggestion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A message for end users with `{name}` placeholders, filled in when the message is shown.
+///
+/// # Details
+///
+/// Placeholders are resolved against the report's [metadata](crate::ExtensionExt::meta) and,
+/// with the `serde` feature enabled, the string pairs exported from its extensions (see
+/// [`Extensions::for_each_pair`](crate::extensions::Extensions::for_each_pair)). Metadata wins
+/// when both have a value for a name. A placeholder without a value is rendered as `{name?}`.
+///
+/// `{{` and `}}` stand for literal braces. A `{` that doesn't start a placeholder is kept as is.
+///
+/// A template takes precedence over a [`UserMessage`] attached to the same report.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::builtin::{self, UserMessage};
+/// use extension_eyre::{eyre::eyre, ExtensionExt};
+///
+/// extension_eyre::install().unwrap();
+///
+/// let report = eyre!("card processor returned 402")
+///     .extension(UserMessage("Payment failed".into()))
+///     .message_template("Payment {payment_id} could not be processed, try again in {retry_after}")
+///     .meta("payment_id", "pay_123")
+///     .meta("retry_after", "5 minutes");
+///
+/// assert_eq!(
+///     builtin::user_message(&report).as_deref(),
+///     Some("Payment pay_123 could not be processed, try again in 5 minutes")
+/// );
+///
+/// // placeholders without a value are marked instead of failing the whole message
+/// let report = eyre!("card processor returned 402")
+///     .message_template("Payment {payment_id} could not be processed, try again in {retry_after}")
+///     .meta("payment_id", "pay_123");
+///
+/// assert_eq!(
+///     builtin::user_message(&report).as_deref(),
+///     Some("Payment pay_123 could not be processed, try again in {retry_after?}")
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageTemplate(pub Cow<'static, str>);
+
+impl MessageTemplate {
+    /// Renders the template with the values attached to `report`.
+    pub fn render(&self, report: &Report) -> String {
+        #[cfg(feature = "serde")]
+        let pairs = report
+            .extensions_ref()
+            .map(|extensions| extensions.to_string_pairs())
+            .unwrap_or_default();
+
+        self.render_with(|name| {
+            if let Some((_, value)) = report.meta_iter().find(|(key, _)| *key == name) {
+                return Some(value.to_string());
+            }
+
+            // the most recently attached value wins
+            #[cfg(feature = "serde")]
+            if let Some((_, value)) = pairs.iter().rev().find(|(key, _)| key == name) {
+                return Some(value.clone());
+            }
+
+            None
+        })
+    }
+
+    /// Renders the template, asking `lookup` for the value of each placeholder.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::builtin::MessageTemplate;
+    ///
+    /// let template = MessageTemplate("{{{name}}} is {state}, {unknown} and {{literal}}".into());
+    /// let rendered = template.render_with(|name| match name {
+    ///     "name" => Some("db".to_owned()),
+    ///     "state" => Some("down".to_owned()),
+    ///     _ => None,
+    /// });
+    ///
+    /// assert_eq!(rendered, "{db} is down, {unknown?} and {literal}");
+    ///
+    /// // stray braces are kept
+    /// let template = MessageTemplate("a } b { c".into());
+    /// assert_eq!(template.render_with(|_| None), "a } b { c");
+    /// ```
+    pub fn render_with<F>(&self, mut lookup: F) -> String
+    where
+        F: FnMut(&str) -> Option<String>,
+    {
+        let mut rendered = String::with_capacity(self.0.len());
+        let mut rest = &*self.0;
+
+        while let Some(brace) = rest.find(['{', '}']) {
+            rendered.push_str(&rest[..brace]);
+            let tail = &rest[brace..];
+
+            if tail.starts_with("{{") || tail.starts_with("}}") {
+                rendered.push_str(&tail[..1]);
+                rest = &tail[2..];
+                continue;
+            }
+
+            let placeholder = tail
+                .strip_prefix('{')
+                .and_then(|inner| Some(&inner[..inner.find(['{', '}'])?]))
+                .filter(|name| tail[1 + name.len()..].starts_with('}'));
+
+            match placeholder {
+                Some(name) => {
+                    match lookup(name) {
+                        Some(value) => rendered.push_str(&value),
+                        None => {
+                            let _ = write!(rendered, "{{{}?}}", name);
+                        }
+                    }
+                    rest = &tail[name.len() + 2..];
+                }
+                None => {
+                    rendered.push_str(&tail[..1]);
+                    rest = &tail[1..];
+                }
+            }
+        }
+
+        rendered.push_str(rest);
+        rendered
+    }
+}
+
+/// The message to show end users for `report`.
+///
+/// Renders the attached [`MessageTemplate`] if there is one, and falls back to the attached
+/// [`UserMessage`]. Returns `None` if neither is attached.
+pub fn user_message(report: &Report) -> Option<Cow<'_, str>> {
+    if let Some(template) = report.extension_ref::<MessageTemplate>() {
+        return Some(Cow::Owned(template.render(report)));
+    }
+
+    report
+        .extension_ref::<UserMessage>()
+        .map(|message| Cow::Borrowed(&*message.0))
+}
@@ -0,0 +1,298 @@
+//! Catalog of the extension types an application exports
+//!
+//! Types are described with [`declare_extension!`](crate::declare_extension) and added to the
+//! process-wide catalog with [`register`]. The catalog is the single source of truth for the key
+//! each serializable extension is exported under, and can be turned into a JSON schema with
+//! [`json_schema`] for downstream consumers of exported error JSON.
+//!
+//! ### Example
+//!
+//! ```rust
+//! use extension_eyre::{catalog, declare_extension};
+//! use serde::Serialize;
+//!
+//! #[derive(Serialize)]
+//! pub struct RequestId(String);
+//!
+//! declare_extension!(RequestId as "request_id", "Id of the request being handled", type = "string");
+//!
+//! catalog::register::<RequestId>();
+//!
+//! let schema = catalog::json_schema();
+//! assert_eq!(schema["properties"]["request_id"]["type"], "string");
+//! ```
+use std::any::TypeId;
+use std::io;
+use std::path::Path;
+use std::sync::RwLock;
+
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+static CATALOG: RwLock<Vec<CatalogEntry>> = RwLock::new(Vec::new());
+
+/// Description of an extension type, usually implemented through
+/// [`declare_extension!`](crate::declare_extension).
+pub trait Declared: Send + Sync + 'static {
+    /// Key the extension is exported under.
+    const KEY: &'static str;
+
+    /// Human readable description of the extension.
+    const DESCRIPTION: &'static str;
+
+    /// JSON type the extension serializes to when no richer schema is available.
+    const JSON_TYPE: &'static str = "object";
+}
+
+/// Implements [`catalog::Declared`](crate::catalog::Declared) for an extension type.
+///
+/// The key defaults to the type's name as written and can be overridden with `as "key"`. The
+/// JSON type used by [`catalog::json_schema`](crate::catalog::json_schema) defaults to
+/// `"object"` and can be overridden with a trailing `type = "..."`.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::catalog::Declared;
+/// use extension_eyre::declare_extension;
+///
+/// pub struct Retry(bool);
+/// pub struct RequestId(String);
+///
+/// declare_extension!(Retry, "Whether the operation may be retried", type = "boolean");
+/// declare_extension!(RequestId as "request_id", "Id of the request being handled");
+///
+/// assert_eq!(Retry::KEY, "Retry");
+/// assert_eq!(Retry::JSON_TYPE, "boolean");
+/// assert_eq!(RequestId::KEY, "request_id");
+/// assert_eq!(RequestId::JSON_TYPE, "object");
+/// ```
+#[macro_export]
+macro_rules! declare_extension {
+    ($ty:ty as $key:expr, $description:literal $(, type = $json_type:literal)? $(,)?) => {
+        impl $crate::catalog::Declared for $ty {
+            const KEY: &'static str = $key;
+            const DESCRIPTION: &'static str = $description;
+            $(const JSON_TYPE: &'static str = $json_type;)?
+        }
+    };
+    ($ty:ty, $description:literal $(, type = $json_type:literal)? $(,)?) => {
+        $crate::declare_extension!($ty as stringify!($ty), $description $(, type = $json_type)?);
+    };
+}
+
+/// A registered extension type.
+#[derive(Debug, Clone)]
+pub struct CatalogEntry {
+    type_id: TypeId,
+    type_name: &'static str,
+    key: &'static str,
+    description: &'static str,
+    schema: Value,
+}
+
+impl CatalogEntry {
+    /// The [`TypeId`] of the registered type.
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    /// The full Rust path of the registered type.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// The key the extension is exported under.
+    pub fn key(&self) -> &'static str {
+        self.key
+    }
+
+    /// The description given to [`declare_extension!`](crate::declare_extension).
+    pub fn description(&self) -> &'static str {
+        self.description
+    }
+}
+
+/// Adds a serializable extension type to the catalog.
+///
+/// Registering the same type again replaces its entry.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::{catalog, declare_extension};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// pub struct Retry(bool);
+///
+/// declare_extension!(Retry, "Whether the operation may be retried", type = "boolean");
+///
+/// catalog::register::<Retry>();
+/// catalog::register::<Retry>();
+///
+/// assert_eq!(catalog::entries().len(), 1);
+/// assert_eq!(catalog::entries()[0].key(), "Retry");
+/// ```
+pub fn register<T: Declared + Serialize>() {
+    insert(entry_for::<T>(json!({ "type": T::JSON_TYPE })));
+}
+
+/// Adds a serializable extension type to the catalog, describing its value with the schema
+/// derived by [`schemars`](https://docs.rs/schemars).
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::{catalog, declare_extension};
+/// use schemars::JsonSchema;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize, JsonSchema)]
+/// pub struct Attempt {
+///     number: u32,
+/// }
+///
+/// declare_extension!(Attempt, "The attempt that failed");
+///
+/// catalog::register_with_schema::<Attempt>();
+///
+/// let schema = catalog::json_schema();
+/// let attempt = &schema["properties"]["Attempt"];
+/// assert_eq!(attempt["description"], "The attempt that failed");
+/// assert_eq!(attempt["properties"]["number"]["type"], "integer");
+/// ```
+#[cfg(feature = "schemars")]
+#[cfg_attr(docsrs, doc(cfg(feature = "schemars")))]
+pub fn register_with_schema<T: Declared + Serialize + schemars::JsonSchema>() {
+    let generator = schemars::generate::SchemaSettings::draft07()
+        .with(|settings| settings.inline_subschemas = true)
+        .into_generator();
+    let mut schema = generator.into_root_schema_for::<T>().to_value();
+
+    if let Some(object) = schema.as_object_mut() {
+        object.remove("$schema");
+        object.remove("title");
+    }
+
+    insert(entry_for::<T>(schema));
+}
+
+/// Returns a snapshot of every registered extension type, in registration order.
+pub fn entries() -> Vec<CatalogEntry> {
+    CATALOG
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone()
+}
+
+/// Builds a draft-07 JSON schema describing the exported extensions object.
+///
+/// # Details
+///
+/// Every registered type becomes a property named after its key, carrying the description
+/// from [`declare_extension!`](crate::declare_extension) and a value schema. Extensions that
+/// aren't in the catalog are still exported under their Rust type name; the schema's
+/// `additionalProperties` documents that catch-all.
+///
+/// # Examples
+///
+/// ```rust
+/// use extension_eyre::{catalog, declare_extension};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// pub struct RequestId(String);
+///
+/// #[derive(Serialize)]
+/// pub struct Attempt {
+///     number: u32,
+/// }
+///
+/// declare_extension!(RequestId as "request_id", "Id of the request being handled", type = "string");
+/// declare_extension!(Attempt, "The attempt that failed");
+///
+/// catalog::register::<RequestId>();
+/// catalog::register::<Attempt>();
+///
+/// let schema = catalog::json_schema();
+/// assert_eq!(schema["$schema"], "http://json-schema.org/draft-07/schema#");
+/// assert_eq!(
+///     schema["properties"]["request_id"],
+///     serde_json::json!({ "description": "Id of the request being handled", "type": "string" })
+/// );
+/// assert_eq!(
+///     schema["properties"]["Attempt"],
+///     serde_json::json!({ "description": "The attempt that failed", "type": "object" })
+/// );
+/// assert!(schema["additionalProperties"]["description"]
+///     .as_str()
+///     .unwrap()
+///     .contains("Rust type name"));
+/// ```
+pub fn json_schema() -> Value {
+    let properties = entries()
+        .into_iter()
+        .map(|entry| (entry.key.to_owned(), entry.schema))
+        .collect::<Map<_, _>>();
+
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "Extensions",
+        "description": "Extensions attached to an exported error report",
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": {
+            "description": "Serializable extensions that aren't registered in the catalog, keyed by their Rust type name"
+        }
+    })
+}
+
+/// Writes the output of [`json_schema`] to `path`, pretty printed.
+///
+/// Meant to be called from build scripts or xtasks that publish the schema.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// extension_eyre::catalog::write_schema("target/error-extensions.schema.json").unwrap();
+/// ```
+pub fn write_schema<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    let schema = serde_json::to_vec_pretty(&json_schema())?;
+    std::fs::write(path, schema)
+}
+
+/// Returns the key the type with `type_id` is exported under, if it's registered.
+pub(crate) fn key_of(type_id: TypeId) -> Option<&'static str> {
+    CATALOG
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .iter()
+        .find(|entry| entry.type_id == type_id)
+        .map(|entry| entry.key)
+}
+
+fn entry_for<T: Declared>(mut schema: Value) -> CatalogEntry {
+    if let Some(object) = schema.as_object_mut() {
+        object.insert("description".to_owned(), T::DESCRIPTION.into());
+    }
+
+    CatalogEntry {
+        type_id: TypeId::of::<T>(),
+        type_name: std::any::type_name::<T>(),
+        key: T::KEY,
+        description: T::DESCRIPTION,
+        schema,
+    }
+}
+
+fn insert(entry: CatalogEntry) {
+    let mut catalog = CATALOG
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    match catalog.iter_mut().find(|e| e.type_id == entry.type_id) {
+        Some(existing) => *existing = entry,
+        None => catalog.push(entry),
+    }
+}
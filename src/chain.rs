@@ -0,0 +1,126 @@
+//! Bounded iteration over error source chains
+//!
+//! Every feature in this crate that walks `source()` goes through [`Chain`], which stops after a
+//! configurable number of errors and when an error is visited twice, so a self-referential
+//! `source()` can't hang report creation or rendering.
+use std::error::Error;
+use std::fmt;
+
+use crate::eyre::Report;
+
+/// Default for [`HookBuilder::max_chain_depth`](crate::config::HookBuilder::max_chain_depth).
+pub(crate) const DEFAULT_MAX_CHAIN_DEPTH: usize = 32;
+
+/// Iterator over an error and its sources, bounded in depth and guarded against cycles.
+pub(crate) struct Chain<'a> {
+    next: Option<&'a (dyn Error + 'static)>,
+    // Compared with their vtables: an error and its first field share an address, e.g. an error
+    // holding its source inline, without being the same error.
+    seen: Vec<*const dyn Error>,
+    max_depth: usize,
+    truncated: bool,
+}
+
+impl<'a> Chain<'a> {
+    pub(crate) fn new(head: &'a (dyn Error + 'static), max_depth: usize) -> Self {
+        Chain {
+            next: Some(head),
+            seen: Vec::new(),
+            max_depth: max_depth.max(1),
+            truncated: false,
+        }
+    }
+
+    /// Walks the chain of `report` with the depth configured on its handler.
+    pub(crate) fn of_report(report: &'a Report) -> Self {
+        let max_depth = report
+            .handler()
+            .downcast_ref::<crate::Handler>()
+            .map_or(DEFAULT_MAX_CHAIN_DEPTH, |handler| {
+                handler.config.max_chain_depth
+            });
+
+        Chain::new(report.as_ref(), max_depth)
+    }
+
+    /// Returns the depth at which the walk stopped early, if it did.
+    ///
+    /// Only meaningful once the iterator has returned `None`.
+    pub(crate) fn truncated_at(&self) -> Option<usize> {
+        if self.truncated {
+            Some(self.seen.len())
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> Iterator for Chain<'a> {
+    type Item = &'a (dyn Error + 'static);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let error = self.next.take()?;
+        let ptr = error as *const dyn Error;
+
+        if self.seen.len() >= self.max_depth || self.seen.contains(&ptr) {
+            self.truncated = true;
+            return None;
+        }
+
+        self.seen.push(ptr);
+        self.next = error.source();
+
+        Some(error)
+    }
+}
+
+/// Walks `head` and, if the walk had to stop early, returns an owned copy of the part that was
+/// visited along with the depth it stopped at.
+///
+/// The copy renders the same messages as the original errors but its last link has no source,
+/// which makes it safe to hand to code that walks `source()` without bounds.
+pub(crate) fn truncate(
+    head: &(dyn Error + 'static),
+    max_depth: usize,
+) -> Option<(TruncatedChain, usize)> {
+    // most chains are short, so only pay for the copy once the walk did stop early
+    let mut chain = Chain::new(head, max_depth);
+    chain.by_ref().for_each(drop);
+    let depth = chain.truncated_at()?;
+
+    let messages = Chain::new(head, max_depth)
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>();
+
+    let truncated = messages
+        .into_iter()
+        .rev()
+        .fold(None, |source, message| {
+            Some(TruncatedChain {
+                message,
+                source: source.map(Box::new),
+            })
+        })
+        .expect("a chain always contains its head");
+
+    Some((truncated, depth))
+}
+
+/// Owned, finite copy of an error chain produced by [`truncate`].
+#[derive(Debug)]
+pub(crate) struct TruncatedChain {
+    message: String,
+    source: Option<Box<TruncatedChain>>,
+}
+
+impl fmt::Display for TruncatedChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for TruncatedChain {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source.as_deref().map(|source| source as _)
+    }
+}
@@ -0,0 +1,75 @@
+use std::fmt;
+
+use crate::chain::Chain;
+use crate::eyre::Report;
+
+/// Displays a report on a single line, for logging.
+///
+/// # Details
+///
+/// Renders the messages of the report's chain joined by `": "`, followed by its extensions in
+/// brackets, like `outer: cause [Retry, ErrorCode=E42]`. Extensions are named by their type
+/// without its module path, and show their value when they have one in the `Extensions:` section
+/// of the report's `{:?}` output. The brackets are left out when there are no extensions.
+///
+/// The separator and whether extensions are shown are configured with
+/// [`HookBuilder::compact_separator`](crate::config::HookBuilder::compact_separator) and
+/// [`HookBuilder::compact_extensions`](crate::config::HookBuilder::compact_extensions). Nothing is
+/// colored.
+///
+/// # Examples
+///
+/// ```rust
+/// use extension_eyre::builtin::{ErrorCode, Retry};
+/// use extension_eyre::{eyre::eyre, Compact, Extension, ExtensionExt, WrapErr};
+///
+/// extension_eyre::install().unwrap();
+///
+/// let mut report = Err::<(), _>(eyre!("connection reset"))
+///     .wrap_err("could not fetch the price list")
+///     .wrap_err("checkout failed")
+///     .extension(Retry)
+///     .unwrap_err();
+/// report.extensions_mut().unwrap().insert_display(ErrorCode("E42"));
+///
+/// let line = Compact(&report).to_string();
+/// assert_eq!(
+///     line,
+///     "checkout failed: could not fetch the price list: connection reset [ErrorCode=E42, Retry]"
+/// );
+/// assert!(!line.contains('\u{1b}'));
+///
+/// assert_eq!(Compact(&eyre!("disk full")).to_string(), "disk full");
+/// ```
+#[derive(Clone, Copy)]
+pub struct Compact<'a>(pub &'a Report);
+
+impl fmt::Display for Compact<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let handler = self.0.handler().downcast_ref::<crate::Handler>();
+        let separator = handler.map_or(": ", |handler| &handler.config.compact_separator);
+
+        let mut errors = Chain::of_report(self.0);
+        if let Some(error) = errors.next() {
+            write!(f, "{}", error)?;
+        }
+        for error in errors {
+            write!(f, "{}{}", separator, error)?;
+        }
+
+        let handler = handler
+            .filter(|handler| handler.config.compact_extensions && !handler.extensions.is_empty());
+        if let Some(handler) = handler {
+            let summary = handler.extensions.summary(&handler.config.formatters);
+            write!(f, " [{}]", summary)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Compact<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Compact").field(&self.0).finish()
+    }
+}
@@ -1,19 +1,51 @@
 //! Configuration options for customizing the behavior of the provided panic
 //! and error reporting hooks
+use crate::chain;
 use crate::extensions::Extensions;
+use crate::panic_extensions;
+#[cfg(not(feature = "color-eyre"))]
+use crate::plain::{
+    EyreHook as EyreHookInner, HookBuilder as HookBuilderInner, PanicHook as PanicHookInner,
+    PanicReport as PanicReportInner,
+};
+use crate::quiet;
+use crate::scope;
+use crate::trace;
+#[cfg(feature = "color-eyre")]
 use color_eyre::config::{
     EyreHook as EyreHookInner, HookBuilder as HookBuilderInner, PanicHook as PanicHookInner,
-    PanicReport,
+    PanicReport as PanicReportInner,
 };
+#[cfg(feature = "color-eyre")]
 use color_eyre::section::PanicMessage;
-use fmt::Display;
+use owo_colors::Style;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
 use std::fmt;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 
+#[cfg(feature = "color-eyre")]
+#[cfg_attr(docsrs, doc(cfg(feature = "color-eyre")))]
 pub use color_eyre::config::{FilterCallback, Frame, Theme};
+#[cfg(feature = "serde")]
+pub use crate::panic_format::PanicFormat;
+pub use crate::precedence::{Precedence, Source};
+pub use crate::styles::ExtensionStyles;
+
+/// The exit code of a Rust program that panicked.
+const DEFAULT_PANIC_EXIT_CODE: u8 = 101;
 
 /// Builder for customizing the behavior of the global panic and error report hooks
 pub struct HookBuilder {
     inner: HookBuilderInner,
+    config: HandlerConfig,
+    panic_sink: Option<Box<PanicSinkCallback>>,
+    report_foreign_handlers: bool,
+    redaction_policy: Option<Arc<RedactionPolicyCallback>>,
+    panic_exit_code: u8,
+    inner_hook: Option<Box<InnerHookCallback>>,
 }
 
 impl HookBuilder {
@@ -28,7 +60,7 @@ impl HookBuilder {
     /// # Example
     ///
     /// ```rust
-    /// use color_eyre::config::HookBuilder;
+    /// use extension_eyre::config::HookBuilder;
     ///
     /// HookBuilder::new()
     ///     .install()
@@ -37,6 +69,12 @@ impl HookBuilder {
     pub fn new() -> Self {
         Self {
             inner: HookBuilderInner::new(),
+            config: HandlerConfig::default(),
+            panic_sink: None,
+            report_foreign_handlers: false,
+            redaction_policy: None,
+            panic_exit_code: DEFAULT_PANIC_EXIT_CODE,
+            inner_hook: None,
         }
     }
 
@@ -44,17 +82,60 @@ impl HookBuilder {
     pub fn blank() -> Self {
         HookBuilder {
             inner: HookBuilderInner::blank(),
+            config: HandlerConfig::default(),
+            panic_sink: None,
+            report_foreign_handlers: false,
+            redaction_policy: None,
+            panic_exit_code: DEFAULT_PANIC_EXIT_CODE,
+            inner_hook: None,
         }
     }
 
     /// Set the global styles that `color_eyre` should use.
     ///
     /// **Tip:** You can test new styles by editing `examples/theme.rs` in the `color-eyre` repository.
+    #[cfg(feature = "color-eyre")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "color-eyre")))]
     pub fn theme(mut self, theme: Theme) -> Self {
         self.inner = self.inner.theme(theme);
         self
     }
 
+    /// Set the style of the header of reports with the [`Severity`](crate::builtin::Severity)
+    /// `severity`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::builtin::Severity;
+    /// use extension_eyre::owo_colors::Style;
+    /// use extension_eyre::{eyre::eyre, ExtensionExt};
+    ///
+    /// extension_eyre::config::HookBuilder::default()
+    ///     .severity_style(Severity::Warning, Style::new().blue())
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// let report = eyre!("disk almost full").extension(Severity::Warning);
+    /// assert!(format!("{:?}", report).starts_with("\u{1b}[34m  ━"));
+    ///
+    /// let report = eyre!("disk full").extension(Severity::Error);
+    /// assert!(format!("{:?}", report).starts_with("\u{1b}[31m  ━"));
+    /// ```
+    pub fn severity_style(mut self, severity: crate::builtin::Severity, style: Style) -> Self {
+        self.config.severity_styles.insert(severity, style);
+        self
+    }
+
+    /// Set the styles of the extensions section, suggestions, error codes and report ids of
+    /// reports
+    ///
+    /// Defaults to [`ExtensionStyles::dark`]. See [`ExtensionStyles`] for an example.
+    pub fn extension_styles(mut self, styles: ExtensionStyles) -> Self {
+        self.config.extension_styles = styles;
+        self
+    }
+
     /// Add a custom section to the panic hook that will be printed
     /// in the panic message.
     ///
@@ -66,7 +147,9 @@ impl HookBuilder {
     ///     .install()
     ///     .unwrap()
     /// ```
-    pub fn panic_section<S: Display + Send + Sync + 'static>(mut self, section: S) -> Self {
+    #[cfg(feature = "color-eyre")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "color-eyre")))]
+    pub fn panic_section<S: fmt::Display + Send + Sync + 'static>(mut self, section: S) -> Self {
         self.inner = self.inner.panic_section(section);
         self
     }
@@ -89,7 +172,7 @@ impl HookBuilder {
     ///     .unwrap();
     ///
     /// impl PanicMessage for MyPanicMessage {
-    ///     fn display(&self, pi: &std::panic::PanicInfo<'_>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    ///     fn display(&self, pi: &std::panic::PanicHookInfo<'_>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     ///         writeln!(f, "{}", "The application panicked (crashed).".red())?;
     ///
     ///         // Print panic message.
@@ -123,11 +206,259 @@ impl HookBuilder {
     ///     "todo"
     /// }
     /// ```
+    #[cfg(feature = "color-eyre")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "color-eyre")))]
     pub fn panic_message<S: PanicMessage>(mut self, section: S) -> Self {
         self.inner = self.inner.panic_message(section);
         self
     }
 
+    /// Wraps the handlers created by `hook` instead of the ones of `color_eyre`
+    ///
+    /// # Details
+    ///
+    /// Extensions are layered on top of the handlers `hook` creates: reports are rendered by
+    /// them, followed by the sections of this crate, and they are told where reports are
+    /// created. Settings of this builder that change how `color_eyre` renders reports, like
+    /// [`theme`](Self::theme) or the frame filters, don't apply to them. Panic reports are still
+    /// rendered by `color_eyre`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::builtin::ErrorCode;
+    /// use extension_eyre::config::HookBuilder;
+    /// use extension_eyre::{eyre, Extension};
+    /// use std::fmt;
+    ///
+    /// struct Company;
+    ///
+    /// impl eyre::EyreHandler for Company {
+    ///     fn debug(
+    ///         &self,
+    ///         error: &(dyn std::error::Error + 'static),
+    ///         f: &mut fmt::Formatter<'_>,
+    ///     ) -> fmt::Result {
+    ///         write!(f, "{}\n\nSupport: support@example.com", error)
+    ///     }
+    /// }
+    ///
+    /// HookBuilder::default()
+    ///     .inner_hook(|_| Box::new(Company))
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// let mut report = eyre::eyre!("disk full");
+    /// report.extensions_mut().unwrap().insert_display(ErrorCode("E42"));
+    ///
+    /// assert_eq!(report.extension_ref::<ErrorCode>(), Some(&ErrorCode("E42")));
+    /// assert_eq!(report.to_string(), "disk full");
+    ///
+    /// let rendered = format!("{:?}", report);
+    /// assert!(rendered.starts_with("disk full\n\nSupport: support@example.com"));
+    /// assert!(rendered.contains("Extensions:") && rendered.contains("E42"));
+    /// ```
+    pub fn inner_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&(dyn std::error::Error + 'static)) -> Box<dyn eyre::EyreHandler>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.inner_hook = Some(Box::new(hook));
+        self
+    }
+
+    /// Hands panic reports to `sink` instead of printing them to stderr
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::config::{HookBuilder, PanicReport};
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let captured = Arc::new(Mutex::new(Vec::new()));
+    /// let sink = captured.clone();
+    ///
+    /// HookBuilder::default()
+    ///     .panic_sink(move |report: &PanicReport<'_>| {
+    ///         sink.lock().unwrap().push(report.to_string());
+    ///     })
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// let _ = std::panic::catch_unwind(|| panic!("request failed"));
+    ///
+    /// let captured = captured.lock().unwrap();
+    /// assert_eq!(captured.len(), 1);
+    /// assert!(captured[0].contains("request failed"));
+    /// ```
+    pub fn panic_sink<F>(mut self, sink: F) -> Self
+    where
+        F: Fn(&PanicReport<'_>) + Send + Sync + 'static,
+    {
+        self.panic_sink = Some(Box::new(sink));
+        self
+    }
+
+    /// Set how panic reports are rendered
+    ///
+    /// # Details
+    ///
+    /// With [`PanicFormat::Json`], panic reports are rendered as a JSON object on a single line,
+    /// wherever they are delivered. It holds the panic `message`, its `location`, the name of the
+    /// `thread` that panicked, the `backtrace` frames kept by the frame filters when a backtrace
+    /// is captured, and the serializable [panic extensions](crate::set_panic_extension).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::config::{HookBuilder, PanicFormat, PanicReport};
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let captured = Arc::new(Mutex::new(String::new()));
+    /// let sink = captured.clone();
+    ///
+    /// HookBuilder::default()
+    ///     .panic_format(PanicFormat::Json)
+    ///     .panic_sink(move |report: &PanicReport<'_>| *sink.lock().unwrap() = report.to_string())
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// let worker = std::thread::Builder::new().name("worker-1".into());
+    /// let _ = worker
+    ///     .spawn(|| {
+    ///         extension_eyre::with_panic_extensions(|extensions| {
+    ///             extensions.insert_serializable(7u64);
+    ///         });
+    ///         panic!("job {} failed", 7);
+    ///     })
+    ///     .unwrap()
+    ///     .join();
+    ///
+    /// let captured = captured.lock().unwrap();
+    /// assert!(!captured.contains('\n'));
+    ///
+    /// let json: serde_json::Value = serde_json::from_str(&captured).unwrap();
+    /// assert_eq!(json["message"], "job 7 failed");
+    /// assert_eq!(json["location"]["file"], file!());
+    /// assert_eq!(json["thread"], "worker-1");
+    /// assert!(json["backtrace"].is_array());
+    /// assert_eq!(json["extensions"]["u64"], 7);
+    /// ```
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn panic_format(mut self, format: PanicFormat) -> Self {
+        self.config.panic_format = format;
+        self
+    }
+
+    /// Writes panic reports to the writer returned by `writer` instead of stderr
+    ///
+    /// `writer` is called once for every panic. Replaces the sink set with
+    /// [`panic_sink`](Self::panic_sink), and the other way around; errors writing the report are
+    /// ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::config::HookBuilder;
+    /// use std::io::{self, Write};
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// #[derive(Clone, Default)]
+    /// pub struct Buffer(Arc<Mutex<Vec<u8>>>);
+    ///
+    /// impl Write for Buffer {
+    ///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    ///         self.0.lock().unwrap().write(buf)
+    ///     }
+    ///
+    ///     fn flush(&mut self) -> io::Result<()> {
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let buffer = Buffer::default();
+    /// let writer = buffer.clone();
+    ///
+    /// HookBuilder::default()
+    ///     .panic_writer(move || Box::new(writer.clone()))
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// let _ = std::panic::catch_unwind(|| panic!("render loop failed"));
+    ///
+    /// let written = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+    /// assert!(written.contains("render loop failed"));
+    /// ```
+    pub fn panic_writer<F>(mut self, writer: F) -> Self
+    where
+        F: Fn() -> Box<dyn std::io::Write + Send> + Send + Sync + 'static,
+    {
+        self.panic_sink = Some(Box::new(move |report| {
+            let mut writer = writer();
+            let _ = writeln!(writer, "{}", report);
+            let _ = writer.flush();
+        }));
+        self
+    }
+
+    /// Set how the extensions of panics are rendered in panic reports
+    ///
+    /// # Details
+    ///
+    /// The [panic extensions](crate::set_panic_extension) of the thread that panicked are
+    /// rendered by `section` after the rest of the panic report, separated by an empty line.
+    /// Without it, they are rendered like the `Extensions:` section of error reports, which
+    /// [`display_extensions_section`](Self::display_extensions_section) turns off for both.
+    /// Panics without extensions, and panic reports rendered as JSON, get no section either way.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::config::{HookBuilder, PanicReport};
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// #[derive(Debug, Clone, Copy)]
+    /// pub struct RequestId(u64);
+    ///
+    /// let rendered = Arc::new(Mutex::new(Vec::new()));
+    /// let sink = rendered.clone();
+    ///
+    /// let (panic_hook, _) = HookBuilder::default()
+    ///     .panic_extensions_section(|extensions, f| match extensions.get::<RequestId>() {
+    ///         Some(RequestId(id)) => write!(f, "While handling request {}", id),
+    ///         None => Ok(()),
+    ///     })
+    ///     .panic_sink(move |report: &PanicReport<'_>| {
+    ///         sink.lock().unwrap().push(report.to_string());
+    ///     })
+    ///     .into_hooks();
+    /// panic_hook.install();
+    ///
+    /// extension_eyre::set_panic_extension(RequestId(7));
+    /// let _ = std::panic::catch_unwind(|| panic!("index out of bounds"));
+    ///
+    /// // the values attached on this thread don't show up in panics on others
+    /// std::thread::spawn(|| {
+    ///     let _ = std::panic::catch_unwind(|| panic!("index out of bounds"));
+    /// })
+    /// .join()
+    /// .unwrap();
+    ///
+    /// let rendered = rendered.lock().unwrap();
+    /// assert!(rendered[0].ends_with("\n\nWhile handling request 7"));
+    /// assert!(!rendered[1].contains("While handling request"));
+    /// ```
+    pub fn panic_extensions_section<F>(mut self, section: F) -> Self
+    where
+        F: Fn(&Extensions, &mut fmt::Formatter<'_>) -> fmt::Result + Send + Sync + 'static,
+    {
+        self.config.panic_extensions_section = Some(Box::new(section));
+        self
+    }
+
     /// Set an upstream github repo and enable issue reporting url generation
     ///
     /// # Details
@@ -150,7 +481,7 @@ impl HookBuilder {
     #[cfg(feature = "issue-url")]
     #[cfg_attr(docsrs, doc(cfg(feature = "issue-url")))]
     pub fn issue_url<S: ToString>(mut self, url: S) -> Self {
-        self.inner = self.inner.issue_url(url);
+        self.config.issue_url = Some(url.to_string());
         self
     }
 
@@ -171,13 +502,78 @@ impl HookBuilder {
     #[cfg_attr(docsrs, doc(cfg(feature = "issue-url")))]
     pub fn add_issue_metadata<K, V>(mut self, key: K, value: V) -> Self
     where
-        K: Display,
-        V: Display + Send + Sync + 'static,
+        K: fmt::Display,
+        V: fmt::Display + Send + Sync + 'static,
     {
+        let rendered = value.to_string();
+        self.config
+            .issue_metadata
+            .push((key.to_string(), Box::new(move |_| Some(rendered.clone()))));
         self.inner = self.inner.add_issue_metadata(key, value);
         self
     }
 
+    /// Add an entry to the metadata table in generated github issue urls, with the value of the
+    /// extension `T` of the failing report
+    ///
+    /// The entry is left out of reports without a `T`, and of panics.
+    ///
+    /// **Note**: this metadata will be ignored if no `issue_url` is set.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, ExtensionExt};
+    ///
+    /// #[derive(Debug)]
+    /// pub struct RequestId(u64);
+    ///
+    /// impl std::fmt::Display for RequestId {
+    ///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    ///         write!(f, "req-{}", self.0)
+    ///     }
+    /// }
+    ///
+    /// extension_eyre::config::HookBuilder::default()
+    ///     .issue_url("https://github.com/owner/repo/issues/new")
+    ///     .add_issue_metadata("version", "1.2.3")
+    ///     .add_issue_metadata_from_extension::<RequestId, _>("request id")
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// let report = eyre!("connection reset").extension(RequestId(7));
+    /// let output = format!("{:?}", report);
+    /// assert_eq!(output.matches("Consider reporting this error").count(), 1);
+    ///
+    /// let url = output
+    ///     .lines()
+    ///     .find_map(|line| line.strip_prefix("Consider reporting this error using this URL: "))
+    ///     .unwrap();
+    /// assert!(url.contains("%7C**version**%7C1.2.3%7C"));
+    /// assert!(url.contains("%7C**request+id**%7Creq-7%7C"));
+    ///
+    /// let output = format!("{:?}", eyre!("connection reset"));
+    /// assert!(!output.contains("request+id"));
+    /// ```
+    #[cfg(feature = "issue-url")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "issue-url")))]
+    pub fn add_issue_metadata_from_extension<T, K>(mut self, key: K) -> Self
+    where
+        T: fmt::Display + Send + Sync + 'static,
+        K: fmt::Display,
+    {
+        self.config.issue_metadata.push((
+            key.to_string(),
+            Box::new(|extensions| {
+                if extensions.is_redacted::<T>() {
+                    return None;
+                }
+                extensions.get::<T>().map(T::to_string)
+            }),
+        ));
+        self
+    }
+
     /// Configures a filter for disabling issue url generation for certain kinds of errors
     ///
     /// If the closure returns `true`, then the issue url will be generated.
@@ -208,17 +604,61 @@ impl HookBuilder {
     where
         F: Fn(crate::ErrorKind<'_>) -> bool + Send + Sync + 'static,
     {
-        self.inner = self.inner.issue_filter(predicate);
+        self.config.issue_filter = Arc::new(move |kind, _| predicate(kind));
+        self
+    }
+
+    /// Configures a filter for disabling issue url generation for certain kinds of errors,
+    /// based on their extensions too
+    ///
+    /// Like [`issue_filter`](Self::issue_filter), but the closure also gets the extensions of the
+    /// report for recoverable errors, and the [panic extensions](crate::set_panic_extension) of
+    /// the thread for panics. Replaces the filter set with `issue_filter`, and the other way
+    /// around.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, ExtensionExt};
+    ///
+    /// #[derive(Debug)]
+    /// pub struct UserError;
+    ///
+    /// extension_eyre::config::HookBuilder::default()
+    ///     .issue_url("https://github.com/owner/repo/issues/new")
+    ///     .issue_filter_ext(|_, extensions| {
+    ///         extensions.map_or(true, |extensions| extensions.get::<UserError>().is_none())
+    ///     })
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// let bug = format!("{:?}", eyre!("index out of bounds"));
+    /// assert!(bug.contains("Consider reporting this error"));
+    ///
+    /// let mistake = format!("{:?}", eyre!("unknown flag --colour").extension(UserError));
+    /// assert!(!mistake.contains("Consider reporting this error"));
+    /// ```
+    #[cfg(feature = "issue-url")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "issue-url")))]
+    pub fn issue_filter_ext<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(crate::ErrorKind<'_>, Option<&Extensions>) -> bool + Send + Sync + 'static,
+    {
+        self.config.issue_filter = Arc::new(predicate);
         self
     }
 
     /// Configures the default capture mode for `SpanTraces` in error reports and panics
+    #[cfg(feature = "color-eyre")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "color-eyre")))]
     pub fn capture_span_trace_by_default(mut self, cond: bool) -> Self {
         self.inner = self.inner.capture_span_trace_by_default(cond);
         self
     }
 
     /// Configures the enviroment varible info section and whether or not it is displayed
+    #[cfg(feature = "color-eyre")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "color-eyre")))]
     pub fn display_env_section(mut self, cond: bool) -> Self {
         self.inner = self.inner.display_env_section(cond);
         self
@@ -229,8 +669,11 @@ impl HookBuilder {
     /// # Notes
     ///
     /// This will not disable the location section in a panic message.
-    #[cfg(feature = "track-caller")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "track-caller")))]
+    #[cfg(all(feature = "color-eyre", feature = "track-caller"))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(all(feature = "color-eyre", feature = "track-caller")))
+    )]
     pub fn display_location_section(mut self, cond: bool) -> Self {
         self.inner = self.inner.display_location_section(cond);
         self
@@ -262,48 +705,1247 @@ impl HookBuilder {
     ///     .install()
     ///     .unwrap();
     /// ```
+    #[cfg(feature = "color-eyre")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "color-eyre")))]
     pub fn add_frame_filter(mut self, filter: Box<FilterCallback>) -> Self {
         self.inner = self.inner.add_frame_filter(filter);
         self
     }
 
-    /// Install the given Hook as the global error report hook
-    pub fn install(self) -> Result<(), crate::eyre::Report> {
-        let (panic_hook, eyre_hook) = self.into_hooks();
-        eyre_hook.install()?;
-        panic_hook.install();
-        Ok(())
+    /// Add a custom filter to the set of frame filters, which also gets the extensions of the
+    /// report whose backtrace is filtered
+    ///
+    /// The extensions are `None` for the backtraces of panics. Filters run in the order they
+    /// were added, whether with this method or [`add_frame_filter`](HookBuilder::add_frame_filter).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, ExtensionExt};
+    ///
+    /// pub struct KeepFrames;
+    ///
+    /// // backtraces are on for reports
+    /// std::env::set_var("RUST_LIB_BACKTRACE", "1");
+    ///
+    /// extension_eyre::config::HookBuilder::default()
+    ///     .add_contextual_frame_filter(|frames, extensions| {
+    ///         let keep = extensions.and_then(|extensions| extensions.get::<KeepFrames>());
+    ///         if keep.is_none() {
+    ///             frames.clear();
+    ///         }
+    ///     })
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// let tagged = format!("{:?}", eyre!("connection reset").extension(KeepFrames));
+    /// let untagged = format!("{:?}", eyre!("connection reset"));
+    ///
+    /// assert!(tagged.contains(" BACKTRACE ") && untagged.contains(" BACKTRACE "));
+    /// assert!(tagged.contains("rust_out::main"));
+    /// assert!(!untagged.contains("rust_out::main"));
+    /// ```
+    #[cfg(feature = "color-eyre")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "color-eyre")))]
+    pub fn add_contextual_frame_filter<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&mut Vec<&Frame>, Option<&Extensions>) + Send + Sync + 'static,
+    {
+        let filter = crate::frame_filter::contextual(Box::new(filter));
+        self.inner = self.inner.add_frame_filter(filter);
+        self
     }
 
-    /// Add the default set of filters to this `HookBuilder`'s configuration
-    pub fn add_default_filters(mut self) -> Self {
-        self.inner = self.inner.add_default_filters();
+    /// Configures how many errors of a source chain are walked before giving up
+    ///
+    /// # Details
+    ///
+    /// Rendering a report, and every lookup in this crate that searches an error's sources, stops
+    /// after `depth` errors, or earlier if an error shows up twice in its own chain. Rendered
+    /// reports are annotated with `(source chain truncated at depth N)` when this happens.
+    /// Defaults to 32.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::eyre::Report;
+    ///
+    /// #[derive(Debug)]
+    /// struct Cyclic;
+    ///
+    /// impl std::fmt::Display for Cyclic {
+    ///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    ///         f.write_str("cached failure")
+    ///     }
+    /// }
+    ///
+    /// impl std::error::Error for Cyclic {
+    ///     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    ///         Some(self)
+    ///     }
+    /// }
+    ///
+    /// #[derive(Debug)]
+    /// struct Layer(usize, Option<Box<Layer>>);
+    ///
+    /// impl std::fmt::Display for Layer {
+    ///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    ///         write!(f, "layer {}", self.0)
+    ///     }
+    /// }
+    ///
+    /// impl std::error::Error for Layer {
+    ///     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    ///         self.1.as_deref().map(|layer| layer as _)
+    ///     }
+    /// }
+    ///
+    /// extension_eyre::config::HookBuilder::blank()
+    ///     .max_chain_depth(10)
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// let report = Report::new(Cyclic);
+    /// let rendered = format!("{:?}", report);
+    /// assert!(rendered.contains("cached failure"));
+    /// assert!(rendered.contains("(source chain truncated at depth 1)"));
+    ///
+    /// let deep = (0..100).rev().fold(None, |source, n| Some(Layer(n, source.map(Box::new))));
+    /// let rendered = format!("{:?}", Report::new(deep.unwrap()));
+    /// assert!(rendered.contains("layer 9"));
+    /// assert!(!rendered.contains("layer 10"));
+    /// assert!(rendered.contains("(source chain truncated at depth 10)"));
+    /// ```
+    pub fn max_chain_depth(mut self, depth: usize) -> Self {
+        self.config.max_chain_depth = depth;
         self
     }
 
-    /// Create a `PanicHook` and `EyreHook` from this `HookBuilder`.
-    /// This can be used if you want to combine these handlers with other handlers.
-    pub fn into_hooks(self) -> (PanicHook, EyreHook) {
-        let (panic_hook, eyre_hook) = self.inner.into_hooks();
-
-        let panic_hook = PanicHook { inner: panic_hook };
-
-        let eyre_hook = EyreHook { inner: eyre_hook };
+    /// Set the separator between the messages of a report's chain in its
+    /// [`Compact`](crate::Compact) rendering
+    ///
+    /// Defaults to `": "`.
+    pub fn compact_separator<S: ToString>(mut self, separator: S) -> Self {
+        self.config.compact_separator = separator.to_string();
+        self
+    }
 
-        (panic_hook, eyre_hook)
+    /// Configures whether the [`Compact`](crate::Compact) rendering of a report ends with its
+    /// extensions
+    ///
+    /// Enabled by default.
+    pub fn compact_extensions(mut self, cond: bool) -> Self {
+        self.config.compact_extensions = cond;
+        self
     }
-}
 
-#[allow(missing_docs)]
-impl Default for HookBuilder {
-    fn default() -> Self {
-        Self::new()
+    /// Set which source wins when several of them attach the same extension type
+    ///
+    /// Sources are listed strongest first; see [`Precedence`] for the default order and how
+    /// sources left out are ranked.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::config::{HookBuilder, Source};
+    ///
+    /// HookBuilder::default()
+    ///     .precedence([Source::Scope, Source::Explicit, Source::Classifier, Source::Default])
+    ///     .install()
+    ///     .unwrap();
+    /// ```
+    pub fn precedence<I: IntoIterator<Item = Source>>(mut self, order: I) -> Self {
+        self.config.precedence.set_order(order);
+        self
     }
-}
 
-/// A panic reporting hook
-pub struct PanicHook {
+    /// Set which source wins when several of them attach an extension of type `T`, overriding
+    /// the order set with [`precedence`](HookBuilder::precedence) for that type
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::config::{HookBuilder, Source};
+    /// use extension_eyre::{eyre::eyre, Extension, ExtensionExt};
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// pub enum Severity {
+    ///     Warning,
+    ///     Critical,
+    /// }
+    ///
+    /// HookBuilder::default()
+    ///     .precedence_for::<Severity, _>([Source::Classifier])
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// // explicit attachments still win over each other
+    /// let report = eyre!("disk full")
+    ///     .extension(Severity::Warning)
+    ///     .extension(Severity::Critical);
+    /// assert_eq!(report.extension_ref(), Some(&Severity::Critical));
+    /// assert_eq!(report.extensions_ref().unwrap().source::<Severity>(), Some(Source::Explicit));
+    /// ```
+    pub fn precedence_for<T: 'static, I: IntoIterator<Item = Source>>(mut self, order: I) -> Self {
+        let precedence = std::mem::take(&mut self.config.precedence);
+        self.config.precedence = precedence.with_type::<T, I>(order);
+        self
+    }
+
+    /// Attach a clone of `value` to every report created by the installed hook
+    ///
+    /// Defaults are attached as [`Source::Default`], so extensions of the same type attached
+    /// later, e.g. with [`ExtensionExt::extension`](crate::ExtensionExt::extension), replace
+    /// them. Setting a default for the same type again replaces the earlier value.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, Extension, ExtensionExt};
+    ///
+    /// #[derive(Debug, Clone, PartialEq)]
+    /// pub struct ServiceInfo {
+    ///     name: &'static str,
+    ///     version: &'static str,
+    ///     region: &'static str,
+    /// }
+    ///
+    /// #[derive(Debug, Clone, PartialEq)]
+    /// pub struct Tenant(&'static str);
+    ///
+    /// let service = ServiceInfo {
+    ///     name: "billing",
+    ///     version: "1.4.2",
+    ///     region: "eu-west-1",
+    /// };
+    ///
+    /// extension_eyre::config::HookBuilder::default()
+    ///     .default_extension(service.clone())
+    ///     .default_extension(Tenant("unknown"))
+    ///     .default_extension(Tenant("shared"))
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// let report = eyre!("invoice rendering failed");
+    /// assert_eq!(report.extension_ref(), Some(&service));
+    /// assert_eq!(report.extension_ref(), Some(&Tenant("shared")));
+    ///
+    /// let report = report.extension(Tenant("acme"));
+    /// assert_eq!(report.extension_ref(), Some(&Tenant("acme")));
+    /// ```
+    pub fn default_extension<T: Clone + Send + Sync + 'static>(mut self, value: T) -> Self {
+        let insert: Box<DefaultFn> = Box::new(move |extensions| {
+            extensions.insert_from(Source::Default, value.clone());
+        });
+
+        let type_id = TypeId::of::<T>();
+        let defaults = &mut self.config.defaults;
+        match defaults.iter_mut().find(|(id, _)| *id == type_id) {
+            Some((_, existing)) => *existing = insert,
+            None => defaults.push((type_id, insert)),
+        }
+        self
+    }
+
+    /// Derive extensions from the error of every report created by the installed hook
+    ///
+    /// `hook` is called with the error a report is created from and an empty map to fill. The
+    /// values it inserts are attached as [`Source::Classifier`], so they replace
+    /// [defaults](HookBuilder::default_extension) and are replaced by explicitly attached
+    /// extensions under the default [`Precedence`]. Hooks run in the order they were added and
+    /// are skipped for reports created [`quietly`](crate::quietly).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::Report, Extension};
+    /// use std::io;
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct Retry(bool);
+    ///
+    /// extension_eyre::config::HookBuilder::default()
+    ///     .extension_hook(|error, extensions| {
+    ///         if let Some(error) = error.downcast_ref::<io::Error>() {
+    ///             if error.kind() == io::ErrorKind::TimedOut {
+    ///                 extensions.insert(Retry(true));
+    ///             }
+    ///         }
+    ///     })
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// let report = Report::new(io::Error::new(io::ErrorKind::TimedOut, "read timed out"));
+    /// assert_eq!(report.extension_ref(), Some(&Retry(true)));
+    ///
+    /// let report = Report::new(io::Error::new(io::ErrorKind::NotFound, "no such file"));
+    /// assert_eq!(report.extension_ref::<Retry>(), None);
+    ///
+    /// let report = extension_eyre::quietly(|| {
+    ///     Report::new(io::Error::new(io::ErrorKind::TimedOut, "read timed out"))
+    /// });
+    /// assert_eq!(report.extension_ref::<Retry>(), None);
+    /// ```
+    pub fn extension_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&(dyn std::error::Error + 'static), &mut Extensions) + Send + Sync + 'static,
+    {
+        self.config.extension_hooks.push(Box::new(hook));
+        self
+    }
+
+    /// Call `observer` with every report created by the installed hook
+    ///
+    /// # Details
+    ///
+    /// `observer` is called with the error a report is created from and its extensions, once
+    /// [defaults](HookBuilder::default_extension) and [extension hooks](HookBuilder::extension_hook)
+    /// attached theirs, which makes it a single place to count errors or push them into metrics.
+    /// Observers run in the order they were added and are skipped for reports created
+    /// [`quietly`](crate::quietly). A panicking observer doesn't keep the report from being
+    /// created, nor the observers after it from running.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, WrapErr};
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let observed = Arc::new(Mutex::new(Vec::new()));
+    /// let sink = observed.clone();
+    ///
+    /// extension_eyre::config::HookBuilder::default()
+    ///     .on_report(move |error, _| sink.lock().unwrap().push(error.to_string()))
+    ///     .on_report(|_, _| panic!("the metrics backend is down"))
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// let _ = eyre!("disk full");
+    /// let _ = std::fs::read_to_string("fake_file").wrap_err("could not read the config");
+    /// let _ = Err::<(), _>(eyre!("timed out")).wrap_err("request failed");
+    /// let _ = extension_eyre::quietly(|| eyre!("audit log tampered with"));
+    ///
+    /// assert_eq!(
+    ///     *observed.lock().unwrap(),
+    ///     ["disk full", "could not read the config", "timed out"]
+    /// );
+    /// ```
+    pub fn on_report<F>(mut self, observer: F) -> Self
+    where
+        F: Fn(&(dyn std::error::Error + 'static), &Extensions) + Send + Sync + 'static,
+    {
+        self.config.observers.push(Box::new(observer));
+        self
+    }
+
+    /// Keep the last `capacity` reports created by the installed hook, for
+    /// [`recent_errors`](crate::recent_errors)
+    ///
+    /// # Details
+    ///
+    /// Each report is recorded when it's created, as a [`RecentError`](crate::RecentError)
+    /// holding copies of its messages and extensions rather than the report itself. Recording is
+    /// disabled by default, and with a `capacity` of 0.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::{builtin::Retry, eyre::eyre};
+    ///
+    /// extension_eyre::config::HookBuilder::default()
+    ///     .default_extension(Retry)
+    ///     .record_recent_errors(2)
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// for message in ["first", "second", "third", "fourth"] {
+    ///     let _ = eyre!(message);
+    /// }
+    ///
+    /// let recent = extension_eyre::recent_errors();
+    /// assert_eq!(recent.len(), 2);
+    /// assert_eq!(recent[0].chain, ["third"]);
+    /// assert_eq!(recent[1].chain, ["fourth"]);
+    /// assert_eq!(recent[1].extensions, [std::any::type_name::<Retry>()]);
+    /// ```
+    pub fn record_recent_errors(mut self, capacity: usize) -> Self {
+        self.config.recent_errors = capacity;
+        self
+    }
+
+    /// Count every report created by the installed hook with the [`metrics`] facade
+    ///
+    /// # Details
+    ///
+    /// Increments the counter `extension_eyre.reports` for each report, once
+    /// [extension hooks](HookBuilder::extension_hook) attached theirs. It's labelled with
+    /// `error_code`, the report's [`ErrorCode`](crate::builtin::ErrorCode) or `"unknown"`, and
+    /// with `severity`, the lowercase [`Severity`](crate::builtin::Severity) of reports that have
+    /// one. Reports created [`quietly`](crate::quietly) aren't counted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::builtin::{ErrorCode, Severity};
+    /// use extension_eyre::{eyre::eyre, Extension, ExtensionExt};
+    /// use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+    ///
+    /// let recorder = DebuggingRecorder::new();
+    /// let snapshotter = recorder.snapshotter();
+    /// recorder.install().unwrap();
+    ///
+    /// extension_eyre::config::HookBuilder::default()
+    ///     .extension_hook(|error, extensions| {
+    ///         if error.to_string().contains("timed out") {
+    ///             extensions.insert(ErrorCode("E_TIMEOUT"));
+    ///             extensions.insert(Severity::Warning);
+    ///         }
+    ///     })
+    ///     .emit_metrics(true)
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// let _ = eyre!("request timed out");
+    /// let _ = eyre!("request timed out");
+    /// let _ = eyre!("disk full");
+    ///
+    /// let mut counters: Vec<_> = snapshotter
+    ///     .snapshot()
+    ///     .into_vec()
+    ///     .into_iter()
+    ///     .map(|(key, _, _, value)| {
+    ///         let key = key.key();
+    ///         let mut labels: Vec<_> = key
+    ///             .labels()
+    ///             .map(|label| format!("{}={}", label.key(), label.value()))
+    ///             .collect();
+    ///         labels.sort();
+    ///         (key.name().to_owned(), labels, value)
+    ///     })
+    ///     .collect();
+    /// counters.sort_by(|a, b| a.1.cmp(&b.1));
+    ///
+    /// assert_eq!(
+    ///     counters,
+    ///     [
+    ///         (
+    ///             "extension_eyre.reports".to_owned(),
+    ///             vec!["error_code=E_TIMEOUT".to_owned(), "severity=warning".to_owned()],
+    ///             DebugValue::Counter(2),
+    ///         ),
+    ///         (
+    ///             "extension_eyre.reports".to_owned(),
+    ///             vec!["error_code=unknown".to_owned()],
+    ///             DebugValue::Counter(1),
+    ///         ),
+    ///     ]
+    /// );
+    /// ```
+    #[cfg(feature = "metrics")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+    pub fn emit_metrics(mut self, cond: bool) -> Self {
+        self.config.emit_metrics = cond;
+        self
+    }
+
+    /// Attach the extension `f` produces from an error of type `E` found in the source chain of
+    /// a new report
+    ///
+    /// The chain is searched from the outermost error down, and `f` is called with the first
+    /// error of type `E`. When several mappers produce the same extension type, the first one
+    /// added that matches wins. Mapped extensions are attached as [`Source::Classifier`], like
+    /// the values of an [`extension_hook`](HookBuilder::extension_hook), and are skipped for
+    /// reports created [`quietly`](crate::quietly) the same way.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::Report, Extension, WrapErr};
+    /// use std::{fmt, io};
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct Retry(bool);
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct FailedQuery(&'static str);
+    ///
+    /// #[derive(Debug)]
+    /// pub struct QueryError(&'static str, io::Error);
+    ///
+    /// impl fmt::Display for QueryError {
+    ///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    ///         write!(f, "query {} failed", self.0)
+    ///     }
+    /// }
+    ///
+    /// impl std::error::Error for QueryError {
+    ///     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    ///         Some(&self.1)
+    ///     }
+    /// }
+    ///
+    /// extension_eyre::config::HookBuilder::default()
+    ///     .map_error_extension(|error: &QueryError| FailedQuery(error.0))
+    ///     .map_error_extension(|error: &io::Error| Retry(error.kind() == io::ErrorKind::TimedOut))
+    ///     .map_error_extension(|_: &io::Error| Retry(false))
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// let timeout = io::Error::new(io::ErrorKind::TimedOut, "read timed out");
+    /// let report = Err::<(), _>(QueryError("load_user", timeout))
+    ///     .wrap_err("could not render the profile page")
+    ///     .unwrap_err();
+    ///
+    /// // the io error is two levels deep
+    /// assert_eq!(report.extension_ref(), Some(&Retry(true)));
+    /// assert_eq!(report.extension_ref(), Some(&FailedQuery("load_user")));
+    ///
+    /// let report = Report::msg("no io error in here");
+    /// assert_eq!(report.extension_ref::<Retry>(), None);
+    /// assert_eq!(report.extension_ref::<FailedQuery>(), None);
+    /// ```
+    ///
+    /// The search goes as deep as [`max_chain_depth`](HookBuilder::max_chain_depth), whether it's
+    /// set before or after the mapper is added:
+    ///
+    /// ```rust
+    /// use extension_eyre::{Extension, WrapErr};
+    /// use std::{fmt, io};
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct Retry(bool);
+    ///
+    /// #[derive(Debug)]
+    /// pub struct QueryError(io::Error);
+    ///
+    /// impl fmt::Display for QueryError {
+    ///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    ///         f.write_str("query failed")
+    ///     }
+    /// }
+    ///
+    /// impl std::error::Error for QueryError {
+    ///     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    ///         Some(&self.0)
+    ///     }
+    /// }
+    ///
+    /// extension_eyre::config::HookBuilder::default()
+    ///     .map_error_extension(|error: &io::Error| Retry(error.kind() == io::ErrorKind::TimedOut))
+    ///     .max_chain_depth(2)
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// let timeout = || io::Error::new(io::ErrorKind::TimedOut, "read timed out");
+    ///
+    /// // the io error is the second error of the chain
+    /// let report = Err::<(), _>(timeout()).wrap_err("could not load the profile").unwrap_err();
+    /// assert_eq!(report.extension_ref(), Some(&Retry(true)));
+    ///
+    /// // and the third one here
+    /// let report = Err::<(), _>(QueryError(timeout()))
+    ///     .wrap_err("could not load the profile")
+    ///     .unwrap_err();
+    /// assert_eq!(report.extension_ref::<Retry>(), None);
+    /// ```
+    pub fn map_error_extension<E, T, F>(mut self, f: F) -> Self
+    where
+        E: std::error::Error + 'static,
+        T: Send + Sync + 'static,
+        F: Fn(&E) -> T + Send + Sync + 'static,
+    {
+        self.config
+            .error_mappers
+            .push(Box::new(move |error, extensions, max_depth| {
+                if extensions.get::<T>().is_some() {
+                    return;
+                }
+
+                let error =
+                    chain::Chain::new(error, max_depth).find_map(|error| error.downcast_ref::<E>());
+                if let Some(error) = error {
+                    extensions.insert(f(error));
+                }
+            }));
+        self
+    }
+
+    /// Configures whether a [`ReportId`](crate::builtin::ReportId) is generated for every
+    /// report
+    ///
+    /// Disabled by default, so enabling the `uuid` feature doesn't change the reports of other
+    /// crates sharing the dependency.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::builtin::ReportId;
+    /// use extension_eyre::{eyre::eyre, Extension};
+    ///
+    /// extension_eyre::config::HookBuilder::default()
+    ///     .generate_report_ids(true)
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// let report = eyre!("connection reset");
+    /// let id = report.extension_ref::<ReportId>().unwrap();
+    /// assert!(format!("{:?}", report).starts_with(&format!("Report id: {}\n", id)));
+    /// ```
+    #[cfg(feature = "uuid")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "uuid")))]
+    pub fn generate_report_ids(mut self, cond: bool) -> Self {
+        self.config.generate_report_ids = cond;
+        self
+    }
+
+    /// Configures whether the time every report is created at is captured as a
+    /// [`CreatedAt`](crate::builtin::CreatedAt)
+    ///
+    /// Disabled by default.
+    #[cfg(feature = "time")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "time")))]
+    pub fn capture_timestamps(mut self, cond: bool) -> Self {
+        self.config.capture_timestamps = cond;
+        self
+    }
+
+    /// Configures whether the thread, and task, every report is created on is captured as a
+    /// [`ThreadInfo`](crate::builtin::ThreadInfo)
+    ///
+    /// Disabled by default.
+    pub fn capture_thread_info(mut self, cond: bool) -> Self {
+        self.config.capture_thread_info = cond;
+        self
+    }
+
+    /// Configures whether the id, executable and arguments of the process are captured as a
+    /// [`ProcessInfo`](crate::builtin::ProcessInfo) and attached to every report
+    ///
+    /// The info is captured once, when the hook is installed. Disabled by default.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::builtin::ProcessInfo;
+    /// use extension_eyre::{eyre::eyre, Extension};
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let report = eyre!("job queue closed");
+    /// assert_eq!(report.extension_ref::<ProcessInfo>(), None);
+    /// assert!(!format!("{:?}", report).contains("Process:"));
+    /// ```
+    pub fn capture_process_info(mut self, cond: bool) -> Self {
+        self.config.capture_process_info = cond;
+        self
+    }
+
+    /// Configures whether the backtrace of every report is captured as a
+    /// [`CapturedBacktrace`](crate::builtin::CapturedBacktrace)
+    ///
+    /// Unlike the backtrace rendered in the report, it can be read back with
+    /// [`Extension::captured_backtrace`](crate::Extension::captured_backtrace), e.g. to send it to
+    /// telemetry. Capturing follows the `RUST_LIB_BACKTRACE` and `RUST_BACKTRACE` environment
+    /// variables, unless [`force_backtrace_extension`](Self::force_backtrace_extension) is set.
+    /// Disabled by default.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, Extension};
+    ///
+    /// std::env::set_var("RUST_LIB_BACKTRACE", "0");
+    ///
+    /// extension_eyre::config::HookBuilder::default()
+    ///     .capture_backtrace_extension(true)
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// let report = eyre!("connection reset");
+    /// assert!(report.captured_backtrace().is_none());
+    /// ```
+    pub fn capture_backtrace_extension(mut self, cond: bool) -> Self {
+        self.config.capture_backtrace_extension = cond;
+        self
+    }
+
+    /// Configures whether the backtrace captured with
+    /// [`capture_backtrace_extension`](Self::capture_backtrace_extension) is captured regardless
+    /// of the `RUST_LIB_BACKTRACE` and `RUST_BACKTRACE` environment variables
+    ///
+    /// Disabled by default.
+    pub fn force_backtrace_extension(mut self, cond: bool) -> Self {
+        self.config.force_backtrace_extension = cond;
+        self
+    }
+
+    /// Replaces the captured process arguments for which `predicate` returns `true` with
+    /// `<redacted>`
+    ///
+    /// Only applies with [`capture_process_info`](HookBuilder::capture_process_info). The
+    /// arguments are redacted before they are stored in the
+    /// [`ProcessInfo`](crate::builtin::ProcessInfo), so they don't show up anywhere.
+    pub fn redact_process_args<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        self.config.process_arg_redaction = Some(Box::new(predicate));
+        self
+    }
+
+    /// Captures the values of the environment variables named `names` when every report is
+    /// created, as a [`CapturedEnv`](crate::builtin::CapturedEnv)
+    ///
+    /// Reports show the values on an `Environment:` line. Nothing is captured by default.
+    pub fn capture_env_vars(mut self, names: &[&str]) -> Self {
+        self.config.captured_env_vars = names.iter().map(|&name| name.to_owned()).collect();
+        self
+    }
+
+    /// Masks the values of the captured environment variables whose names match one of
+    /// `patterns` wherever reports show them
+    ///
+    /// In a pattern, `*` stands for any run of characters, so `*_TOKEN` matches `GITHUB_TOKEN`.
+    /// Matching is case sensitive. The values held by the
+    /// [`CapturedEnv`](crate::builtin::CapturedEnv) extension aren't masked.
+    pub fn redact_env_vars(mut self, patterns: &[&str]) -> Self {
+        self.config.redacted_env_vars =
+            patterns.iter().map(|&pattern| pattern.to_owned()).collect();
+        self
+    }
+
+    /// Configures whether the innermost span every report is created in is captured as a
+    /// [`SpanContext`](crate::builtin::SpanContext)
+    ///
+    /// Disabled by default.
+    #[cfg(feature = "capture-spantrace")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "capture-spantrace")))]
+    pub fn capture_span_context(mut self, cond: bool) -> Self {
+        self.config.capture_span_context = cond;
+        self
+    }
+
+    /// Link the [`ErrorCode`](crate::builtin::ErrorCode) of reports to its documentation at
+    /// `url` followed by the code
+    ///
+    /// A `/` is put between the two unless `url` already ends with one. Without a base URL,
+    /// only the code is shown.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::builtin::ErrorCode;
+    /// use extension_eyre::{eyre::eyre, ExtensionExt};
+    ///
+    /// extension_eyre::config::HookBuilder::default()
+    ///     .error_code_base_url("https://docs.example.com/errors")
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// let report = eyre!("unknown flag --colour").extension(ErrorCode("E0123"));
+    /// assert!(format!("{:?}", report)
+    ///     .contains("Error code: E0123 (https://docs.example.com/errors/E0123)"));
+    ///
+    /// let report = eyre!("unexpected failure");
+    /// assert!(!format!("{:?}", report).contains("Error code:"));
+    /// ```
+    pub fn error_code_base_url<S: ToString>(mut self, url: S) -> Self {
+        self.config.error_code_base_url = Some(url.to_string());
+        self
+    }
+
+    /// Configures whether reports capture the traces the inner handler didn't, so they can be
+    /// shown for reports marked [`Verbose`](crate::builtin::Verbose)
+    ///
+    /// Enabled by default. Capturing a backtrace for every report has a cost; disable this when
+    /// reports are created on hot paths and `Verbose` isn't used.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::builtin::Verbose;
+    /// use extension_eyre::{eyre::eyre, ExtensionExt};
+    ///
+    /// std::env::set_var("RUST_LIB_BACKTRACE", "0");
+    ///
+    /// extension_eyre::config::HookBuilder::default()
+    ///     .capture_verbose_traces(false)
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// let report = eyre!("invariant violated").extension(Verbose);
+    /// assert!(!format!("{:?}", report).contains(" BACKTRACE "));
+    /// ```
+    pub fn capture_verbose_traces(mut self, cond: bool) -> Self {
+        self.config.capture_verbose_traces = cond;
+        self
+    }
+
+    /// Configures whether the `Extensions:` section is shown in error reports
+    ///
+    /// The section lists every extension attached to a report, rendering values inserted with
+    /// [`Extensions::insert_display`] and naming the type of every other one. It is shown by
+    /// default, and left out of reports without extensions either way.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, ExtensionExt};
+    ///
+    /// pub struct Retry;
+    ///
+    /// extension_eyre::config::HookBuilder::default()
+    ///     .display_extensions_section(false)
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// let report = eyre!("query failed").extension(Retry);
+    /// assert!(!format!("{:?}", report).contains("Extensions:"));
+    /// ```
+    pub fn display_extensions_section(mut self, cond: bool) -> Self {
+        self.config.display_extensions_section = cond;
+        self
+    }
+
+    /// Configures whether the `Extensions:` section shows where each extension was attached
+    ///
+    /// Values attached with [`ExtensionExt::extension`](crate::ExtensionExt::extension) or
+    /// [`with_extension`](crate::ExtensionExt::with_extension) are followed by the call site,
+    /// like `(attached at src/main.rs:42)`, also available from
+    /// [`Extensions::provenance`]. Disabled by default.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, ExtensionExt};
+    /// use std::any::type_name;
+    ///
+    /// pub struct Retry;
+    ///
+    /// extension_eyre::config::HookBuilder::default()
+    ///     .display_extension_provenance(true)
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// let report = eyre!("query failed").extension(Retry);
+    /// let attached = format!("(attached at {}:{})", file!(), line!() - 1);
+    /// let line = format!("{} {}", type_name::<Retry>(), attached);
+    /// assert!(format!("{:?}", report).contains(&line));
+    /// ```
+    pub fn display_extension_provenance(mut self, cond: bool) -> Self {
+        self.config.display_extension_provenance = cond;
+        self
+    }
+
+    /// Render extensions of type `T` with `formatter` in the `Extensions:` section of error
+    /// reports
+    ///
+    /// Meant for types that don't implement `Display`, or whose `Display` output isn't what
+    /// should end up in a report. Registering another formatter for the same type replaces the
+    /// earlier one. A registered formatter takes precedence over the `Display` implementation
+    /// recorded by [`Extensions::insert_display`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, ExtensionExt};
+    /// use std::any::type_name;
+    ///
+    /// pub struct StatusCode(u16);
+    /// pub struct Connection;
+    ///
+    /// extension_eyre::config::HookBuilder::default()
+    ///     .extension_formatter::<StatusCode, _>(|_, f| write!(f, "unused"))
+    ///     .extension_formatter::<StatusCode, _>(|status, f| write!(f, "HTTP {}", status.0))
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// let report = eyre!("upstream failed")
+    ///     .extension(StatusCode(502))
+    ///     .extension(Connection);
+    ///
+    /// let rendered = format!("{:?}", report);
+    /// assert!(rendered.contains(&format!("\n   {}: HTTP 502", type_name::<StatusCode>())));
+    ///
+    /// // types without a formatter are listed by name only
+    /// assert!(rendered.contains(&format!("\n   {}\n", type_name::<Connection>())));
+    /// ```
+    pub fn extension_formatter<T, F>(mut self, formatter: F) -> Self
+    where
+        T: Send + Sync + 'static,
+        F: Fn(&T, &mut fmt::Formatter<'_>) -> fmt::Result + Send + Sync + 'static,
+    {
+        self.config.formatters.insert(
+            TypeId::of::<T>(),
+            Box::new(move |value, f| {
+                let value = (value as &(dyn Any + 'static))
+                    .downcast_ref::<T>()
+                    .expect("formatted value has the registered type");
+
+                formatter(value, f)
+            }),
+        );
+        self
+    }
+
+    /// Record call sites that use extensions on reports created by another handler
+    ///
+    /// Meant for migrating code from `color_eyre`: reports from another handler drop every
+    /// extension attached to them. The recorded call sites are available from
+    /// [`migration::foreign_handler_sightings`](crate::migration::foreign_handler_sightings).
+    ///
+    /// Recording starts when the hooks are created, even if installing them fails because
+    /// another handler was installed first.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::config::HookBuilder;
+    ///
+    /// HookBuilder::default()
+    ///     .report_foreign_handlers(true)
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// // ... run the workload
+    ///
+    /// assert!(extension_eyre::migration::foreign_handler_sightings().is_empty());
+    /// ```
+    pub fn report_foreign_handlers(mut self, cond: bool) -> Self {
+        self.report_foreign_handlers = cond;
+        self
+    }
+
+    /// Redact every extension whose type `predicate` returns `true` for
+    ///
+    /// The predicate gets the `TypeId` and type name of an extension, and redacted extensions
+    /// are handled like the ones inserted with
+    /// [`Extensions::insert_redacted`]: shown as `type: <redacted>` and left out of serialized
+    /// extensions and issue url metadata, though still readable programmatically.
+    ///
+    /// The policy applies to every `Extensions` once the hooks are created, and replaces the
+    /// policy of hooks created before.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, Extension, ExtensionExt};
+    ///
+    /// #[derive(Debug, serde::Serialize)]
+    /// pub struct RequestBody(String);
+    ///
+    /// extension_eyre::config::HookBuilder::default()
+    ///     .redaction_policy(|_, type_name| type_name.ends_with("RequestBody"))
+    ///     .extension_formatter(|body: &RequestBody, f| write!(f, "{:?}", body))
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// let body = || RequestBody(r#"{"password":"hunter2"}"#.to_owned());
+    /// let report = eyre!("invalid payload").extension(body());
+    ///
+    /// for output in [format!("{:?}", report), format!("{:#?}", report)] {
+    ///     assert!(!output.contains("hunter2"));
+    ///     assert!(output.contains("RequestBody: <redacted>"));
+    /// }
+    /// assert!(report.extension_ref::<RequestBody>().unwrap().0.contains("hunter2"));
+    ///
+    /// #[cfg(feature = "serde")]
+    /// {
+    ///     let mut extensions = extension_eyre::extensions::Extensions::new();
+    ///     extensions.insert_serializable(body());
+    ///     assert_eq!(extensions.to_json_value(), serde_json::json!({}));
+    /// }
+    /// ```
+    pub fn redaction_policy<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(TypeId, &'static str) -> bool + Send + Sync + 'static,
+    {
+        self.redaction_policy = Some(Arc::new(predicate));
+        self
+    }
+
+    /// Write a crash report file to `dir` for every panic
+    ///
+    /// # Details
+    ///
+    /// Each file gets a unique name in `dir`, even for panics on several threads at once. It
+    /// holds the rendered report without colors, followed by a `Metadata:` section with the
+    /// time it was written, the process id, the entries added with `add_issue_metadata` and,
+    /// with the `serde` feature, the serializable extensions. The path of the file is printed to
+    /// stderr; if it can't be written, the reason is printed instead.
+    ///
+    /// Reports can be written to the same directory with
+    /// [`Extension::write_crash_report`](crate::Extension::write_crash_report).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::config::HookBuilder;
+    ///
+    /// let dir = std::env::temp_dir().join(format!("crash-reports-{}", std::process::id()));
+    /// std::fs::create_dir_all(&dir).unwrap();
+    ///
+    /// HookBuilder::default()
+    ///     .capture_crash_reports(&dir)
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// let _ = std::panic::catch_unwind(|| panic!("render loop failed"));
+    ///
+    /// let files = std::fs::read_dir(&dir).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+    /// assert_eq!(files.len(), 1);
+    ///
+    /// let contents = std::fs::read_to_string(files[0].path()).unwrap();
+    /// assert!(contents.contains("render loop failed"));
+    /// assert!(contents.contains("\nMetadata:\ntimestamp: "));
+    /// assert!(!contents.contains('\u{1b}'));
+    /// # std::fs::remove_dir_all(&dir).unwrap();
+    /// ```
+    pub fn capture_crash_reports<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.config.crash_report_dir = Some(dir.into());
+        self
+    }
+
+    /// Set the exit code [`run`](HookBuilder::run) returns when the closure panics
+    ///
+    /// Defaults to 101, the exit code of a Rust program that panicked.
+    pub fn panic_exit_code(mut self, code: u8) -> Self {
+        self.panic_exit_code = code;
+        self
+    }
+
+    /// Install the hooks and run `f`, turning its outcome into the exit code of the process
+    ///
+    /// An error is printed to stderr and the process exits with the report's
+    /// [`exit_code`](crate::Extension::exit_code). A panic is reported by the installed panic
+    /// hook and the process exits with the code set by
+    /// [`panic_exit_code`](HookBuilder::panic_exit_code).
+    ///
+    /// If a hook was installed already, `f` is still run and its reports are rendered by that
+    /// hook.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::builtin::ExitCode;
+    /// use extension_eyre::config::HookBuilder;
+    /// use extension_eyre::{eyre::eyre, ExtensionExt};
+    ///
+    /// let code = HookBuilder::default()
+    ///     .panic_exit_code(70)
+    ///     .run(|| Err(eyre!("invalid arguments").extension(ExitCode(2))));
+    /// assert_eq!(code, std::process::ExitCode::from(2));
+    ///
+    /// let code = HookBuilder::default()
+    ///     .panic_exit_code(70)
+    ///     .run(|| panic!("index out of bounds"));
+    /// assert_eq!(code, std::process::ExitCode::from(70));
+    /// ```
+    pub fn run<F>(self, f: F) -> std::process::ExitCode
+    where
+        F: FnOnce() -> Result<(), crate::eyre::Report>,
+    {
+        use crate::Extension as _;
+
+        let panic_exit_code = self.panic_exit_code;
+        if let Ok((panic_hook, eyre_hook)) = self.try_into_hooks() {
+            if eyre_hook.install().is_ok() {
+                panic_hook.install();
+            }
+        }
+
+        match std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)) {
+            Ok(Ok(())) => std::process::ExitCode::SUCCESS,
+            Ok(Err(report)) => {
+                eprintln!("Error: {:?}", report);
+                std::process::ExitCode::from(report.exit_code())
+            }
+            // the panic hook has printed the panic report already
+            Err(_) => std::process::ExitCode::from(panic_exit_code),
+        }
+    }
+
+    /// Install the given Hook as the global error report hook
+    ///
+    /// # Details
+    ///
+    /// Fails with [`InstallError::AlreadyInstalled`] when an eyre hook was installed before,
+    /// telling whether it was installed by this crate. The panic hook is only installed along
+    /// with the eyre hook.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::config::{HookBuilder, InstallError};
+    ///
+    /// HookBuilder::default().install().unwrap();
+    ///
+    /// match HookBuilder::default().install() {
+    ///     Err(InstallError::AlreadyInstalled { ours: true }) => {}
+    ///     other => panic!("unexpected {:?}", other),
+    /// }
+    /// ```
+    pub fn install(self) -> Result<(), InstallError> {
+        let mut installed = lock_installed();
+        if *installed {
+            return Err(InstallError::AlreadyInstalled { ours: true });
+        }
+
+        let (panic_hook, eyre_hook) = self.try_into_hooks().map_err(InstallError::Other)?;
+        eyre_hook.install_locked(&mut installed)?;
+        panic_hook.install();
+        Ok(())
+    }
+
+    /// Add the default set of filters to this `HookBuilder`'s configuration
+    #[cfg(feature = "color-eyre")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "color-eyre")))]
+    pub fn add_default_filters(mut self) -> Self {
+        self.inner = self.inner.add_default_filters();
+        self
+    }
+
+    /// Create a `PanicHook` and `EyreHook` from this `HookBuilder`.
+    /// This can be used if you want to combine these handlers with other handlers.
+    pub fn into_hooks(self) -> (PanicHook, EyreHook) {
+        self.try_into_hooks().expect("into_hooks should only be called when no `color_spantrace` themes have previously been set")
+    }
+
+    /// Create a `PanicHook` and `EyreHook` from this `HookBuilder`.
+    /// This can be used if you want to combine these handlers with other handlers.
+    ///
+    /// Fails if a `color_spantrace` theme was set already, e.g. by creating hooks before.
+    pub fn try_into_hooks(self) -> Result<(PanicHook, EyreHook), crate::eyre::Report> {
+        if self.report_foreign_handlers {
+            crate::migration::enable();
+        }
+
+        if let Some(policy) = self.redaction_policy {
+            crate::redaction::set_policy(policy);
+        }
+
+        #[cfg_attr(
+            not(any(feature = "issue-url", all(feature = "serde", feature = "color-eyre"))),
+            allow(unused_mut)
+        )]
+        let mut inner = self.inner;
+
+        #[cfg(feature = "issue-url")]
+        if let Some(url) = &self.config.issue_url {
+            // the eyre hook renders the issue urls of reports itself
+            let filter = self.config.issue_filter.clone();
+            inner = inner.issue_url(url).issue_filter(move |kind| match kind {
+                crate::ErrorKind::NonRecoverable(_) => {
+                    panic_extensions::with_current(|extensions| filter(kind, Some(extensions)))
+                }
+                crate::ErrorKind::Recoverable(_) => false,
+            });
+        }
+
+        #[cfg(all(feature = "serde", feature = "color-eyre"))]
+        if self.config.panic_format == PanicFormat::Json {
+            // added last, to see the frames every other filter kept
+            inner = inner.add_frame_filter(Box::new(crate::panic_format::record_frames));
+        }
+
+        let (panic_hook, eyre_hook) = inner.try_into_hooks()?;
+
+        let config = Arc::new(self.config);
+
+        let panic_hook = PanicHook {
+            inner: panic_hook,
+            sink: self.panic_sink,
+            config: config.clone(),
+        };
+
+        let eyre_hook = EyreHook {
+            inner: eyre_hook,
+            custom: self.inner_hook,
+            config,
+        };
+
+        Ok((panic_hook, eyre_hook))
+    }
+}
+
+#[allow(missing_docs)]
+impl Default for HookBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// whether an eyre hook of this crate was installed; held while installing, so concurrent installs
+// see the hook installed by the first
+static INSTALLED: Mutex<bool> = Mutex::new(false);
+
+fn lock_installed() -> MutexGuard<'static, bool> {
+    INSTALLED.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Whether the eyre hook of this crate was installed.
+pub(crate) fn is_installed() -> bool {
+    *lock_installed()
+}
+
+/// Error returned when installing the hooks fails
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum InstallError {
+    /// An eyre hook was installed already
+    AlreadyInstalled {
+        /// Whether the installed hook is one of this crate
+        ours: bool,
+    },
+    /// The hooks could not be created, e.g. because a `color_spantrace` theme was set already
+    Other(crate::eyre::Report),
+}
+
+impl fmt::Display for InstallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InstallError::AlreadyInstalled { ours: true } => {
+                f.write_str("the extension-eyre hook was installed already")
+            }
+            InstallError::AlreadyInstalled { ours: false } => {
+                f.write_str("another eyre hook was installed already")
+            }
+            InstallError::Other(report) => write!(f, "could not create the hooks: {}", report),
+        }
+    }
+}
+
+impl std::error::Error for InstallError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            InstallError::Other(report) => Some(&**report),
+            InstallError::AlreadyInstalled { .. } => None,
+        }
+    }
+}
+
+/// Callback deciding which extension types are redacted, see [`HookBuilder::redaction_policy`]
+pub type RedactionPolicyCallback = dyn Fn(TypeId, &'static str) -> bool + Send + Sync + 'static;
+
+/// Callback receiving panic reports, see [`HookBuilder::panic_sink`]
+pub type PanicSinkCallback = dyn Fn(&PanicReport<'_>) + Send + Sync + 'static;
+
+/// Callback rendering the extensions of panics, see [`HookBuilder::panic_extensions_section`]
+pub type PanicExtensionsSectionCallback =
+    dyn Fn(&Extensions, &mut fmt::Formatter<'_>) -> fmt::Result + Send + Sync + 'static;
+
+/// Callback filtering backtrace frames with the extensions of the report they belong to, see
+/// [`HookBuilder::add_contextual_frame_filter`]
+#[cfg(feature = "color-eyre")]
+#[cfg_attr(docsrs, doc(cfg(feature = "color-eyre")))]
+pub type ContextualFilterCallback =
+    dyn Fn(&mut Vec<&Frame>, Option<&Extensions>) + Send + Sync + 'static;
+
+/// A panic reporting hook
+pub struct PanicHook {
     inner: PanicHookInner,
+    sink: Option<Box<PanicSinkCallback>>,
+    config: Arc<HandlerConfig>,
 }
 
 impl PanicHook {
@@ -312,56 +1954,562 @@ impl PanicHook {
         std::panic::set_hook(self.into_panic_hook());
     }
 
+    /// Install a global panic hook handing every panic report to `f`
+    ///
+    /// `f` decides how the report is delivered; the sink configured on the
+    /// [`HookBuilder`] isn't used, though crash reports are still written.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::config::HookBuilder;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let delivered = Arc::new(Mutex::new(Vec::new()));
+    /// let sink = delivered.clone();
+    ///
+    /// let (panic_hook, eyre_hook) = HookBuilder::default().into_hooks();
+    /// eyre_hook.install().unwrap();
+    /// panic_hook.install_with(move |report| {
+    ///     sink.lock().unwrap().push(report.payload_str().unwrap_or_default().to_owned());
+    /// });
+    ///
+    /// let _ = std::panic::catch_unwind(|| panic!("render loop failed"));
+    ///
+    /// assert_eq!(*delivered.lock().unwrap(), ["render loop failed"]);
+    /// ```
+    pub fn install_with<F>(self, f: F)
+    where
+        F: Fn(PanicReport<'_>) + Send + Sync + 'static,
+    {
+        std::panic::set_hook(Box::new(move |panic_info| {
+            let report = self.panic_report(panic_info);
+            self.write_crash_report(&report);
+            f(report);
+        }));
+    }
+
     /// Convert self into the type expected by `std::panic::set_hook`.
     pub fn into_panic_hook(
         self,
-    ) -> Box<dyn Fn(&std::panic::PanicInfo<'_>) + Send + Sync + 'static> {
+    ) -> Box<dyn Fn(&std::panic::PanicHookInfo<'_>) + Send + Sync + 'static> {
         Box::new(move |panic_info| {
-            eprintln!("{}", self.panic_report(panic_info));
+            let report = self.panic_report(panic_info);
+
+            match &self.sink {
+                Some(sink) => sink(&report),
+                None => eprintln!("{}", report),
+            }
+
+            self.write_crash_report(&report);
         })
     }
 
+    fn write_crash_report(&self, report: &PanicReport<'_>) {
+        if let Some(dir) = &self.config.crash_report_dir {
+            let rendered = report.to_string();
+            let extensions = report.extensions();
+            let _ = crate::crash::write_or_report(dir, &rendered, &self.config, extensions);
+        }
+    }
+
     /// Construct a panic reporter which prints it's panic report via the
     /// `Display` trait.
     pub fn panic_report<'a>(
         &'a self,
-        panic_info: &'a std::panic::PanicInfo<'_>,
+        panic_info: &'a std::panic::PanicHookInfo<'_>,
     ) -> PanicReport<'a> {
-        self.inner.panic_report(panic_info)
+        // taken before the inner report exists, so a custom `PanicMessage` rendering it can
+        // read the map through `PanicHook::panic_extensions`
+        let extensions = panic_extensions::snapshot();
+
+        PanicReport {
+            inner: self.inner.panic_report(panic_info),
+            panic_info,
+            extensions,
+            config: &self.config,
+        }
+    }
+
+    /// Calls `f` with the extensions of the panic being reported on the current thread
+    ///
+    /// While a panic is reported, these are the values set with
+    /// [`set_panic_extension`](crate::set_panic_extension) before it fired, which makes them
+    /// available to code that only sees the `PanicHookInfo`, like a custom [`PanicMessage`].
+    /// Otherwise these are the values that would be attached to a panic right now.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "color-eyre")] {
+    /// use extension_eyre::config::{HookBuilder, PanicHook, PanicReport};
+    /// use color_eyre::section::PanicMessage;
+    /// use std::fmt;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// pub struct RequestId(u64);
+    ///
+    /// struct WithRequestId;
+    ///
+    /// impl PanicMessage for WithRequestId {
+    ///     fn display(
+    ///         &self,
+    ///         _: &std::panic::PanicHookInfo<'_>,
+    ///         f: &mut fmt::Formatter<'_>,
+    ///     ) -> fmt::Result {
+    ///         PanicHook::panic_extensions(|extensions| match extensions.get::<RequestId>() {
+    ///             Some(RequestId(id)) => write!(f, "request {} panicked", id),
+    ///             None => write!(f, "panicked outside of a request"),
+    ///         })
+    ///     }
+    /// }
+    ///
+    /// let rendered = Arc::new(Mutex::new(String::new()));
+    /// let sink = rendered.clone();
+    ///
+    /// let (panic_hook, _) = HookBuilder::default()
+    ///     .panic_message(WithRequestId)
+    ///     .panic_sink(move |report: &PanicReport<'_>| *sink.lock().unwrap() = report.to_string())
+    ///     .into_hooks();
+    /// panic_hook.install();
+    ///
+    /// extension_eyre::set_panic_extension(RequestId(7));
+    /// let _ = std::panic::catch_unwind(|| panic!("index out of bounds"));
+    ///
+    /// assert!(rendered.lock().unwrap().starts_with("request 7 panicked"));
+    /// # }
+    /// ```
+    pub fn panic_extensions<R, F: FnOnce(&Extensions) -> R>(f: F) -> R {
+        panic_extensions::with_current(f)
+    }
+}
+
+/// A panic report, printed via the `Display` trait and inspectable through its accessors
+///
+/// # Examples
+///
+/// ```rust
+/// use extension_eyre::config::{HookBuilder, PanicReport};
+/// use std::sync::{Arc, Mutex};
+///
+/// #[derive(Debug, Default, PartialEq)]
+/// struct Inspected {
+///     payload: Option<String>,
+///     line: Option<u32>,
+///     extensions: usize,
+///     rendered: String,
+/// }
+///
+/// let inspected = Arc::new(Mutex::new(Inspected::default()));
+/// let sink = inspected.clone();
+///
+/// let (panic_hook, _) = HookBuilder::default()
+///     .panic_sink(move |report: &PanicReport<'_>| {
+///         *sink.lock().unwrap() = Inspected {
+///             payload: report.payload_str().map(str::to_owned),
+///             line: report.location().map(|location| location.line()),
+///             extensions: report.extensions().len(),
+///             rendered: report.to_string(),
+///         };
+///     })
+///     .into_hooks();
+/// panic_hook.install();
+///
+/// let line = line!() + 1;
+/// let _ = std::panic::catch_unwind(|| panic!("request {} failed", 7));
+///
+/// let inspected = inspected.lock().unwrap();
+/// assert_eq!(inspected.payload.as_deref(), Some("request 7 failed"));
+/// assert_eq!(inspected.line, Some(line));
+/// assert_eq!(inspected.extensions, 0);
+/// assert!(inspected.rendered.contains("The application panicked"));
+/// ```
+pub struct PanicReport<'a> {
+    inner: PanicReportInner<'a>,
+    panic_info: &'a std::panic::PanicHookInfo<'a>,
+    extensions: Rc<Extensions>,
+    config: &'a HandlerConfig,
+}
+
+impl<'a> PanicReport<'a> {
+    /// The extensions attached to this panic, see
+    /// [`set_panic_extension`](crate::set_panic_extension)
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// The panic message, if the panic payload is a string
+    pub fn payload_str(&self) -> Option<&str> {
+        let payload = self.panic_info.payload();
+
+        payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+    }
+
+    /// The location the panic originated from, if known
+    pub fn location(&self) -> Option<&std::panic::Location<'_>> {
+        self.panic_info.location()
+    }
+}
+
+impl Drop for PanicReport<'_> {
+    fn drop(&mut self) {
+        panic_extensions::restore(std::mem::take(&mut self.extensions));
+    }
+}
+
+impl fmt::Display for PanicReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[cfg(feature = "serde")]
+        if self.config.panic_format == PanicFormat::Json {
+            return crate::panic_format::write_json(self, &self.inner, f);
+        }
+
+        self.inner.fmt(f)?;
+
+        if self.extensions.is_empty() {
+            return Ok(());
+        }
+
+        match &self.config.panic_extensions_section {
+            Some(section) => {
+                f.write_str("\n\n")?;
+                section(&self.extensions, f)
+            }
+            None if self.config.display_extensions_section => {
+                write!(f, "\n\n{}", self.extensions.section(self.config))
+            }
+            None => Ok(()),
+        }
     }
 }
 
 /// An eyre reporting hook used to construct `EyreHandler`s
 pub struct EyreHook {
     inner: EyreHookInner,
+    custom: Option<Box<InnerHookCallback>>,
+    config: Arc<HandlerConfig>,
+}
+
+/// Settings shared by every `Handler` constructed from the same `EyreHook`
+pub(crate) struct HandlerConfig {
+    pub(crate) max_chain_depth: usize,
+    pub(crate) precedence: Precedence,
+    pub(crate) display_extensions_section: bool,
+    pub(crate) display_extension_provenance: bool,
+    pub(crate) compact_separator: String,
+    pub(crate) compact_extensions: bool,
+    pub(crate) crash_report_dir: Option<PathBuf>,
+    #[cfg(feature = "serde")]
+    pub(crate) panic_format: PanicFormat,
+    pub(crate) panic_extensions_section: Option<Box<PanicExtensionsSectionCallback>>,
+    pub(crate) capture_verbose_traces: bool,
+    pub(crate) error_code_base_url: Option<String>,
+    #[cfg(feature = "issue-url")]
+    pub(crate) issue_url: Option<String>,
+    #[cfg(feature = "issue-url")]
+    pub(crate) issue_metadata: Vec<(String, Box<crate::issue::IssueMetadataCallback>)>,
+    #[cfg(feature = "issue-url")]
+    pub(crate) issue_filter: Arc<IssueFilterExtCallback>,
+    #[cfg(feature = "uuid")]
+    pub(crate) generate_report_ids: bool,
+    #[cfg(feature = "time")]
+    pub(crate) capture_timestamps: bool,
+    pub(crate) capture_thread_info: bool,
+    pub(crate) capture_backtrace_extension: bool,
+    pub(crate) force_backtrace_extension: bool,
+    pub(crate) capture_process_info: bool,
+    pub(crate) process_arg_redaction: Option<Box<ProcessArgRedaction>>,
+    pub(crate) captured_env_vars: Vec<String>,
+    pub(crate) redacted_env_vars: Vec<String>,
+    #[cfg(feature = "capture-spantrace")]
+    pub(crate) capture_span_context: bool,
+    pub(crate) severity_styles: HashMap<crate::builtin::Severity, Style>,
+    pub(crate) extension_styles: ExtensionStyles,
+    pub(crate) formatters: Formatters,
+    pub(crate) defaults: Vec<(TypeId, Box<DefaultFn>)>,
+    pub(crate) extension_hooks: Vec<Box<ExtensionHook>>,
+    pub(crate) error_mappers: Vec<Box<ErrorMapper>>,
+    pub(crate) observers: Vec<Box<ReportObserver>>,
+    pub(crate) recent_errors: usize,
+    #[cfg(feature = "metrics")]
+    pub(crate) emit_metrics: bool,
+}
+
+/// Derives extensions for a new report from its error, see [`HookBuilder::extension_hook`].
+pub(crate) type ExtensionHook =
+    dyn Fn(&(dyn std::error::Error + 'static), &mut Extensions) + Send + Sync;
+
+/// Searches the chain of a new report, as deep as `max_depth`, for an error to derive an
+/// extension from, see [`HookBuilder::map_error_extension`].
+pub(crate) type ErrorMapper =
+    dyn Fn(&(dyn std::error::Error + 'static), &mut Extensions, usize) + Send + Sync;
+
+/// Decides which process arguments are redacted, see [`HookBuilder::redact_process_args`].
+pub(crate) type ProcessArgRedaction = dyn Fn(&str) -> bool + Send + Sync;
+
+/// Observes every new report, see [`HookBuilder::on_report`].
+pub(crate) type ReportObserver =
+    dyn Fn(&(dyn std::error::Error + 'static), &Extensions) + Send + Sync;
+
+/// Attaches a default extension to the extensions of a new report, see
+/// [`HookBuilder::default_extension`].
+pub(crate) type DefaultFn = dyn Fn(&mut Extensions) + Send + Sync;
+
+/// Formatter for an extension value, see [`HookBuilder::extension_formatter`].
+pub(crate) type FormatFn =
+    dyn Fn(&(dyn Any + Send + Sync), &mut fmt::Formatter<'_>) -> fmt::Result + Send + Sync;
+
+/// Formatters registered with [`HookBuilder::extension_formatter`], by extension type.
+pub(crate) type Formatters = HashMap<TypeId, Box<FormatFn>>;
+
+impl Default for HandlerConfig {
+    fn default() -> Self {
+        use crate::builtin::Severity;
+
+        Self {
+            max_chain_depth: chain::DEFAULT_MAX_CHAIN_DEPTH,
+            precedence: Precedence::default(),
+            display_extensions_section: true,
+            display_extension_provenance: false,
+            compact_separator: ": ".to_owned(),
+            compact_extensions: true,
+            crash_report_dir: None,
+            #[cfg(feature = "serde")]
+            panic_format: PanicFormat::Human,
+            panic_extensions_section: None,
+            capture_verbose_traces: true,
+            error_code_base_url: None,
+            #[cfg(feature = "issue-url")]
+            issue_url: None,
+            #[cfg(feature = "issue-url")]
+            issue_metadata: Vec::new(),
+            #[cfg(feature = "issue-url")]
+            issue_filter: Arc::new(|_, _| true),
+            #[cfg(feature = "uuid")]
+            generate_report_ids: false,
+            #[cfg(feature = "time")]
+            capture_timestamps: false,
+            capture_thread_info: false,
+            capture_backtrace_extension: false,
+            force_backtrace_extension: false,
+            capture_process_info: false,
+            process_arg_redaction: None,
+            captured_env_vars: Vec::new(),
+            redacted_env_vars: Vec::new(),
+            #[cfg(feature = "capture-spantrace")]
+            capture_span_context: false,
+            severity_styles: HashMap::from([
+                (Severity::Warning, Style::new().yellow()),
+                (Severity::Error, Style::new().red()),
+                (Severity::Fatal, Style::new().bright_red().bold()),
+            ]),
+            extension_styles: ExtensionStyles::default(),
+            formatters: HashMap::new(),
+            defaults: Vec::new(),
+            extension_hooks: Vec::new(),
+            error_mappers: Vec::new(),
+            observers: Vec::new(),
+            recent_errors: 0,
+            #[cfg(feature = "metrics")]
+            emit_metrics: false,
+        }
+    }
 }
 
+/// Callback creating the handlers wrapped by this crate's, see [`HookBuilder::inner_hook`]
+pub type InnerHookCallback = dyn Fn(&(dyn std::error::Error + 'static)) -> Box<dyn eyre::EyreHandler>
+    + Send
+    + Sync
+    + 'static;
+
+type HookFunc = Box<InnerHookCallback>;
+
 impl EyreHook {
     /// Installs self as the global eyre handling hook via `eyre::set_hook`
     pub fn install(self) -> Result<(), crate::eyre::InstallError> {
+        let mut installed = lock_installed();
+        crate::eyre::set_hook(self.into_eyre_hook())?;
+        *installed = true;
+        Ok(())
+    }
+
+    fn install_locked(self, installed: &mut bool) -> Result<(), InstallError> {
         crate::eyre::set_hook(self.into_eyre_hook())
+            .map_err(|_| InstallError::AlreadyInstalled { ours: false })?;
+        *installed = true;
+        Ok(())
     }
 
     /// Convert the self into the boxed type expected by `eyre::set_hook`.
-    pub fn into_eyre_hook(
-        self,
-    ) -> Box<
-        dyn Fn(&(dyn std::error::Error + 'static)) -> Box<dyn eyre::EyreHandler>
-            + Send
-            + Sync
-            + 'static,
-    > {
-        let f = self.inner.into_eyre_hook();
+    pub fn into_eyre_hook(self) -> HookFunc {
+        let f = match self.custom {
+            Some(custom) => custom,
+            None => self.inner.into_eyre_hook(),
+        };
+        let config = self.config;
+        // the process doesn't change over its lifetime, so it's captured once for every report
+        let process = if config.capture_process_info {
+            let mut process = crate::builtin::ProcessInfo::current();
+            if let Some(redact) = &config.process_arg_redaction {
+                for arg in process.args.iter_mut().filter(|arg| redact(arg)) {
+                    *arg = "<redacted>".to_owned();
+                }
+            }
+            Some(process)
+        } else {
+            None
+        };
         Box::new(move |e| {
+            // the inner hook walks `e`'s sources without bounds, so hand it a finite copy when
+            // the chain is too deep or cyclic
+            let inner = match chain::truncate(e, config.max_chain_depth) {
+                Some((truncated, _)) => f(&truncated),
+                None => f(e),
+            };
+
+            let mut extensions = Extensions::new();
+
+            for (_, insert) in &config.defaults {
+                insert(&mut extensions);
+            }
+
+            scope::attach_to(&mut extensions, &config.precedence);
+
+            // quiet reports must not reach user code through the hook, classifiers included
+            let quiet = quiet::is_active();
+
+            if !quiet && !config.error_mappers.is_empty() {
+                let mut mapped = Extensions::new();
+                for mapper in &config.error_mappers {
+                    mapper(e, &mut mapped, config.max_chain_depth);
+                }
+                extensions.merge_from(Source::Classifier, mapped, &config.precedence);
+            }
+
+            if !quiet {
+                for hook in &config.extension_hooks {
+                    let mut classified = Extensions::new();
+                    hook(e, &mut classified);
+                    extensions.merge_from(Source::Classifier, classified, &config.precedence);
+                }
+            }
+
+            #[cfg(feature = "time")]
+            if config.capture_timestamps {
+                let now = crate::builtin::CreatedAt(time::OffsetDateTime::now_utc());
+                extensions.insert_from(Source::Default, now);
+            }
+
+            if config.capture_thread_info {
+                let thread = crate::builtin::ThreadInfo::current();
+                extensions.insert_from(Source::Default, thread);
+            }
+
+            if config.capture_backtrace_extension {
+                let force = config.force_backtrace_extension;
+                if let Some(backtrace) = crate::builtin::CapturedBacktrace::capture(force) {
+                    extensions.insert_from(Source::Default, backtrace);
+                }
+            }
+
+            if let Some(process) = &process {
+                extensions.insert_from(Source::Default, process.clone());
+            }
+
+            if !config.captured_env_vars.is_empty() {
+                let env = crate::builtin::CapturedEnv::capture(&config.captured_env_vars);
+                extensions.insert_from(Source::Default, env);
+            }
+
+            #[cfg(feature = "capture-spantrace")]
+            if config.capture_span_context {
+                if let Some(span) = crate::builtin::SpanContext::current() {
+                    extensions.insert_from(Source::Default, span);
+                }
+            }
+
+            #[cfg(feature = "uuid")]
+            {
+                use crate::builtin::ReportId;
+
+                if config.generate_report_ids && extensions.get::<ReportId>().is_none() {
+                    extensions.insert_from(Source::Default, ReportId(uuid::Uuid::new_v4()));
+                }
+            }
+
+            if quiet {
+                extensions.insert(crate::ObserversBypassed);
+            } else {
+                for observer in &config.observers {
+                    // a failing observer must not fail creating the report
+                    let observe = || observer(e, &extensions);
+                    let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(observe));
+                }
+
+                #[cfg(feature = "metrics")]
+                if config.emit_metrics {
+                    count_report(&extensions);
+                }
+
+                if config.recent_errors > 0 {
+                    let depth = config.max_chain_depth;
+                    crate::recent::record(config.recent_errors, e, &extensions, depth);
+                }
+            }
+
+            let traces = if config.capture_verbose_traces {
+                trace::Traces::capture(inner.as_ref())
+            } else {
+                trace::Traces::default()
+            };
+
             Box::new(crate::Handler {
-                inner: f(e),
-                extensions: Extensions::new(),
+                inner,
+                traces,
+                location: None,
+                extensions,
+                meta: Default::default(),
+                frames: Default::default(),
+                sections: Vec::new(),
+                config: config.clone(),
             })
         })
     }
 }
 
+/// Counts a new report with `extensions`, see [`HookBuilder::emit_metrics`].
+#[cfg(feature = "metrics")]
+fn count_report(extensions: &Extensions) {
+    use crate::builtin::{ErrorCode, Severity};
+
+    let error_code = extensions
+        .get::<ErrorCode>()
+        .map_or("unknown", |code| code.0);
+    let counter = match extensions.get::<Severity>() {
+        Some(severity) => {
+            let severity = severity.to_string().to_lowercase();
+            metrics::counter!(
+                "extension_eyre.reports",
+                "error_code" => error_code,
+                "severity" => severity
+            )
+        }
+        None => metrics::counter!("extension_eyre.reports", "error_code" => error_code),
+    };
+    counter.increment(1);
+}
+
 /// Callback for filtering issue url generation in error reports
 #[cfg(feature = "issue-url")]
 #[cfg_attr(docsrs, doc(cfg(feature = "issue-url")))]
 pub type IssueFilterCallback = dyn Fn(crate::ErrorKind<'_>) -> bool + Send + Sync + 'static;
+
+/// Callback for filtering issue url generation in error reports by their extensions too, see
+/// [`HookBuilder::issue_filter_ext`]
+#[cfg(feature = "issue-url")]
+#[cfg_attr(docsrs, doc(cfg(feature = "issue-url")))]
+pub type IssueFilterExtCallback =
+    dyn Fn(crate::ErrorKind<'_>, Option<&Extensions>) -> bool + Send + Sync + 'static;
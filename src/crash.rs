@@ -0,0 +1,122 @@
+//! Crash report files, see [`HookBuilder::capture_crash_reports`]
+//!
+//! [`HookBuilder::capture_crash_reports`]: crate::config::HookBuilder::capture_crash_reports
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::HandlerConfig;
+use crate::extensions::Extensions;
+
+// tells apart the files of reports written in the same nanosecond, e.g. by concurrent panics
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Writes `rendered` and its metadata to a new file in `dir`, returning its path.
+pub(crate) fn write(
+    dir: &Path,
+    rendered: &str,
+    config: &HandlerConfig,
+    extensions: &Extensions,
+) -> io::Result<PathBuf> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let name = format!(
+        "crash-{}-{}-{}.txt",
+        std::process::id(),
+        timestamp.as_nanos(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    );
+    let path = dir.join(name);
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)?;
+    writeln!(file, "{}", strip_ansi(rendered.trim_end()))?;
+    writeln!(file, "\nMetadata:")?;
+    writeln!(file, "timestamp: {}", timestamp.as_secs())?;
+    writeln!(file, "pid: {}", std::process::id())?;
+    for (key, value) in metadata(config, extensions) {
+        writeln!(file, "{}: {}", key, value)?;
+    }
+    file.flush()?;
+
+    Ok(path)
+}
+
+/// Writes a crash report for a panic or report, reporting on stderr where it went.
+///
+/// Never panics, since it runs inside the panic hook.
+pub(crate) fn write_or_report(
+    dir: &Path,
+    rendered: &str,
+    config: &HandlerConfig,
+    extensions: &Extensions,
+) -> io::Result<PathBuf> {
+    let written = write(dir, rendered, config, extensions);
+    let _ = match &written {
+        Ok(path) => writeln!(io::stderr(), "Crash report written to {}", path.display()),
+        Err(error) => writeln!(
+            io::stderr(),
+            "Could not write a crash report to {}: {}",
+            dir.display(),
+            error
+        ),
+    };
+
+    written
+}
+
+fn metadata(config: &HandlerConfig, extensions: &Extensions) -> Vec<(String, String)> {
+    let issue = issue_metadata(config, extensions);
+    issue.into_iter().chain(serialized(extensions)).collect()
+}
+
+#[cfg(feature = "issue-url")]
+fn issue_metadata(config: &HandlerConfig, extensions: &Extensions) -> Vec<(String, String)> {
+    config
+        .issue_metadata
+        .iter()
+        .filter_map(|(key, value)| Some((key.clone(), value(extensions)?)))
+        .collect()
+}
+
+#[cfg(not(feature = "issue-url"))]
+fn issue_metadata(_: &HandlerConfig, _: &Extensions) -> Vec<(String, String)> {
+    Vec::new()
+}
+
+#[cfg(feature = "serde")]
+fn serialized(extensions: &Extensions) -> Option<(String, String)> {
+    let json = extensions.to_json_value().to_string();
+    Some(("extensions".to_owned(), json))
+}
+
+#[cfg(not(feature = "serde"))]
+fn serialized(_: &Extensions) -> Option<(String, String)> {
+    None
+}
+
+// reports are colored for terminals, which files aren't
+fn strip_ansi(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // skip up to the letter ending the escape sequence
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+
+    stripped
+}
@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::compact::Compact;
+use crate::extensions::Extension;
+use crate::eyre::Report;
+
+/// Tells apart reports of a failure seen recently, so retry loops don't log it over and over.
+///
+/// # Details
+///
+/// Reports describe the same failure when they have the same
+/// [`fingerprint`](Extension::fingerprint): the messages of their source chain and their
+/// [`ErrorCode`](crate::builtin::ErrorCode). A failure is reported again once `window` has passed
+/// since it was last reported.
+///
+/// # Examples
+///
+/// ```rust
+/// use extension_eyre::builtin::ErrorCode;
+/// use extension_eyre::{eyre::eyre, ExtensionExt, ReportDeduper};
+/// use std::time::Duration;
+///
+/// extension_eyre::install().unwrap();
+///
+/// let deduper = ReportDeduper::new(Duration::from_millis(200));
+///
+/// assert!(deduper.should_report(&eyre!("connection reset")));
+/// assert!(!deduper.should_report(&eyre!("connection reset")));
+/// assert!(deduper.should_report(&eyre!("connection reset").extension(ErrorCode("E42"))));
+/// assert!(deduper.should_report(&eyre!("connection reset").extension(ErrorCode("E43"))));
+///
+/// std::thread::sleep(Duration::from_millis(300));
+/// assert!(deduper.should_report(&eyre!("connection reset")));
+/// ```
+#[derive(Debug)]
+pub struct ReportDeduper {
+    window: Duration,
+    seen: Mutex<HashMap<u64, Seen>>,
+}
+
+#[derive(Debug)]
+struct Seen {
+    reported_at: Instant,
+    repeats: usize,
+}
+
+impl ReportDeduper {
+    /// Creates a deduper reporting each failure at most once per `window`.
+    pub fn new(window: Duration) -> Self {
+        ReportDeduper {
+            window,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns whether `report` should be reported, i.e. its failure wasn't reported within the
+    /// window.
+    pub fn should_report(&self, report: &Report) -> bool {
+        self.observe(report) == 0
+    }
+
+    /// Prints `report` to stderr the first time its failure is seen within the window, and a
+    /// one-line summary of it afterwards.
+    ///
+    /// See [`log_to`](ReportDeduper::log_to).
+    pub fn log(&self, report: &Report) {
+        let _ = self.log_to(report, &mut io::stderr());
+    }
+
+    /// Writes `report` to `writer` the first time its failure is seen within the window, and a
+    /// one-line summary of it afterwards.
+    ///
+    /// The summary is the report's [`Compact`] rendering followed by how many times it was
+    /// repeated since it was last written in full.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, ReportDeduper};
+    /// use std::time::Duration;
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let deduper = ReportDeduper::new(Duration::from_secs(60));
+    /// let mut log = Vec::new();
+    /// for _ in 0..3 {
+    ///     deduper.log_to(&eyre!("connection reset"), &mut log).unwrap();
+    /// }
+    ///
+    /// let log = String::from_utf8(log).unwrap();
+    /// let lines: Vec<_> = log.lines().collect();
+    /// assert!(lines[0].starts_with("Error:"));
+    /// assert!(lines.contains(&"connection reset (repeated 1 times)"));
+    /// assert_eq!(lines.last(), Some(&"connection reset (repeated 2 times)"));
+    /// ```
+    pub fn log_to(&self, report: &Report, writer: &mut dyn Write) -> io::Result<()> {
+        match self.observe(report) {
+            0 => writeln!(writer, "Error: {:?}", report),
+            repeats => writeln!(writer, "{} (repeated {} times)", Compact(report), repeats),
+        }
+    }
+
+    // records `report`, returning how many times its failure was repeated within the window
+    fn observe(&self, report: &Report) -> usize {
+        let fingerprint = report.fingerprint();
+        let now = Instant::now();
+        let mut seen = self.seen.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if let Some(seen) = seen.get_mut(&fingerprint) {
+            if now.duration_since(seen.reported_at) < self.window {
+                seen.repeats += 1;
+                return seen.repeats;
+            }
+        }
+
+        // forget failures whose window has passed, so the map stays small
+        let window = self.window;
+        seen.retain(|_, seen| now.duration_since(seen.reported_at) < window);
+        seen.insert(
+            fingerprint,
+            Seen {
+                reported_at: now,
+                repeats: 0,
+            },
+        );
+
+        0
+    }
+}
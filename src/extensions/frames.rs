@@ -0,0 +1,152 @@
+use std::collections::{btree_map, BTreeMap};
+use std::error::Error;
+use std::fmt;
+use std::iter::Rev;
+
+use super::{Extensions, NestedReport};
+use crate::chain::Chain;
+use crate::eyre::Report;
+
+/// Extensions attached to the frames of a report's chain with
+/// [`ExtensionExt::frame_extension`](crate::ExtensionExt::frame_extension).
+///
+/// Frames are keyed by their distance from the innermost error, which stays the same as the
+/// report is wrapped, unlike their index in the chain.
+#[derive(Default)]
+pub(crate) struct Frames {
+    by_depth: BTreeMap<usize, Extensions>,
+}
+
+impl Frames {
+    pub(crate) fn insert<T: Send + Sync + 'static>(&mut self, depth: usize, value: T) {
+        self.by_depth.entry(depth).or_default().insert(value);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.by_depth.is_empty()
+    }
+
+    /// The value of type `T` attached to the outermost frame holding one.
+    pub(crate) fn get<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.by_depth
+            .values()
+            .rev()
+            .find_map(|extensions| extensions.get::<T>())
+    }
+
+    pub(crate) fn get_mut<T: Send + Sync + 'static>(&mut self) -> Option<&mut T> {
+        self.by_depth
+            .values_mut()
+            .rev()
+            .find_map(|extensions| extensions.get_mut::<T>())
+    }
+
+    /// The extensions attached to the frame at `index` in a chain of `len` errors.
+    pub(crate) fn at(&self, index: usize, len: usize) -> Option<&Extensions> {
+        let depth = len.checked_sub(index + 1)?;
+        self.by_depth.get(&depth)
+    }
+
+    /// The frames holding extensions, outermost first, for a chain of `len` errors.
+    pub(crate) fn iter(&self, len: usize) -> FrameExtensions<'_> {
+        FrameExtensions {
+            inner: Some(self.by_depth.iter().rev()),
+            len,
+        }
+    }
+}
+
+/// Iterator over the frames of a report holding extensions, returned by
+/// [`Extension::frame_extensions`](crate::Extension::frame_extensions).
+///
+/// Yields the index of each frame in the report's chain, `0` being the outermost error, with the
+/// extensions attached to it, outermost first.
+pub struct FrameExtensions<'a> {
+    inner: Option<Rev<btree_map::Iter<'a, usize, Extensions>>>,
+    len: usize,
+}
+
+impl<'a> FrameExtensions<'a> {
+    pub(crate) fn empty() -> Self {
+        FrameExtensions {
+            inner: None,
+            len: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for FrameExtensions<'a> {
+    type Item = (usize, &'a Extensions);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = self.len;
+        // frames below a truncated chain have no index
+        self.inner.as_mut()?.find_map(|(depth, extensions)| {
+            let index = len.checked_sub(depth + 1)?;
+            Some((index, extensions))
+        })
+    }
+}
+
+impl fmt::Debug for FrameExtensions<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrameExtensions").finish()
+    }
+}
+
+/// Iterator over the errors of a report's chain paired with their extensions, returned by
+/// [`Extension::chain_with_extensions`](crate::Extension::chain_with_extensions).
+///
+/// See there for which extensions go with which error.
+pub struct ChainWithExtensions<'a> {
+    chain: Chain<'a>,
+    index: usize,
+    len: usize,
+    // The handler of the report the current error belongs to, and the index its chain starts at.
+    scope: Option<(&'a crate::Handler, usize)>,
+}
+
+impl<'a> ChainWithExtensions<'a> {
+    pub(crate) fn new(report: &'a Report) -> Self {
+        let handler = report.handler().downcast_ref::<crate::Handler>();
+
+        ChainWithExtensions {
+            chain: Chain::of_report(report),
+            index: 0,
+            len: Chain::of_report(report).count(),
+            scope: handler.map(|handler| (handler, 0)),
+        }
+    }
+}
+
+impl<'a> Iterator for ChainWithExtensions<'a> {
+    type Item = (&'a (dyn Error + 'static), Option<&'a Extensions>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let error = self.chain.next()?;
+        let index = self.index;
+        self.index += 1;
+
+        // the errors after a nested report belong to it, with frames counted from its error
+        if let Some(nested) = error.downcast_ref::<NestedReport>() {
+            let handler = nested.report().handler().downcast_ref::<crate::Handler>();
+            self.scope = handler.map(|handler| (handler, index));
+        }
+
+        let extensions = self.scope.and_then(|(handler, start)| {
+            if index == start {
+                Some(&handler.extensions)
+            } else {
+                handler.frames.at(index - start, self.len - start)
+            }
+        });
+
+        Some((error, extensions))
+    }
+}
+
+impl fmt::Debug for ChainWithExtensions<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChainWithExtensions").finish()
+    }
+}
@@ -1,9 +1,136 @@
 use std::any::{Any, TypeId};
-use std::collections::HashMap;
+use std::collections::{hash_map, HashMap};
 use std::fmt;
 use std::hash::{BuildHasherDefault, Hasher};
+use std::iter::FromIterator;
+use std::marker::PhantomData;
+use std::panic::Location;
 
-type AnyMap = HashMap<TypeId, Box<dyn Any + Send + Sync>, BuildHasherDefault<IdHasher>>;
+use owo_colors::OwoColorize;
+
+use crate::config::{Formatters, HandlerConfig, Precedence, Source};
+
+#[cfg(feature = "serde")]
+mod export;
+#[cfg(feature = "serde")]
+use export::Serializable;
+
+type AnyMap = HashMap<TypeId, Slot, BuildHasherDefault<IdHasher>>;
+type KeyedMap = HashMap<(TypeId, &'static str), Slot>;
+type CloneFn = fn(&(dyn Any + Send + Sync)) -> Box<dyn Any + Send + Sync>;
+type DisplayFn = fn(&(dyn Any + Send + Sync), &mut fmt::Formatter<'_>) -> fmt::Result;
+
+// A stored extension. The name of its type is kept next to the value so the map can describe
+// its contents without knowing the concrete types.
+//
+// `value` is the most recent value of the type; values it displaced through `push` are kept in
+// `earlier`, oldest first.
+struct Slot {
+    value: Box<dyn Any + Send + Sync>,
+    earlier: Vec<Box<dyn Any + Send + Sync>>,
+    type_name: &'static str,
+    source: Source,
+    // Where `value` was attached, for values attached through `ExtensionExt`.
+    location: Option<&'static Location<'static>>,
+    // Set for values inserted with `insert_cloneable`.
+    clone: Option<CloneFn>,
+    // Set for values inserted with `insert_display`.
+    display: Option<DisplayFn>,
+    // Set for values inserted with `insert_redacted`.
+    redacted: bool,
+    // Set for values inserted with `insert_serializable`.
+    #[cfg(feature = "serde")]
+    serializable: Option<Serializable>,
+}
+
+impl Slot {
+    fn new<T: Send + Sync + 'static>(value: T) -> Slot {
+        Slot::from_source(Source::Explicit, value)
+    }
+
+    fn from_source<T: Send + Sync + 'static>(source: Source, value: T) -> Slot {
+        Slot {
+            value: Box::new(value),
+            earlier: Vec::new(),
+            type_name: std::any::type_name::<T>(),
+            source,
+            location: None,
+            clone: None,
+            display: None,
+            redacted: false,
+            #[cfg(feature = "serde")]
+            serializable: None,
+        }
+    }
+
+    // For values whose concrete type isn't known, see `Extensions::insert_boxed`.
+    fn from_boxed(value: Box<dyn Any + Send + Sync>) -> Slot {
+        Slot {
+            value,
+            earlier: Vec::new(),
+            type_name: "<unknown>",
+            source: Source::Explicit,
+            location: None,
+            clone: None,
+            display: None,
+            redacted: false,
+            #[cfg(feature = "serde")]
+            serializable: None,
+        }
+    }
+
+    fn push<T: Send + Sync + 'static>(&mut self, value: T) {
+        let previous = std::mem::replace(&mut self.value, Box::new(value));
+        self.earlier.push(previous);
+        self.location = None;
+    }
+
+    // Every stored value, oldest first.
+    fn values(&self) -> impl Iterator<Item = &(dyn Any + Send + Sync)> {
+        self.earlier
+            .iter()
+            .chain(std::iter::once(&self.value))
+            .map(|value| &**value)
+    }
+
+    // A deep copy of the slot, if it was inserted with `insert_cloneable`.
+    fn try_clone(&self) -> Option<Slot> {
+        let clone = self.clone?;
+
+        Some(Slot {
+            value: clone(&*self.value),
+            earlier: self.earlier.iter().map(|value| clone(&**value)).collect(),
+            type_name: self.type_name,
+            source: self.source,
+            location: self.location,
+            clone: self.clone,
+            display: self.display,
+            redacted: self.redacted,
+            #[cfg(feature = "serde")]
+            serializable: self.serializable,
+        })
+    }
+
+    // Whether the value is kept out of rendered and exported output.
+    fn is_redacted(&self, type_id: TypeId) -> bool {
+        self.redacted || crate::redaction::is_redacted(type_id, self.type_name)
+    }
+
+    fn downcast<T: 'static>(self) -> Option<T> {
+        (self.value as Box<dyn Any + 'static>)
+            .downcast()
+            .ok()
+            .map(|boxed| *boxed)
+    }
+
+    fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+        (&*self.value as &(dyn Any + 'static)).downcast_ref()
+    }
+
+    fn downcast_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        (&mut *self.value as &mut (dyn Any + 'static)).downcast_mut()
+    }
+}
 
 // With TypeIds as keys, there's no need to hash them. They are already hashes
 // themselves, coming from the compiler. The IdHasher just holds the u64 of
@@ -36,19 +163,73 @@ pub struct Extensions {
     // If extensions are never used, no need to carry around an empty HashMap.
     // That's 3 words. Instead, this is only 1 word.
     map: Option<Box<AnyMap>>,
+    // Values stored under a `Key`, kept apart so type based lookups never see them.
+    keyed: Option<Box<KeyedMap>>,
+}
+
+/// A name for storing a value of type `T` in [`Extensions`] next to other values of the same
+/// type.
+///
+/// Keys with the same name but a different `T` refer to different entries.
+///
+/// # Example
+///
+/// ```
+/// use extension_eyre::extensions::Key;
+///
+/// const REQUEST_PATH: Key<String> = Key::new("request_path");
+///
+/// assert_eq!(REQUEST_PATH.name(), "request_path");
+/// ```
+pub struct Key<T> {
+    name: &'static str,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Key<T> {
+    /// Create a key with the given name.
+    pub const fn new(name: &'static str) -> Self {
+        Key {
+            name,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The name of this key.
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl<T> Clone for Key<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Key<T> {}
+
+impl<T> fmt::Debug for Key<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Key").field(&self.name).finish()
+    }
 }
 
 impl Extensions {
     /// Create an empty `Extensions`.
     #[inline]
     pub fn new() -> Extensions {
-        Extensions { map: None }
+        Extensions {
+            map: None,
+            keyed: None,
+        }
     }
 
     /// Insert a type into this `Extensions`.
     ///
     /// If a extension of this type already existed, it will
-    /// be returned.
+    /// be returned. Values added with [`push`](Extensions::push) are discarded, so `val` becomes
+    /// the only value of its type.
     ///
     /// # Example
     ///
@@ -62,169 +243,1678 @@ impl Extensions {
     pub fn insert<T: Send + Sync + 'static>(&mut self, val: T) -> Option<T> {
         self.map
             .get_or_insert_with(|| Box::new(HashMap::default()))
-            .insert(TypeId::of::<T>(), Box::new(val))
-            .and_then(|boxed| {
-                (boxed as Box<dyn Any + 'static>)
-                    .downcast()
-                    .ok()
-                    .map(|boxed| *boxed)
-            })
+            .insert(TypeId::of::<T>(), Slot::new(val))
+            .and_then(Slot::downcast)
     }
 
-    /// Get a reference to a type previously inserted on this `Extensions`.
+    /// Insert a serializable type into this `Extensions`.
+    ///
+    /// Works like [`insert`](Extensions::insert), but also records how to serialize the value,
+    /// so it is included when the `Extensions` are serialized. Values pushed later with
+    /// [`push`](Extensions::push) are serialized along with it; a later plain `insert` replaces
+    /// it with a value that isn't serialized.
     ///
     /// # Example
     ///
     /// ```
     /// # use extension_eyre::extensions::Extensions;
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// pub struct Upstream {
+    ///     host: String,
+    ///     status: Status,
+    /// }
+    ///
+    /// #[derive(Serialize)]
+    /// pub struct Status {
+    ///     code: u16,
+    /// }
+    ///
+    /// // not serializable
+    /// pub struct Connection;
+    ///
     /// let mut ext = Extensions::new();
-    /// assert!(ext.get::<i32>().is_none());
-    /// ext.insert(5i32);
+    /// ext.insert_serializable(Upstream { host: "db".into(), status: Status { code: 502 } });
+    /// ext.insert_serializable(3u8);
+    /// ext.insert(Connection);
     ///
-    /// assert_eq!(ext.get::<i32>(), Some(&5i32));
+    /// let json = serde_json::to_value(&ext).unwrap();
+    /// let upstream = std::any::type_name::<Upstream>();
+    ///
+    /// assert_eq!(json.as_object().unwrap().len(), 2);
+    /// assert_eq!(json[upstream], serde_json::json!({ "host": "db", "status": { "code": 502 } }));
+    /// assert_eq!(json["u8"], 3);
+    ///
+    /// ext.push(4u8);
+    /// assert_eq!(serde_json::to_value(&ext).unwrap()["u8"], serde_json::json!([3, 4]));
     /// ```
-    pub fn get<T: Send + Sync + 'static>(&self) -> Option<&T> {
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn insert_serializable<T>(&mut self, val: T) -> Option<T>
+    where
+        T: serde::Serialize + Send + Sync + 'static,
+    {
+        let mut slot = Slot::new(val);
+        slot.serializable = Some(Serializable::of::<T>());
+
         self.map
-            .as_ref()
-            .and_then(|map| map.get(&TypeId::of::<T>()))
-            .and_then(|boxed| (&**boxed as &(dyn Any + 'static)).downcast_ref())
+            .get_or_insert_with(|| Box::new(HashMap::default()))
+            .insert(TypeId::of::<T>(), slot)
+            .and_then(Slot::downcast)
     }
 
-    /// Get a mutable reference to a type previously inserted on this `Extensions`.
+    /// Insert a cloneable type into this `Extensions`.
+    ///
+    /// Works like [`insert`](Extensions::insert), but also records how to clone the value, so
+    /// it is copied by [`clone_map`](Extensions::clone_map). Values pushed later with
+    /// [`push`](Extensions::push) are cloned along with it; a later plain `insert` replaces it
+    /// with a value that isn't cloned.
     ///
     /// # Example
     ///
     /// ```
     /// # use extension_eyre::extensions::Extensions;
     /// let mut ext = Extensions::new();
-    /// ext.insert(String::from("Hello"));
-    /// ext.get_mut::<String>().unwrap().push_str(" World");
+    /// ext.insert_cloneable(vec![1, 2]);
     ///
-    /// assert_eq!(ext.get::<String>().unwrap(), "Hello World");
+    /// let mut copy = ext.clone_map();
+    /// copy.get_mut::<Vec<i32>>().unwrap().push(3);
+    ///
+    /// assert_eq!(ext.get::<Vec<i32>>(), Some(&vec![1, 2]));
+    /// assert_eq!(copy.get::<Vec<i32>>(), Some(&vec![1, 2, 3]));
     /// ```
-    pub fn get_mut<T: Send + Sync + 'static>(&mut self) -> Option<&mut T> {
+    pub fn insert_cloneable<T: Clone + Send + Sync + 'static>(&mut self, val: T) -> Option<T> {
+        let mut slot = Slot::new(val);
+        slot.clone = Some(clone_value::<T>);
+
         self.map
-            .as_mut()
-            .and_then(|map| map.get_mut(&TypeId::of::<T>()))
-            .and_then(|boxed| (&mut **boxed as &mut (dyn Any + 'static)).downcast_mut())
+            .get_or_insert_with(|| Box::new(HashMap::default()))
+            .insert(TypeId::of::<T>(), slot)
+            .and_then(Slot::downcast)
     }
 
-    /// Remove a type from this `Extensions`.
+    /// Insert a displayable type into this `Extensions`.
+    ///
+    /// Works like [`insert`](Extensions::insert), but also records how to display the value, so
+    /// it is rendered with its [`Display`](fmt::Display) implementation in the `Extensions:`
+    /// section of a report's `{:?}` output. Other extensions are listed there by type name only.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use extension_eyre::{eyre::eyre, Extension, ExtensionExt};
+    /// use std::any::type_name;
+    ///
+    /// pub struct Connection;
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let mut report = eyre!("query failed").extension(Connection);
+    /// report.extensions_mut().unwrap().insert_display(502u16);
+    ///
+    /// let rendered = format!("{:?}", report);
+    /// let section = &rendered[rendered.find("Extensions:").unwrap()..];
+    /// assert_eq!(
+    ///     section,
+    ///     format!("Extensions:\n   {}\n   u16: 502", type_name::<Connection>())
+    /// );
+    /// ```
+    pub fn insert_display<T>(&mut self, val: T) -> Option<T>
+    where
+        T: fmt::Display + Send + Sync + 'static,
+    {
+        let mut slot = Slot::new(val);
+        slot.display = Some(display_value::<T>);
+
+        self.map
+            .get_or_insert_with(|| Box::new(HashMap::default()))
+            .insert(TypeId::of::<T>(), slot)
+            .and_then(Slot::downcast)
+    }
+
+    /// Insert a sensitive type into this `Extensions`.
+    ///
+    /// Works like [`insert`](Extensions::insert), but the value is kept out of every rendered or
+    /// exported form of the map: the `Extensions:` section of a report shows it as
+    /// `type: <redacted>`, and it is left out of serialized extensions and issue url metadata.
+    /// It can still be read with [`get`](Extensions::get). A later plain `insert` replaces it
+    /// with a value that isn't redacted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use extension_eyre::{eyre::eyre, Extension};
+    /// use std::any::type_name;
+    /// use std::fmt;
+    ///
+    /// pub struct AuthToken(String);
+    ///
+    /// impl fmt::Display for AuthToken {
+    ///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    ///         f.write_str(&self.0)
+    ///     }
+    /// }
+    ///
+    /// extension_eyre::config::HookBuilder::default()
+    ///     .extension_formatter(|token: &AuthToken, f| write!(f, "{}", token))
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// let mut report = eyre!("upstream rejected the request");
+    /// let extensions = report.extensions_mut().unwrap();
+    /// extensions.insert_redacted(AuthToken("hunter2".to_owned()));
+    /// assert!(extensions.is_redacted::<AuthToken>());
+    ///
+    /// for output in [format!("{:?}", report), format!("{:#?}", report)] {
+    ///     assert!(!output.contains("hunter2"));
+    ///     assert!(output.contains(&format!("{}: <redacted>", type_name::<AuthToken>())));
+    /// }
+    ///
+    /// assert_eq!(report.extension_ref::<AuthToken>().unwrap().0, "hunter2");
+    /// ```
+    pub fn insert_redacted<T: Send + Sync + 'static>(&mut self, val: T) -> Option<T> {
+        let mut slot = Slot::new(val);
+        slot.redacted = true;
+
+        self.map
+            .get_or_insert_with(|| Box::new(HashMap::default()))
+            .insert(TypeId::of::<T>(), slot)
+            .and_then(Slot::downcast)
+    }
+
+    /// Whether the extension of type `T` is kept out of rendered and exported output.
+    ///
+    /// This is the case for values inserted with
+    /// [`insert_redacted`](Extensions::insert_redacted), and for every type the
+    /// [redaction policy](crate::config::HookBuilder::redaction_policy) redacts, whether or not
+    /// a `T` is stored.
+    pub fn is_redacted<T: Send + Sync + 'static>(&self) -> bool {
+        let type_id = TypeId::of::<T>();
+
+        match self.map.as_ref().and_then(|map| map.get(&type_id)) {
+            Some(slot) => slot.is_redacted(type_id),
+            None => crate::redaction::is_redacted(type_id, std::any::type_name::<T>()),
+        }
+    }
+
+    /// Create a copy of this `Extensions` holding a clone of every extension inserted with
+    /// [`insert_cloneable`](Extensions::insert_cloneable).
     ///
-    /// If a extension of this type existed, it will be returned.
+    /// Other extensions are skipped; use [`try_clone_map`](Extensions::try_clone_map) to fail
+    /// on them instead.
     ///
     /// # Example
     ///
     /// ```
     /// # use extension_eyre::extensions::Extensions;
+    /// // not cloneable
+    /// pub struct Connection;
+    ///
     /// let mut ext = Extensions::new();
-    /// ext.insert(5i32);
-    /// assert_eq!(ext.remove::<i32>(), Some(5i32));
-    /// assert!(ext.get::<i32>().is_none());
+    /// ext.insert_cloneable(String::from("GET /api/users"));
+    /// ext.insert_cloneable(1u8);
+    /// ext.push(2u8);
+    /// ext.insert(Connection);
+    ///
+    /// let copy = ext.clone_map();
+    /// assert_eq!(copy.len(), 2);
+    /// assert_eq!(copy.get::<String>().unwrap(), "GET /api/users");
+    /// assert_eq!(copy.get_all::<u8>().collect::<Vec<_>>(), [&1, &2]);
+    /// assert!(copy.get::<Connection>().is_none());
+    /// assert!(ext.get::<Connection>().is_some());
     /// ```
-    pub fn remove<T: Send + Sync + 'static>(&mut self) -> Option<T> {
-        self.map
-            .as_mut()
-            .and_then(|map| map.remove(&TypeId::of::<T>()))
-            .and_then(|boxed| {
-                (boxed as Box<dyn Any + 'static>)
-                    .downcast()
-                    .ok()
-                    .map(|boxed| *boxed)
-            })
+    pub fn clone_map(&self) -> Extensions {
+        Extensions {
+            map: self.map.as_ref().map(|map| {
+                Box::new(
+                    map.iter()
+                        .filter_map(|(type_id, slot)| Some((*type_id, slot.try_clone()?)))
+                        .collect(),
+                )
+            }),
+            keyed: self.keyed.as_ref().map(|keyed| {
+                Box::new(
+                    keyed
+                        .iter()
+                        .filter_map(|(key, slot)| Some((*key, slot.try_clone()?)))
+                        .collect(),
+                )
+            }),
+        }
     }
 
-    /// Clear the `Extensions` of all inserted extensions.
+    /// Create a copy of this `Extensions` like [`clone_map`](Extensions::clone_map), failing if
+    /// any extension wasn't inserted with [`insert_cloneable`](Extensions::insert_cloneable).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// pub struct Connection;
+    ///
+    /// let mut ext = Extensions::new();
+    /// ext.insert_cloneable(1u8);
+    /// assert_eq!(ext.try_clone_map().unwrap().get::<u8>(), Some(&1u8));
+    ///
+    /// ext.insert(Connection);
+    /// let error = ext.try_clone_map().unwrap_err();
+    /// assert_eq!(error.type_name(), std::any::type_name::<Connection>());
+    /// ```
+    pub fn try_clone_map(&self) -> Result<Extensions, NotCloneable> {
+        let uncloneable = self
+            .slots()
+            .map(|(_, slot)| slot)
+            .find(|slot| slot.clone.is_none());
+
+        match uncloneable {
+            Some(slot) => Err(NotCloneable {
+                type_name: slot.type_name,
+            }),
+            None => Ok(self.clone_map()),
+        }
+    }
+
+    /// Insert a boxed value under `type_id`, without knowing its concrete type.
+    ///
+    /// Works like [`insert`](Extensions::insert), returning the value it replaces. Meant for
+    /// moving values between maps together with [`drain`](Extensions::drain): a
+    /// [`BoxedExtension`] it yields is inserted under its [`Key`], if it had one, with its
+    /// pushed values, its type name, and whether it's redacted, cloneable, displayed or
+    /// serialized. A plain `Box<dyn Any>` carries none of that, so it's listed as `<unknown>` by
+    /// [`type_names`](Extensions::type_names) and isn't serialized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` isn't of the type identified by `type_id`.
     ///
     /// # Example
     ///
     /// ```
     /// # use extension_eyre::extensions::Extensions;
+    /// use std::any::{Any, TypeId};
+    ///
     /// let mut ext = Extensions::new();
-    /// ext.insert(5i32);
-    /// ext.clear();
+    /// let value: Box<dyn Any + Send + Sync> = Box::new(5i32);
     ///
-    /// assert!(ext.get::<i32>().is_none());
+    /// assert!(ext.insert_boxed(TypeId::of::<i32>(), value).is_none());
+    /// assert_eq!(ext.get::<i32>(), Some(&5i32));
     /// ```
-    #[inline]
-    pub fn clear(&mut self) {
-        if let Some(ref mut map) = self.map {
-            map.clear();
+    pub fn insert_boxed<V: Into<BoxedExtension>>(
+        &mut self,
+        type_id: TypeId,
+        value: V,
+    ) -> Option<Box<dyn Any + Send + Sync>> {
+        let BoxedExtension { slot, key } = value.into();
+        assert_eq!(
+            (*slot.value).type_id(),
+            type_id,
+            "boxed extension doesn't have the given type id"
+        );
+
+        let replaced = match key {
+            Some(key) => self
+                .keyed
+                .get_or_insert_with(Default::default)
+                .insert((type_id, key), slot),
+            None => self
+                .map
+                .get_or_insert_with(|| Box::new(HashMap::default()))
+                .insert(type_id, slot),
+        };
+
+        replaced.map(|slot| slot.value)
+    }
+
+    /// Insert a value attached by `source`, replacing any value of the same type.
+    pub(crate) fn insert_from<T: Send + Sync + 'static>(&mut self, source: Source, val: T) {
+        self.map
+            .get_or_insert_with(|| Box::new(HashMap::default()))
+            .insert(TypeId::of::<T>(), Slot::from_source(source, val));
+    }
+
+    /// Insert a value attached by `source` at `location`, replacing any value of the same type.
+    pub(crate) fn insert_at<T: Send + Sync + 'static>(
+        &mut self,
+        source: Source,
+        val: T,
+        location: &'static Location<'static>,
+    ) {
+        let mut slot = Slot::from_source(source, val);
+        slot.location = Some(location);
+        self.map
+            .get_or_insert_with(|| Box::new(HashMap::default()))
+            .insert(TypeId::of::<T>(), slot);
+    }
+
+    /// Moves the values of `other` into `self` as attached by `source`, keeping the values whose
+    /// source `precedence` ranks higher.
+    pub(crate) fn merge_from(
+        &mut self,
+        source: Source,
+        other: Extensions,
+        precedence: &Precedence,
+    ) {
+        if let Some(other) = other.map {
+            let map = self.map.get_or_insert_with(|| Box::new(HashMap::default()));
+
+            for (type_id, mut slot) in *other {
+                let wins = map
+                    .get(&type_id)
+                    .is_none_or(|existing| precedence.prefers_id(type_id, source, existing.source));
+
+                if wins {
+                    slot.source = source;
+                    map.insert(type_id, slot);
+                }
+            }
+        }
+
+        if let Some(other) = other.keyed {
+            self.keyed
+                .get_or_insert_with(|| Box::new(HashMap::default()))
+                .extend(*other);
         }
     }
 
-    /// Check whether the extension set is empty or not.
+    /// Get the [`Source`] the value of type `T` was attached by.
+    ///
+    /// Values inserted directly on an `Extensions` count as [`Source::Explicit`].
     ///
     /// # Example
     ///
     /// ```
     /// # use extension_eyre::extensions::Extensions;
+    /// use extension_eyre::config::Source;
+    ///
     /// let mut ext = Extensions::new();
-    /// assert!(ext.is_empty());
+    /// assert!(ext.source::<i32>().is_none());
+    ///
     /// ext.insert(5i32);
-    /// assert!(!ext.is_empty());
+    /// assert_eq!(ext.source::<i32>(), Some(Source::Explicit));
     /// ```
-    #[inline]
-    pub fn is_empty(&self) -> bool {
+    pub fn source<T: Send + Sync + 'static>(&self) -> Option<Source> {
         self.map
             .as_ref()
-            .map_or(true, |map| map.is_empty())
+            .and_then(|map| map.get(&TypeId::of::<T>()))
+            .map(|slot| slot.source)
     }
 
-    /// Get the numer of extensions available.
+    /// Get the call site the value of type `T` was attached at.
+    ///
+    /// Only values attached to a report with [`ExtensionExt::extension`] or
+    /// [`ExtensionExt::with_extension`] have one; it's updated whenever such a call replaces the
+    /// value, and cleared when a value is [pushed](Extensions::push) after it.
+    ///
+    /// [`ExtensionExt::extension`]: crate::ExtensionExt::extension
+    /// [`ExtensionExt::with_extension`]: crate::ExtensionExt::with_extension
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use extension_eyre::{eyre::eyre, Extension, ExtensionExt};
+    ///
+    /// pub struct Retry(bool);
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let report = eyre!("connection reset").extension(Retry(false));
+    /// let report = report.extension(Retry(true));
+    /// let second = line!() - 1;
+    ///
+    /// let extensions = report.extensions_ref().unwrap();
+    /// let location = extensions.provenance::<Retry>().unwrap();
+    /// assert_eq!(location.file(), file!());
+    /// assert_eq!(location.line(), second);
+    ///
+    /// let mut ext = extension_eyre::extensions::Extensions::new();
+    /// ext.insert(5i32);
+    /// assert!(ext.provenance::<i32>().is_none());
+    /// ```
+    pub fn provenance<T: Send + Sync + 'static>(&self) -> Option<&'static Location<'static>> {
+        self.map
+            .as_ref()
+            .and_then(|map| map.get(&TypeId::of::<T>()))
+            .and_then(|slot| slot.location)
+    }
+
+    /// Get a reference to a type previously inserted on this `Extensions`.
+    ///
+    /// If several values of the type were [pushed](Extensions::push), this is the most recent
+    /// one.
     ///
     /// # Example
     ///
     /// ```
     /// # use extension_eyre::extensions::Extensions;
     /// let mut ext = Extensions::new();
-    /// assert_eq!(ext.len(), 0);
+    /// assert!(ext.get::<i32>().is_none());
     /// ext.insert(5i32);
-    /// assert_eq!(ext.len(), 1);
+    ///
+    /// assert_eq!(ext.get::<i32>(), Some(&5i32));
     /// ```
-    #[inline]
-    pub fn len(&self) -> usize {
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.get_by_type_id(&TypeId::of::<T>())
+            .and_then(|value| (value as &(dyn Any + 'static)).downcast_ref())
+    }
+
+    /// Get a reference to the extension of the type identified by `type_id`.
+    ///
+    /// This is the untyped form of [`get`](Extensions::get), for code that only learns the
+    /// extension types at runtime.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// use std::any::TypeId;
+    ///
+    /// let mut ext = Extensions::new();
+    /// ext.insert(5i32);
+    ///
+    /// let value = ext.get_by_type_id(&TypeId::of::<i32>()).unwrap();
+    /// assert_eq!(value.downcast_ref::<i32>(), Some(&5i32));
+    /// assert!(ext.get_by_type_id(&TypeId::of::<u8>()).is_none());
+    /// ```
+    pub fn get_by_type_id(&self, type_id: &TypeId) -> Option<&(dyn Any + Send + Sync)> {
         self.map
             .as_ref()
-            .map_or(0, |map| map.len())
+            .and_then(|map| map.get(type_id))
+            .map(|slot| &*slot.value)
     }
 
-    /// Extends `self` with another `Extensions`.
+    /// Get a mutable reference to a type previously inserted on this `Extensions`.
     ///
-    /// If an instance of a specific type exists in both, the one in `self` is overwritten with the
-    /// one from `other`.
-    /// 
     /// # Example
-    /// 
+    ///
     /// ```
     /// # use extension_eyre::extensions::Extensions;
-    /// let mut ext_a = Extensions::new();
-    /// ext_a.insert(8u8);
-    /// ext_a.insert(16u16);
-    /// 
-    /// let mut ext_b = Extensions::new();
-    /// ext_b.insert(4u8);
-    /// ext_b.insert("hello");
-    /// 
-    /// ext_a.extend(ext_b);
-    /// assert_eq!(ext_a.len(), 3);
-    /// assert_eq!(ext_a.get::<u8>(), Some(&4u8));
-    /// assert_eq!(ext_a.get::<u16>(), Some(&16u16));
-    /// assert_eq!(ext_a.get::<&'static str>().copied(), Some("hello"));
+    /// let mut ext = Extensions::new();
+    /// ext.insert(String::from("Hello"));
+    /// ext.get_mut::<String>().unwrap().push_str(" World");
+    ///
+    /// assert_eq!(ext.get::<String>().unwrap(), "Hello World");
     /// ```
-    pub fn extend(&mut self, other: Self) {
-        if let Some(other) = other.map {
-            if let Some(map) = &mut self.map {
-                map.extend(*other);
-            } else {
-                self.map = Some(other);
-            }
-        }
+    pub fn get_mut<T: Send + Sync + 'static>(&mut self) -> Option<&mut T> {
+        self.get_mut_by_type_id(&TypeId::of::<T>())
+            .and_then(|value| (value as &mut (dyn Any + 'static)).downcast_mut())
     }
-}
 
+    /// Get a mutable reference to the extension of the type identified by `type_id`.
+    ///
+    /// This is the untyped form of [`get_mut`](Extensions::get_mut).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// use std::any::TypeId;
+    ///
+    /// let mut ext = Extensions::new();
+    /// ext.insert(String::from("Hello"));
+    ///
+    /// let value = ext.get_mut_by_type_id(&TypeId::of::<String>()).unwrap();
+    /// value.downcast_mut::<String>().unwrap().push_str(" World");
+    ///
+    /// assert_eq!(ext.get::<String>().unwrap(), "Hello World");
+    /// ```
+    pub fn get_mut_by_type_id(&mut self, type_id: &TypeId) -> Option<&mut (dyn Any + Send + Sync)> {
+        self.map
+            .as_mut()
+            .and_then(|map| map.get_mut(type_id))
+            .map(|slot| &mut *slot.value)
+    }
+
+    /// Add another value of type `T`, keeping the ones already present.
+    ///
+    /// The pushed value becomes the one returned by [`get`](Extensions::get), and all values can
+    /// be read back in the order they were added with [`get_all`](Extensions::get_all).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// #[derive(Debug, PartialEq)]
+    /// struct Attempt(u32);
+    ///
+    /// let mut ext = Extensions::new();
+    /// ext.push(Attempt(1));
+    /// ext.push(Attempt(2));
+    ///
+    /// assert_eq!(ext.get::<Attempt>(), Some(&Attempt(2)));
+    /// assert_eq!(ext.get_all::<Attempt>().count(), 2);
+    /// ```
+    pub fn push<T: Send + Sync + 'static>(&mut self, val: T) {
+        match self
+            .map
+            .get_or_insert_with(|| Box::new(HashMap::default()))
+            .entry(TypeId::of::<T>())
+        {
+            hash_map::Entry::Occupied(slot) => slot.into_mut().push(val),
+            hash_map::Entry::Vacant(slot) => {
+                slot.insert(Slot::new(val));
+            }
+        }
+    }
+
+    /// Iterate over every value of type `T`, oldest first.
+    ///
+    /// A value added with [`insert`](Extensions::insert) replaces all values of its type, so
+    /// only the values pushed after the last insert, and that insert itself, are yielded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// # use std::time::Duration;
+    /// #[derive(Debug, PartialEq)]
+    /// struct AttemptInfo {
+    ///     attempt: u32,
+    ///     elapsed: Duration,
+    /// }
+    ///
+    /// let attempt = |attempt| AttemptInfo { attempt, elapsed: Duration::from_millis(10) };
+    ///
+    /// let mut ext = Extensions::new();
+    /// ext.push(attempt(1));
+    /// ext.push(attempt(2));
+    /// ext.insert(attempt(3));
+    /// ext.push(attempt(4));
+    /// ext.push(attempt(5));
+    ///
+    /// let attempts = ext.get_all::<AttemptInfo>().map(|a| a.attempt).collect::<Vec<_>>();
+    /// assert_eq!(attempts, [3, 4, 5]);
+    /// assert_eq!(ext.get::<AttemptInfo>(), Some(&attempt(5)));
+    /// assert_eq!(ext.insert(attempt(6)), Some(attempt(5)));
+    /// assert_eq!(ext.get_all::<AttemptInfo>().count(), 1);
+    ///
+    /// assert_eq!(ext.get_all::<u8>().count(), 0);
+    /// ```
+    pub fn get_all<T: Send + Sync + 'static>(&self) -> impl Iterator<Item = &T> + '_ {
+        self.map
+            .as_ref()
+            .and_then(|map| map.get(&TypeId::of::<T>()))
+            .into_iter()
+            .flat_map(Slot::values)
+            .filter_map(|value| (value as &(dyn Any + 'static)).downcast_ref())
+    }
+
+    /// Run the callbacks of `registry` registered for the types present in this `Extensions`.
+    ///
+    /// Callbacks run in the order they were registered, once per value of their type, oldest
+    /// first. Values stored under a [`Key`] aren't visited.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use extension_eyre::extensions::{ExtensionVisitorRegistry, Extensions};
+    /// use std::sync::Mutex;
+    ///
+    /// pub struct Retry(bool);
+    /// pub struct StatusCode(u16);
+    /// pub struct Tenant(&'static str);
+    ///
+    /// static SEEN: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    ///
+    /// let record = |entry: String| SEEN.lock().unwrap().push(entry);
+    /// let registry = ExtensionVisitorRegistry::new()
+    ///     .register(move |retry: &Retry| record(format!("retry={}", retry.0)))
+    ///     .register(move |status: &StatusCode| record(format!("status={}", status.0)))
+    ///     .register(move |tenant: &Tenant| record(format!("tenant={}", tenant.0)));
+    ///
+    /// let mut ext = Extensions::new();
+    /// ext.insert(Tenant("acme"));
+    /// ext.insert(StatusCode(502));
+    ///
+    /// ext.visit(&registry);
+    /// assert_eq!(*SEEN.lock().unwrap(), ["status=502", "tenant=acme"]);
+    /// ```
+    pub fn visit(&self, registry: &super::ExtensionVisitorRegistry) {
+        let map = match &self.map {
+            Some(map) => map,
+            None => return,
+        };
+
+        for (type_id, callback) in registry.callbacks() {
+            if let Some(slot) = map.get(type_id) {
+                slot.values().for_each(callback);
+            }
+        }
+    }
+
+    /// Insert a value under `key`.
+    ///
+    /// If a value was already stored under the same key, it will be returned. Keyed values are
+    /// separate from the ones inserted by type, so [`get`](Extensions::get) doesn't see them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::{Extensions, Key};
+    /// const UPSTREAM_RESPONSE_BODY: Key<String> = Key::new("upstream_response_body");
+    /// const REQUEST_PATH: Key<String> = Key::new("request_path");
+    ///
+    /// let mut ext = Extensions::new();
+    /// ext.insert_keyed(UPSTREAM_RESPONSE_BODY, String::from("502 Bad Gateway"));
+    /// ext.insert_keyed(REQUEST_PATH, String::from("/api/users"));
+    ///
+    /// assert_eq!(ext.get_keyed(UPSTREAM_RESPONSE_BODY).unwrap(), "502 Bad Gateway");
+    /// assert_eq!(ext.get_keyed(REQUEST_PATH).unwrap(), "/api/users");
+    /// assert!(ext.get::<String>().is_none());
+    ///
+    /// // same name, different type
+    /// ext.insert_keyed(Key::new("request_path"), 404u16);
+    /// assert_eq!(ext.get_keyed(Key::<u16>::new("request_path")), Some(&404));
+    /// assert_eq!(ext.get_keyed(REQUEST_PATH).unwrap(), "/api/users");
+    /// ```
+    pub fn insert_keyed<T: Send + Sync + 'static>(&mut self, key: Key<T>, val: T) -> Option<T> {
+        self.keyed
+            .get_or_insert_with(Default::default)
+            .insert((TypeId::of::<T>(), key.name), Slot::new(val))
+            .and_then(Slot::downcast)
+    }
+
+    /// Get a reference to the value stored under `key`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::{Extensions, Key};
+    /// const REQUEST_PATH: Key<String> = Key::new("request_path");
+    ///
+    /// let mut ext = Extensions::new();
+    /// ext.insert(String::from("not keyed"));
+    /// assert!(ext.get_keyed(REQUEST_PATH).is_none());
+    ///
+    /// ext.insert_keyed(REQUEST_PATH, String::from("/api/users"));
+    /// assert_eq!(ext.get_keyed(REQUEST_PATH).unwrap(), "/api/users");
+    /// assert_eq!(ext.get::<String>().unwrap(), "not keyed");
+    /// ```
+    pub fn get_keyed<T: Send + Sync + 'static>(&self, key: Key<T>) -> Option<&T> {
+        self.keyed
+            .as_ref()
+            .and_then(|keyed| keyed.get(&(TypeId::of::<T>(), key.name)))
+            .and_then(Slot::downcast_ref)
+    }
+
+    /// Remove the value stored under `key`.
+    ///
+    /// If a value was stored under the key, it will be returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::{Extensions, Key};
+    /// const REQUEST_PATH: Key<String> = Key::new("request_path");
+    ///
+    /// let mut ext = Extensions::new();
+    /// ext.insert_keyed(REQUEST_PATH, String::from("/api/users"));
+    ///
+    /// assert_eq!(ext.remove_keyed(REQUEST_PATH).unwrap(), "/api/users");
+    /// assert!(ext.get_keyed(REQUEST_PATH).is_none());
+    /// assert!(ext.is_empty());
+    /// ```
+    pub fn remove_keyed<T: Send + Sync + 'static>(&mut self, key: Key<T>) -> Option<T> {
+        self.keyed
+            .as_mut()
+            .and_then(|keyed| keyed.remove(&(TypeId::of::<T>(), key.name)))
+            .and_then(Slot::downcast)
+    }
+
+    /// Get a mutable reference to a type previously inserted on this `Extensions`, inserting
+    /// `value` first if the type is missing.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// let mut ext = Extensions::new();
+    /// *ext.get_or_insert(5i32) += 1;
+    /// *ext.get_or_insert(0i32) += 1;
+    ///
+    /// assert_eq!(ext.get::<i32>(), Some(&7i32));
+    /// ```
+    pub fn get_or_insert<T: Send + Sync + 'static>(&mut self, value: T) -> &mut T {
+        self.get_or_insert_with(|| value)
+    }
+
+    /// Get a mutable reference to a type previously inserted on this `Extensions`, inserting
+    /// the value returned by `f` first if the type is missing.
+    ///
+    /// `f` is only called when no value of type `T` is present.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// struct Counter(usize);
+    ///
+    /// let mut ext = Extensions::new();
+    /// ext.get_or_insert_with(|| Counter(0)).0 += 1;
+    /// ext.get_or_insert_with(|| -> Counter { unreachable!("already present") }).0 += 1;
+    ///
+    /// assert_eq!(ext.get::<Counter>().unwrap().0, 2);
+    /// ```
+    ///
+    /// The same works on the map attached to a report:
+    ///
+    /// ```
+    /// use extension_eyre::{eyre::eyre, Extension};
+    /// # struct Counter(usize);
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let mut err = eyre!("cmd exited with non-zero status code");
+    /// err.extensions_mut().unwrap().get_or_insert_with(|| Counter(0)).0 += 1;
+    /// err.extensions_mut().unwrap().get_or_insert_with(|| Counter(0)).0 += 1;
+    ///
+    /// assert_eq!(err.extension_ref::<Counter>().unwrap().0, 2);
+    /// ```
+    pub fn get_or_insert_with<T: Send + Sync + 'static, F: FnOnce() -> T>(
+        &mut self,
+        f: F,
+    ) -> &mut T {
+        self.map
+            .get_or_insert_with(|| Box::new(HashMap::default()))
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Slot::new(f()))
+            .downcast_mut()
+            .expect("extension is stored under its own TypeId")
+    }
+
+    /// Insert `val` only if no value of type `T` is present, returning whether it was inserted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// let mut ext = Extensions::new();
+    /// assert!(ext.insert_if_absent(5i32));
+    /// assert!(!ext.insert_if_absent(6i32));
+    ///
+    /// assert_eq!(ext.get::<i32>(), Some(&5i32));
+    /// ```
+    pub fn insert_if_absent<T: Send + Sync + 'static>(&mut self, val: T) -> bool {
+        self.insert_if_absent_with(|| val)
+    }
+
+    /// Insert the value returned by `f` only if no value of type `T` is present, returning
+    /// whether it was inserted.
+    ///
+    /// `f` is only called when the value is inserted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// let mut ext = Extensions::new();
+    /// ext.insert(5i32);
+    ///
+    /// assert!(!ext.insert_if_absent_with(|| -> i32 { unreachable!("already present") }));
+    /// assert_eq!(ext.get::<i32>(), Some(&5i32));
+    /// ```
+    pub fn insert_if_absent_with<T: Send + Sync + 'static, F: FnOnce() -> T>(
+        &mut self,
+        f: F,
+    ) -> bool {
+        let mut inserted = false;
+        self.get_or_insert_with(|| {
+            inserted = true;
+            f()
+        });
+        inserted
+    }
+
+    /// Get a mutable reference to the extension of type `T`, inserting `T::default()` if there
+    /// is none.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// static CONSTRUCTED: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// struct RetryCount(usize);
+    ///
+    /// impl Default for RetryCount {
+    ///     fn default() -> Self {
+    ///         CONSTRUCTED.fetch_add(1, Ordering::Relaxed);
+    ///         RetryCount(0)
+    ///     }
+    /// }
+    ///
+    /// let mut ext = Extensions::new();
+    /// for _ in 0..3 {
+    ///     ext.get_or_default::<RetryCount>().0 += 1;
+    /// }
+    ///
+    /// assert_eq!(ext.get::<RetryCount>().unwrap().0, 3);
+    /// assert_eq!(CONSTRUCTED.load(Ordering::Relaxed), 1);
+    /// ```
+    pub fn get_or_default<T: Default + Send + Sync + 'static>(&mut self) -> &mut T {
+        self.get_or_insert_with(T::default)
+    }
+
+    /// Update the extension of type `T` in place, returning whether there was one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// struct Counter(usize);
+    ///
+    /// let mut ext = Extensions::new();
+    /// assert!(!ext.modify::<Counter>(|counter| counter.0 += 1));
+    /// assert!(ext.get::<Counter>().is_none());
+    ///
+    /// ext.insert(Counter(0));
+    /// assert!(ext.modify::<Counter>(|counter| counter.0 += 1));
+    /// assert_eq!(ext.get::<Counter>().unwrap().0, 1);
+    /// ```
+    pub fn modify<T: Send + Sync + 'static>(&mut self, f: impl FnOnce(&mut T)) -> bool {
+        match self.get_mut::<T>() {
+            Some(value) => {
+                f(value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Update the extension of type `T` in place, inserting `default` first if there is none.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// struct Counter(usize);
+    ///
+    /// let mut ext = Extensions::new();
+    /// ext.modify_or_insert(Counter(10), |counter| counter.0 += 1);
+    /// ext.modify_or_insert(Counter(10), |counter| counter.0 += 1);
+    ///
+    /// assert_eq!(ext.get::<Counter>().unwrap().0, 12);
+    /// ```
+    pub fn modify_or_insert<T: Send + Sync + 'static>(
+        &mut self,
+        default: T,
+        f: impl FnOnce(&mut T),
+    ) {
+        f(self.get_or_insert(default));
+    }
+
+    /// Get the [`Entry`] for type `T` for in-place manipulation.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// struct Counter(usize);
+    ///
+    /// let mut ext = Extensions::new();
+    /// ext.entry::<Counter>().and_modify(|c| c.0 += 1).or_insert(Counter(0));
+    /// ext.entry::<Counter>().and_modify(|c| c.0 += 1).or_insert(Counter(0));
+    ///
+    /// assert_eq!(ext.get::<Counter>().unwrap().0, 1);
+    /// ```
+    pub fn entry<T: Send + Sync + 'static>(&mut self) -> Entry<'_, T> {
+        Entry {
+            extensions: self,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Remove a type from this `Extensions`.
+    ///
+    /// If a extension of this type existed, it will be returned. When several values were
+    /// [pushed](Extensions::push), all of them are removed and the most recent one is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// let mut ext = Extensions::new();
+    /// ext.insert(5i32);
+    /// assert_eq!(ext.remove::<i32>(), Some(5i32));
+    /// assert!(ext.get::<i32>().is_none());
+    /// ```
+    pub fn remove<T: Send + Sync + 'static>(&mut self) -> Option<T> {
+        self.remove_by_type_id(&TypeId::of::<T>())
+            .and_then(|value| (value as Box<dyn Any + 'static>).downcast().ok())
+            .map(|boxed| *boxed)
+    }
+
+    /// Remove the extensions of the type identified by `type_id`, returning the most recent one.
+    ///
+    /// This is the untyped form of [`remove`](Extensions::remove).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// use std::any::TypeId;
+    ///
+    /// let mut ext = Extensions::new();
+    /// ext.push(4u8);
+    /// ext.push(8u8);
+    ///
+    /// let value = ext.remove_by_type_id(&TypeId::of::<u8>()).unwrap();
+    /// assert_eq!(value.downcast_ref::<u8>(), Some(&8u8));
+    /// assert!(ext.get::<u8>().is_none());
+    /// assert!(ext.remove_by_type_id(&TypeId::of::<u8>()).is_none());
+    /// ```
+    pub fn remove_by_type_id(&mut self, type_id: &TypeId) -> Option<Box<dyn Any + Send + Sync>> {
+        self.map
+            .as_mut()
+            .and_then(|map| map.remove(type_id))
+            .map(|slot| slot.value)
+    }
+
+    /// Retain only the extensions for which `f` returns `true`.
+    ///
+    /// `f` is called once per stored type with its [`TypeId`] and its value, the most recent one
+    /// if several were [pushed](Extensions::push). Values stored under a [`Key`] are visited as
+    /// well.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// let mut ext = Extensions::new();
+    /// ext.insert(5i32);
+    /// ext.insert(4u8);
+    /// ext.insert(String::from("internal"));
+    ///
+    /// ext.retain(|_, value| match value.downcast_mut::<i32>() {
+    ///     Some(n) => {
+    ///         *n += 1;
+    ///         true
+    ///     }
+    ///     None => false,
+    /// });
+    ///
+    /// assert_eq!(ext.len(), 1);
+    /// assert_eq!(ext.get::<i32>(), Some(&6i32));
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&TypeId, &mut (dyn Any + Send + Sync)) -> bool,
+    {
+        if let Some(map) = &mut self.map {
+            map.retain(|type_id, slot| f(type_id, &mut *slot.value));
+        }
+        if let Some(keyed) = &mut self.keyed {
+            keyed.retain(|(type_id, _), slot| f(type_id, &mut *slot.value));
+        }
+    }
+
+    /// Remove every extension, returning them with their [`TypeId`]s.
+    ///
+    /// Values stored under a [`Key`] are included. Each [`BoxedExtension`] holds everything the
+    /// map knew about the value, including the values [pushed](Extensions::push) before it, and
+    /// brings it along when inserted into another map with
+    /// [`insert_boxed`](Extensions::insert_boxed).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::{Extensions, Key};
+    /// use std::any::type_name;
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct Worker(usize);
+    /// pub struct AuthToken(&'static str);
+    ///
+    /// const FALLBACK: Key<Worker> = Key::new("fallback");
+    ///
+    /// let mut summary = Extensions::new();
+    /// summary.insert(Worker(0));
+    ///
+    /// let mut failed = Extensions::new();
+    /// failed.insert(Worker(3));
+    /// failed.insert_keyed(FALLBACK, Worker(4));
+    /// failed.insert(String::from("disk full"));
+    /// failed.push(5i32);
+    /// failed.push(6i32);
+    /// failed.insert_redacted(AuthToken("hunter2"));
+    ///
+    /// for (type_id, value) in failed.drain() {
+    ///     summary.insert_boxed(type_id, value);
+    /// }
+    ///
+    /// assert!(failed.is_empty());
+    /// assert_eq!(summary.len(), 5);
+    /// assert_eq!(summary.get::<Worker>(), Some(&Worker(3)));
+    /// assert_eq!(summary.get_keyed(FALLBACK), Some(&Worker(4)));
+    /// assert_eq!(summary.get::<String>().map(String::as_str), Some("disk full"));
+    /// assert_eq!(summary.get_all::<i32>().collect::<Vec<_>>(), [&5, &6]);
+    /// assert!(summary.is_redacted::<AuthToken>());
+    /// assert!(summary.type_names().any(|name| name == type_name::<AuthToken>()));
+    /// ```
+    pub fn drain(&mut self) -> impl Iterator<Item = (TypeId, BoxedExtension)> {
+        let keyed = self
+            .keyed
+            .take()
+            .into_iter()
+            .flat_map(|keyed| keyed.into_iter())
+            .map(|((type_id, key), slot)| (type_id, BoxedExtension::keyed(slot, key)));
+
+        self.map
+            .take()
+            .into_iter()
+            .flat_map(|map| map.into_iter())
+            .map(|(type_id, slot)| (type_id, BoxedExtension::new(slot)))
+            .chain(keyed)
+    }
+
+    /// Remove every extension whose type isn't listed in `keep`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// use std::any::TypeId;
+    ///
+    /// let mut ext = Extensions::new();
+    /// ext.insert(5i32);
+    /// ext.insert(4u8);
+    /// ext.insert(String::from("internal"));
+    ///
+    /// ext.remove_all_except(&[TypeId::of::<i32>(), TypeId::of::<u8>()]);
+    ///
+    /// assert_eq!(ext.len(), 2);
+    /// assert!(ext.get::<String>().is_none());
+    /// ```
+    pub fn remove_all_except(&mut self, keep: &[TypeId]) {
+        self.retain(|type_id, _| keep.contains(type_id));
+    }
+
+    /// Clear the `Extensions` of all inserted extensions.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// let mut ext = Extensions::new();
+    /// ext.insert(5i32);
+    /// ext.clear();
+    ///
+    /// assert!(ext.get::<i32>().is_none());
+    /// ```
+    #[inline]
+    pub fn clear(&mut self) {
+        if let Some(ref mut map) = self.map {
+            map.clear();
+        }
+        if let Some(ref mut keyed) = self.keyed {
+            keyed.clear();
+        }
+    }
+
+    /// Check whether the extension set is empty or not.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// let mut ext = Extensions::new();
+    /// assert!(ext.is_empty());
+    /// ext.insert(5i32);
+    /// assert!(!ext.is_empty());
+    /// ```
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.map.as_ref().is_none_or(|map| map.is_empty())
+            && self.keyed.as_ref().is_none_or(|keyed| keyed.is_empty())
+    }
+
+    /// Get the numer of extensions available.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// let mut ext = Extensions::new();
+    /// assert_eq!(ext.len(), 0);
+    /// ext.insert(5i32);
+    /// assert_eq!(ext.len(), 1);
+    /// ```
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.map.as_ref().map_or(0, |map| map.len())
+            + self.keyed.as_ref().map_or(0, |keyed| keyed.len())
+    }
+
+    /// Extends `self` with another `Extensions`.
+    ///
+    /// If an instance of a specific type exists in both, the one in `self` is overwritten with the
+    /// one from `other`.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// let mut ext_a = Extensions::new();
+    /// ext_a.insert(8u8);
+    /// ext_a.insert(16u16);
+    /// 
+    /// let mut ext_b = Extensions::new();
+    /// ext_b.insert(4u8);
+    /// ext_b.insert("hello");
+    /// 
+    /// ext_a.extend(ext_b);
+    /// assert_eq!(ext_a.len(), 3);
+    /// assert_eq!(ext_a.get::<u8>(), Some(&4u8));
+    /// assert_eq!(ext_a.get::<u16>(), Some(&16u16));
+    /// assert_eq!(ext_a.get::<&'static str>().copied(), Some("hello"));
+    /// ```
+    pub fn extend(&mut self, other: Self) {
+        if let Some(other) = other.map {
+            if let Some(map) = &mut self.map {
+                map.extend(*other);
+            } else {
+                self.map = Some(other);
+            }
+        }
+        if let Some(other) = other.keyed {
+            if let Some(keyed) = &mut self.keyed {
+                keyed.extend(*other);
+            } else {
+                self.keyed = Some(other);
+            }
+        }
+    }
+
+    /// Iterate over the type names of the stored extensions, in alphabetical order.
+    ///
+    /// Every type is listed once, however many values were [pushed](Extensions::push) to it or
+    /// stored under a [`Key`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::{Extensions, Key};
+    /// let mut ext = Extensions::new();
+    /// assert_eq!(ext.type_names().count(), 0);
+    ///
+    /// ext.insert(5i32);
+    /// ext.push(4u8);
+    /// ext.push(8u8);
+    /// ext.insert_keyed(Key::new("request_path"), String::from("/api/users"));
+    /// ext.insert_keyed(Key::new("user_agent"), String::from("curl/8.5.0"));
+    ///
+    /// let names = ext.type_names().collect::<Vec<_>>();
+    /// assert_eq!(names, ["alloc::string::String", "i32", "u8"]);
+    ///
+    /// assert_eq!(ext.remove::<u8>(), Some(8u8));
+    /// assert_eq!(ext.get::<i32>(), Some(&5i32));
+    /// assert_eq!(ext.type_names().collect::<Vec<_>>(), ["alloc::string::String", "i32"]);
+    /// ```
+    pub fn type_names(&self) -> impl Iterator<Item = &'static str> {
+        // deduplicated by id, types inserted from a plain box all share a name
+        let mut names = self
+            .slots()
+            .map(|(type_id, slot)| (slot.type_name, *type_id))
+            .collect::<Vec<_>>();
+
+        names.sort_unstable();
+        names.dedup();
+        names.into_iter().map(|(name, _)| name)
+    }
+
+    /// Iterates over the type name and value of every stored extension, including every value
+    /// of types that were pushed more than once.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&'static str, &(dyn Any + Send + Sync))> {
+        self.slots()
+            .flat_map(|(_, slot)| slot.values().map(move |value| (slot.type_name, value)))
+    }
+
+    // Every slot with the id of its type, keyed slots last.
+    fn slots(&self) -> impl Iterator<Item = (&TypeId, &Slot)> {
+        let keyed = self
+            .keyed
+            .iter()
+            .flat_map(|keyed| keyed.iter().map(|((type_id, _), slot)| (type_id, slot)));
+
+        self.map.iter().flat_map(|map| map.iter()).chain(keyed)
+    }
+
+    /// The `Extensions:` section of a report, listing every stored value as `config` says.
+    pub(crate) fn section<'a>(&'a self, config: &'a HandlerConfig) -> Section<'a> {
+        Section {
+            extensions: self,
+            config,
+        }
+    }
+}
+
+/// Renders the stored values on one line, like `Retry, ErrorCode=E42`, sorted by type name.
+///
+/// Types are named without their module path. Values are shown like in [`Section`], except
+/// that redacted values are left out and only their type is named.
+pub(crate) struct Summary<'a> {
+    extensions: &'a Extensions,
+    formatters: &'a Formatters,
+}
+
+impl Extensions {
+    /// The stored values on one line, for the compact rendering of a report.
+    pub(crate) fn summary<'a>(&'a self, formatters: &'a Formatters) -> Summary<'a> {
+        Summary {
+            extensions: self,
+            formatters,
+        }
+    }
+}
+
+impl fmt::Display for Summary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut slots = self.extensions.slots().collect::<Vec<_>>();
+
+        slots.sort_by_key(|(_, slot)| short_type_name(slot.type_name));
+
+        let mut separator = "";
+        for (type_id, slot) in slots {
+            let formatter = self.formatters.get(type_id);
+
+            for value in slot.values() {
+                write!(f, "{}{}", separator, short_type_name(slot.type_name))?;
+                separator = ", ";
+
+                if slot.is_redacted(*type_id) {
+                    continue;
+                } else if let Some(formatter) = formatter {
+                    write!(f, "=")?;
+                    formatter(value, f)?;
+                } else if let Some(display) = slot.display {
+                    write!(f, "=")?;
+                    display(value, f)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Extensions {
+    /// The rendered value of every type shown in the `Extensions:` section, by type name.
+    ///
+    /// Only the most recent value of each type is rendered. Redacted values are left out.
+    #[cfg_attr(
+        not(any(feature = "miette", feature = "opentelemetry", feature = "sentry")),
+        allow(dead_code)
+    )]
+    pub(crate) fn displayed(&self, formatters: &Formatters) -> Vec<(&'static str, String)> {
+        self.slots()
+            .filter(|(type_id, slot)| !slot.is_redacted(**type_id))
+            .filter_map(|(type_id, slot)| {
+                let value = &*slot.value;
+                let rendered = match (formatters.get(type_id), slot.display) {
+                    (Some(formatter), _) => Rendered(value, &**formatter).to_string(),
+                    (None, Some(display)) => Rendered(value, &display).to_string(),
+                    (None, None) => return None,
+                };
+                Some((slot.type_name, rendered))
+            })
+            .collect()
+    }
+}
+
+// Renders a value with a formatter or the `Display` impl it was inserted with.
+struct Rendered<'a>(
+    &'a (dyn Any + Send + Sync),
+    &'a dyn Fn(&(dyn Any + Send + Sync), &mut fmt::Formatter<'_>) -> fmt::Result,
+);
+
+impl fmt::Display for Rendered<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (self.1)(self.0, f)
+    }
+}
+
+// `a::b::Type<c::D>` becomes `Type<c::D>`.
+fn short_type_name(type_name: &str) -> &str {
+    let path_end = type_name.find('<').unwrap_or(type_name.len());
+    let start = type_name[..path_end].rfind("::").map_or(0, |i| i + 2);
+
+    &type_name[start..]
+}
+
+/// Renders the stored values as a report section, sorted by type name.
+///
+/// Values of a type with a formatter registered through
+/// [`HookBuilder::extension_formatter`](crate::config::HookBuilder::extension_formatter) are
+/// shown as `type: value`, as are values inserted with [`Extensions::insert_display`]. Redacted
+/// values are shown as `type: <redacted>`, and everything else by its type name only. With
+/// [`HookBuilder::display_extension_provenance`], the most recent value of a type is followed by
+/// `(attached at file:line)` if its call site is known. Parts are styled with the configured
+/// [`ExtensionStyles`](crate::config::ExtensionStyles). Renders nothing when the map is empty.
+///
+/// [`HookBuilder::display_extension_provenance`]:
+/// crate::config::HookBuilder::display_extension_provenance
+pub(crate) struct Section<'a> {
+    extensions: &'a Extensions,
+    config: &'a HandlerConfig,
+}
+
+impl fmt::Display for Section<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut slots = self.extensions.slots().collect::<Vec<_>>();
+
+        if slots.is_empty() {
+            return Ok(());
+        }
+
+        slots.sort_by_key(|(_, slot)| slot.type_name);
+
+        let styles = &self.config.extension_styles;
+        write!(f, "{}", "Extensions:".style(styles.extension_header))?;
+        for (type_id, slot) in slots {
+            let formatter = self.config.formatters.get(type_id);
+            let style = styles.value(type_id);
+
+            for value in slot.values() {
+                write!(f, "\n   {}", slot.type_name.style(styles.extension_key))?;
+                if slot.is_redacted(*type_id) {
+                    write!(f, ": <redacted>")?;
+                } else if let Some(formatter) = formatter {
+                    write!(f, ": {}", Rendered(value, &**formatter).style(style))?;
+                } else if let Some(display) = slot.display {
+                    write!(f, ": {}", Rendered(value, &display).style(style))?;
+                }
+            }
+
+            let provenance = self.config.display_extension_provenance;
+            if let Some(location) = slot.location.filter(|_| provenance) {
+                write!(f, " (attached at {}:{})", location.file(), location.line())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Collects values drained from another map, see [`Extensions::drain`].
+///
+/// # Example
+///
+/// ```
+/// # use extension_eyre::extensions::Extensions;
+/// use std::any::{Any, TypeId};
+///
+/// let values: Vec<(TypeId, Box<dyn Any + Send + Sync>)> = vec![
+///     (TypeId::of::<i32>(), Box::new(5i32)),
+///     (TypeId::of::<u8>(), Box::new(4u8)),
+/// ];
+///
+/// let mut ext = values.into_iter().collect::<Extensions>();
+/// assert_eq!(ext.get::<i32>(), Some(&5i32));
+///
+/// let mut other = Extensions::new();
+/// other.insert(String::from("moved"));
+///
+/// // `Extensions::extend` merges whole maps, so name the trait to extend from an iterator
+/// Extend::extend(&mut ext, other.drain());
+/// assert_eq!(ext.len(), 3);
+/// assert_eq!(ext.get::<String>().unwrap(), "moved");
+/// ```
+impl<V: Into<BoxedExtension>> FromIterator<(TypeId, V)> for Extensions {
+    fn from_iter<I: IntoIterator<Item = (TypeId, V)>>(iter: I) -> Self {
+        let mut extensions = Extensions::new();
+        Extend::extend(&mut extensions, iter);
+        extensions
+    }
+}
+
+impl<V: Into<BoxedExtension>> Extend<(TypeId, V)> for Extensions {
+    fn extend<I: IntoIterator<Item = (TypeId, V)>>(&mut self, iter: I) {
+        for (type_id, value) in iter {
+            self.insert_boxed(type_id, value);
+        }
+    }
+}
+
+/// An extension removed from a map by [`Extensions::drain`], ready to be inserted into another
+/// with [`Extensions::insert_boxed`].
+///
+/// Besides the value, it keeps the values [pushed](Extensions::push) before it, the [`Key`] it
+/// was stored under and how it was inserted, e.g. with
+/// [`insert_redacted`](Extensions::insert_redacted).
+///
+/// # Example
+///
+/// ```
+/// # use extension_eyre::extensions::Extensions;
+/// let mut ext = Extensions::new();
+/// ext.insert(5i32);
+///
+/// let (_, value) = ext.drain().next().unwrap();
+/// assert_eq!(value.type_name(), "i32");
+/// assert_eq!(value.into_value().downcast_ref::<i32>(), Some(&5i32));
+/// ```
+pub struct BoxedExtension {
+    slot: Slot,
+    key: Option<&'static str>,
+}
+
+impl BoxedExtension {
+    fn new(slot: Slot) -> Self {
+        BoxedExtension { slot, key: None }
+    }
+
+    fn keyed(slot: Slot, key: &'static str) -> Self {
+        BoxedExtension {
+            slot,
+            key: Some(key),
+        }
+    }
+
+    /// The name of the value's type, or `<unknown>` if it was inserted from a plain box.
+    pub fn type_name(&self) -> &'static str {
+        self.slot.type_name
+    }
+
+    /// The name of the [`Key`] the value was stored under, if any.
+    pub fn key(&self) -> Option<&'static str> {
+        self.key
+    }
+
+    /// Takes the most recent value, dropping the values pushed before it.
+    pub fn into_value(self) -> Box<dyn Any + Send + Sync> {
+        self.slot.value
+    }
+}
+
+impl From<Box<dyn Any + Send + Sync>> for BoxedExtension {
+    fn from(value: Box<dyn Any + Send + Sync>) -> Self {
+        BoxedExtension::new(Slot::from_boxed(value))
+    }
+}
+
+impl fmt::Debug for BoxedExtension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BoxedExtension")
+            .field("type_name", &self.slot.type_name)
+            .field("key", &self.key)
+            .finish()
+    }
+}
+
+/// The error returned by [`Extensions::try_clone_map`] when an extension can't be cloned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotCloneable {
+    type_name: &'static str,
+}
+
+impl NotCloneable {
+    /// The name of the type that wasn't inserted with
+    /// [`insert_cloneable`](Extensions::insert_cloneable).
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+}
+
+impl fmt::Display for NotCloneable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "extension of type `{}` is not cloneable", self.type_name)
+    }
+}
+
+impl std::error::Error for NotCloneable {}
+
+fn clone_value<T: Clone + Send + Sync + 'static>(
+    value: &(dyn Any + Send + Sync),
+) -> Box<dyn Any + Send + Sync> {
+    let value = (value as &(dyn Any + 'static))
+        .downcast_ref::<T>()
+        .expect("cloned value has the type of its slot");
+
+    Box::new(value.clone())
+}
+
+fn display_value<T: fmt::Display + Send + Sync + 'static>(
+    value: &(dyn Any + Send + Sync),
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    let value = (value as &(dyn Any + 'static))
+        .downcast_ref::<T>()
+        .expect("displayed value has the type of its slot");
+
+    fmt::Display::fmt(value, f)
+}
+
+/// A view into the slot for type `T` in an [`Extensions`], which may be vacant or occupied.
+///
+/// This is constructed from [`Extensions::entry`].
+pub struct Entry<'a, T> {
+    extensions: &'a mut Extensions,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<'a, T: Send + Sync + 'static> Entry<'a, T> {
+    /// Ensures a value is present by inserting `default` if the slot is vacant, and returns a
+    /// mutable reference to the value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// let mut ext = Extensions::new();
+    /// *ext.entry().or_insert(1u8) += 1;
+    ///
+    /// assert_eq!(ext.get::<u8>(), Some(&2u8));
+    /// ```
+    pub fn or_insert(self, default: T) -> &'a mut T {
+        self.extensions.get_or_insert(default)
+    }
+
+    /// Ensures a value is present by inserting the result of `f` if the slot is vacant, and
+    /// returns a mutable reference to the value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// let mut ext = Extensions::new();
+    /// ext.entry().or_insert_with(|| String::from("Hello")).push_str(" World");
+    ///
+    /// assert_eq!(ext.get::<String>().unwrap(), "Hello World");
+    /// ```
+    pub fn or_insert_with<F: FnOnce() -> T>(self, f: F) -> &'a mut T {
+        self.extensions.get_or_insert_with(f)
+    }
+
+    /// Calls `f` with the value if the slot is occupied, and returns the entry for further
+    /// chaining.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// let mut ext = Extensions::new();
+    /// ext.entry::<u8>().and_modify(|n| *n += 1);
+    /// assert!(ext.get::<u8>().is_none());
+    ///
+    /// ext.insert(1u8);
+    /// ext.entry::<u8>().and_modify(|n| *n += 1);
+    /// assert_eq!(ext.get::<u8>(), Some(&2u8));
+    /// ```
+    pub fn and_modify<F: FnOnce(&mut T)>(self, f: F) -> Self {
+        if let Some(value) = self.extensions.get_mut::<T>() {
+            f(value);
+        }
+
+        self
+    }
+}
+
+impl<'a, T: Default + Send + Sync + 'static> Entry<'a, T> {
+    /// Ensures a value is present by inserting `T::default()` if the slot is vacant, and returns
+    /// a mutable reference to the value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// let mut ext = Extensions::new();
+    /// *ext.entry::<u32>().or_default() += 3;
+    ///
+    /// assert_eq!(ext.get::<u32>(), Some(&3u32));
+    /// ```
+    pub fn or_default(self) -> &'a mut T {
+        self.extensions.get_or_insert_with(T::default)
+    }
+}
+
+/// Lists the type names of the stored extensions.
+///
+/// # Example
+///
+/// ```
+/// # use extension_eyre::extensions::Extensions;
+/// use std::any::type_name;
+///
+/// pub struct Retry;
+/// pub struct Counter(usize);
+///
+/// let mut ext = Extensions::new();
+/// assert_eq!(format!("{:?}", ext), "Extensions {}");
+/// assert_eq!(format!("{:#?}", ext), "Extensions {}");
+///
+/// ext.insert(Retry);
+/// ext.insert(Counter(0));
+///
+/// let (counter, retry) = (type_name::<Counter>(), type_name::<Retry>());
+/// assert_eq!(
+///     format!("{:?}", ext),
+///     format!("Extensions {{ {:?}, {:?} }}", counter, retry)
+/// );
+/// assert_eq!(
+///     format!("{:#?}", ext),
+///     format!("Extensions {{\n    {:?},\n    {:?},\n}}", counter, retry)
+/// );
+/// ```
 impl fmt::Debug for Extensions {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Extensions").finish()
+        f.write_str("Extensions ")?;
+
+        if f.alternate() {
+            return f.debug_set().entries(self.type_names()).finish();
+        }
+
+        let mut names = self.type_names();
+        match names.next() {
+            None => f.write_str("{}"),
+            Some(first) => {
+                write!(f, "{{ {:?}", first)?;
+                for name in names {
+                    write!(f, ", {:?}", name)?;
+                }
+                f.write_str(" }")
+            }
+        }
     }
 }
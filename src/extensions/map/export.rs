@@ -0,0 +1,381 @@
+//! JSON export of the values inserted with `Extensions::insert_serializable`
+//!
+//! Every path here borrows the stored values: [`Extensions::to_json_writer`] and
+//! [`Extensions::for_each_pair`] serialize straight from the map without building intermediate
+//! values, while [`Extensions::to_json_value`] and [`Extensions::to_string_pairs`] are owned
+//! conveniences on top.
+use std::any::Any;
+use std::borrow::Cow;
+use std::io;
+
+use serde::ser::{Error as _, Serialize, SerializeMap, Serializer};
+use serde_json::Value;
+
+use super::{Extensions, Slot};
+
+type Erased<'a> = &'a (dyn Any + Send + Sync);
+
+/// How to serialize a value whose concrete type has been erased.
+#[derive(Clone, Copy)]
+pub(super) struct Serializable {
+    to_value: fn(Erased<'_>) -> Option<Value>,
+    to_writer: fn(Erased<'_>, &mut dyn io::Write) -> serde_json::Result<()>,
+}
+
+impl Serializable {
+    pub(super) fn of<T: Serialize + 'static>() -> Self {
+        Serializable {
+            to_value: to_value::<T>,
+            to_writer: to_writer::<T>,
+        }
+    }
+}
+
+fn to_value<T: Serialize + 'static>(value: Erased<'_>) -> Option<Value> {
+    serde_json::to_value(downcast::<T>(value)?).ok()
+}
+
+fn to_writer<T: Serialize + 'static>(
+    value: Erased<'_>,
+    writer: &mut dyn io::Write,
+) -> serde_json::Result<()> {
+    let value = downcast::<T>(value)
+        .ok_or_else(|| serde_json::Error::custom("extension is stored under another type"))?;
+    serde_json::to_writer(writer, value)
+}
+
+fn downcast<T: 'static>(value: Erased<'_>) -> Option<&T> {
+    (value as &(dyn Any + 'static)).downcast_ref()
+}
+
+/// Borrows string-like values, so they can be handed out without serializing them.
+fn as_str(value: Erased<'_>) -> Option<&str> {
+    let value = value as &(dyn Any + 'static);
+
+    if let Some(s) = value.downcast_ref::<String>() {
+        Some(s)
+    } else if let Some(s) = value.downcast_ref::<&'static str>() {
+        Some(s)
+    } else if let Some(s) = value.downcast_ref::<Cow<'static, str>>() {
+        Some(s)
+    } else {
+        value.downcast_ref::<Box<str>>().map(|s| &**s)
+    }
+}
+
+impl Extensions {
+    /// The serializable entries with the key each is exported under, sorted by key like the
+    /// objects of `serde_json`.
+    fn serializable(&self) -> Vec<(&'static str, &Slot, Serializable)> {
+        let map = match &self.map {
+            Some(map) => map,
+            None => return Vec::new(),
+        };
+
+        let mut entries = Vec::with_capacity(map.len());
+        for (type_id, slot) in map.iter() {
+            if slot.is_redacted(*type_id) {
+                continue;
+            }
+
+            if let Some(serializable) = slot.serializable {
+                let key = crate::catalog::key_of(*type_id).unwrap_or(slot.type_name);
+                entries.push((key, slot, serializable));
+            }
+        }
+        entries.sort_unstable_by_key(|&(key, _, _)| key);
+
+        entries
+    }
+
+    /// Serialize the values inserted with
+    /// [`insert_serializable`](Extensions::insert_serializable) into a JSON object.
+    ///
+    /// This produces the same JSON as the `Serialize` impl of `Extensions`, owning a copy of
+    /// every value. Use [`to_json_writer`](Extensions::to_json_writer) to write the JSON out
+    /// without copying.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// let mut ext = Extensions::new();
+    /// ext.insert_serializable(String::from("upstream body"));
+    /// ext.insert(4u8);
+    ///
+    /// assert_eq!(
+    ///     ext.to_json_value(),
+    ///     serde_json::json!({ "alloc::string::String": "upstream body" })
+    /// );
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn to_json_value(&self) -> Value {
+        let entries = self
+            .serializable()
+            .into_iter()
+            .filter_map(|(key, slot, serializable)| {
+                let mut values = slot
+                    .values()
+                    .filter_map(serializable.to_value)
+                    .collect::<Vec<_>>();
+                let value = if values.len() == 1 {
+                    values.pop()?
+                } else {
+                    Value::Array(values)
+                };
+
+                Some((key.to_owned(), value))
+            });
+
+        Value::Object(entries.collect())
+    }
+
+    /// Write the values inserted with
+    /// [`insert_serializable`](Extensions::insert_serializable) to `writer` as a JSON object.
+    ///
+    /// Values are serialized straight from the map, so string extensions are written without
+    /// being copied. The output holds the same JSON as
+    /// [`to_json_value`](Extensions::to_json_value), though struct fields keep their declaration
+    /// order instead of being sorted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// pub struct Upstream {
+    ///     host: String,
+    ///     body: Vec<u8>,
+    /// }
+    ///
+    /// let mut ext = Extensions::new();
+    /// ext.insert_serializable(String::from("GET /api/users"));
+    /// ext.insert_serializable(Upstream { host: "db".into(), body: b"502".to_vec() });
+    /// ext.insert_serializable(1u8);
+    /// ext.push(2u8);
+    /// ext.insert(std::time::Instant::now());
+    ///
+    /// let mut borrowed = Vec::new();
+    /// ext.to_json_writer(&mut borrowed).unwrap();
+    ///
+    /// let borrowed: serde_json::Value = serde_json::from_slice(&borrowed).unwrap();
+    /// assert_eq!(borrowed, ext.to_json_value());
+    /// assert_eq!(borrowed, serde_json::to_value(&ext).unwrap());
+    /// ```
+    ///
+    /// Writing ten 1 KiB strings only allocates the list of entries to write, where building
+    /// the JSON value copies each of them:
+    ///
+    /// ```
+    /// use extension_eyre::extensions::{Extensions, Key};
+    /// use std::alloc::{GlobalAlloc, Layout, System};
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// struct Counting;
+    ///
+    /// static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// unsafe impl GlobalAlloc for Counting {
+    ///     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    ///         ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    ///         System.alloc(layout)
+    ///     }
+    ///
+    ///     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    ///         System.dealloc(ptr, layout)
+    ///     }
+    /// }
+    ///
+    /// #[global_allocator]
+    /// static GLOBAL: Counting = Counting;
+    ///
+    /// fn allocations(f: impl FnOnce()) -> usize {
+    ///     let before = ALLOCATIONS.load(Ordering::Relaxed);
+    ///     f();
+    ///     ALLOCATIONS.load(Ordering::Relaxed) - before
+    /// }
+    ///
+    /// macro_rules! strings {
+    ///     ($($name:ident)*) => {
+    ///         $(pub struct $name(String);
+    ///         impl serde::Serialize for $name {
+    ///             fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+    ///                 self.0.serialize(s)
+    ///             }
+    ///         })*
+    ///     };
+    /// }
+    ///
+    /// strings!(A B C D E F G H I J);
+    ///
+    /// fn main() {
+    ///     let kib = || "x".repeat(1024);
+    ///     let mut ext = Extensions::new();
+    ///     ext.insert_serializable(A(kib()));
+    ///     ext.insert_serializable(B(kib()));
+    ///     ext.insert_serializable(C(kib()));
+    ///     ext.insert_serializable(D(kib()));
+    ///     ext.insert_serializable(E(kib()));
+    ///     ext.insert_serializable(F(kib()));
+    ///     ext.insert_serializable(G(kib()));
+    ///     ext.insert_serializable(H(kib()));
+    ///     ext.insert_serializable(I(kib()));
+    ///     ext.insert_serializable(J(kib()));
+    ///
+    ///     let mut owned = Vec::with_capacity(16 * 1024);
+    ///     let mut borrowed = Vec::with_capacity(16 * 1024);
+    ///
+    ///     let owned_allocations = allocations(|| {
+    ///         serde_json::to_writer(&mut owned, &ext.to_json_value()).unwrap()
+    ///     });
+    ///     let borrowed_allocations = allocations(|| ext.to_json_writer(&mut borrowed).unwrap());
+    ///
+    ///     assert_eq!(owned, borrowed);
+    ///     assert!(owned_allocations >= 10, "{}", owned_allocations);
+    ///     assert!(borrowed_allocations <= 1, "{}", borrowed_allocations);
+    /// }
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn to_json_writer<W: io::Write>(&self, mut writer: W) -> serde_json::Result<()> {
+        writer.write_all(b"{").map_err(serde_json::Error::io)?;
+
+        for (i, (key, slot, serializable)) in self.serializable().into_iter().enumerate() {
+            if i > 0 {
+                writer.write_all(b",").map_err(serde_json::Error::io)?;
+            }
+            serde_json::to_writer(&mut writer, key)?;
+            writer.write_all(b":").map_err(serde_json::Error::io)?;
+
+            let several = slot.values().nth(1).is_some();
+            if several {
+                writer.write_all(b"[").map_err(serde_json::Error::io)?;
+            }
+            for (j, value) in slot.values().enumerate() {
+                if j > 0 {
+                    writer.write_all(b",").map_err(serde_json::Error::io)?;
+                }
+                (serializable.to_writer)(value, &mut writer)?;
+            }
+            if several {
+                writer.write_all(b"]").map_err(serde_json::Error::io)?;
+            }
+        }
+
+        writer.write_all(b"}").map_err(serde_json::Error::io)
+    }
+
+    /// Call `f` with the export key and text of every value inserted with
+    /// [`insert_serializable`](Extensions::insert_serializable).
+    ///
+    /// String values are passed as they are stored, without copying or quoting; other values
+    /// are passed as their JSON text, written into a buffer reused across calls. Types with
+    /// several values pushed produce one call per value, oldest first.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// let mut ext = Extensions::new();
+    /// ext.insert_serializable(String::from("/api/users"));
+    /// ext.insert_serializable(1u8);
+    /// ext.push(2u8);
+    ///
+    /// let mut pairs = Vec::new();
+    /// ext.for_each_pair(|key, value| pairs.push(format!("{}={}", key, value)));
+    /// pairs.sort();
+    ///
+    /// assert_eq!(pairs, ["alloc::string::String=/api/users", "u8=1", "u8=2"]);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn for_each_pair<F: FnMut(&str, &str)>(&self, mut f: F) {
+        let mut buffer = Vec::new();
+
+        for (key, slot, serializable) in self.serializable() {
+            for value in slot.values() {
+                if let Some(s) = as_str(value) {
+                    f(key, s);
+                    continue;
+                }
+
+                buffer.clear();
+                if (serializable.to_writer)(value, &mut buffer).is_ok() {
+                    if let Ok(text) = std::str::from_utf8(&buffer) {
+                        f(key, text);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Collect the pairs produced by [`for_each_pair`](Extensions::for_each_pair) into owned
+    /// strings.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use extension_eyre::extensions::Extensions;
+    /// let mut ext = Extensions::new();
+    /// ext.insert_serializable(String::from("/api/users"));
+    /// ext.insert_serializable(vec![1u8, 2]);
+    ///
+    /// let mut borrowed = Vec::new();
+    /// ext.for_each_pair(|key, value| borrowed.push((key.to_owned(), value.to_owned())));
+    ///
+    /// let owned = ext.to_string_pairs();
+    /// assert_eq!(owned, borrowed);
+    /// assert!(owned.contains(&("alloc::vec::Vec<u8>".to_owned(), "[1,2]".to_owned())));
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn to_string_pairs(&self) -> Vec<(String, String)> {
+        let mut pairs = Vec::new();
+        self.for_each_pair(|key, value| pairs.push((key.to_owned(), value.to_owned())));
+        pairs
+    }
+}
+
+/// Serializes the values inserted with [`insert_serializable`](Extensions::insert_serializable)
+/// as a map, skipping all other values.
+///
+/// Values are keyed by the key their type was [registered](crate::catalog::register) under, or
+/// by their Rust type name. Types with several values pushed are serialized as an array of
+/// them, oldest first.
+///
+/// # Example
+///
+/// ```
+/// use extension_eyre::{eyre::eyre, Extension, ExtensionExt};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// pub struct RequestId(String);
+///
+/// pub struct Retry;
+///
+/// extension_eyre::declare_extension!(RequestId as "request_id", "Id of the request");
+/// extension_eyre::catalog::register::<RequestId>();
+///
+/// extension_eyre::install().unwrap();
+///
+/// let mut err = eyre!("connection reset").extension(Retry);
+/// err.extensions_mut().unwrap().insert_serializable(RequestId("abc".into()));
+///
+/// let json = serde_json::to_string(err.extensions_ref().unwrap()).unwrap();
+/// assert_eq!(json, r#"{"request_id":"abc"}"#);
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl Serialize for Extensions {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let entries = match self.to_json_value() {
+            Value::Object(entries) => entries,
+            _ => unreachable!("extensions serialize to an object"),
+        };
+
+        let mut map = serializer.serialize_map(Some(entries.len()))?;
+        for (key, value) in &entries {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
@@ -0,0 +1,96 @@
+use std::borrow::Cow;
+use std::fmt::{self, Display};
+use std::slice;
+
+type MetaValue = Box<dyn Display + Send + Sync>;
+
+/// String keyed metadata attached with [`ExtensionExt::meta`](crate::ExtensionExt::meta).
+///
+/// Kept apart from the typed [`Extensions`](super::Extensions), so keys here never collide with
+/// extension types. Entries stay in the order their key was first attached; attaching a key
+/// again replaces its value in place.
+#[derive(Default)]
+pub(crate) struct Meta {
+    entries: Vec<(Cow<'static, str>, MetaValue)>,
+}
+
+impl Meta {
+    pub(crate) fn insert(&mut self, key: Cow<'static, str>, value: MetaValue) {
+        match self.entries.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, existing)) => *existing = value,
+            None => self.entries.push((key, value)),
+        }
+    }
+
+    /// Moves the entries of `other` whose keys aren't attached here, after the existing ones.
+    pub(crate) fn adopt(&mut self, other: Meta) {
+        for (key, value) in other.entries {
+            if self.get(&key).is_none() {
+                self.entries.push((key, value));
+            }
+        }
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<&dyn Display> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| &**value as &dyn Display)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub(crate) fn iter(&self) -> MetaIter<'_> {
+        MetaIter {
+            inner: self.entries.iter(),
+        }
+    }
+}
+
+/// Renders the metadata as a section with the keys aligned, or nothing if there is none.
+impl Display for Meta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.entries.is_empty() {
+            return Ok(());
+        }
+
+        let width = self.entries.iter().map(|(k, _)| k.len()).max().unwrap_or(0);
+
+        write!(f, "Metadata:")?;
+        for (key, value) in &self.entries {
+            write!(f, "\n   {:width$} : {}", key, value, width = width)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Iterator over the metadata attached to a report, returned by
+/// [`Extension::meta_iter`](crate::Extension::meta_iter).
+pub struct MetaIter<'a> {
+    inner: slice::Iter<'a, (Cow<'static, str>, MetaValue)>,
+}
+
+impl<'a> MetaIter<'a> {
+    pub(crate) fn empty() -> Self {
+        MetaIter { inner: [].iter() }
+    }
+}
+
+impl<'a> Iterator for MetaIter<'a> {
+    type Item = (&'a str, &'a dyn Display);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|(key, value)| (&**key, &**value as &dyn Display))
+    }
+}
+
+impl fmt::Debug for MetaIter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MetaIter").finish()
+    }
+}
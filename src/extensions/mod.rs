@@ -4,11 +4,27 @@
 //!
 //! [`Extension`] trait allows accessing custom data from error reports.
 
+mod frames;
 mod map;
+mod meta;
+mod nested;
+mod visit;
 
-use color_eyre::Report;
-pub use map::Extensions;
+use std::any::{type_name, Any, TypeId};
+use std::borrow::Cow;
+use std::fmt::{self, Display};
+use std::panic::Location;
 
+use crate::eyre::Report;
+pub use frames::{ChainWithExtensions, FrameExtensions};
+pub(crate) use frames::Frames;
+pub use map::{BoxedExtension, Entry, Extensions, Key, NotCloneable};
+pub(crate) use meta::Meta;
+pub use meta::MetaIter;
+pub use nested::NestedReport;
+pub use visit::ExtensionVisitorRegistry;
+
+use crate::config::Source;
 use crate::private::Sealed;
 
 /// Trait for accessing custom data from errors.
@@ -112,6 +128,48 @@ pub trait Extension: Sealed {
     /// ```
     fn extension_ref<T: Send + Sync + 'static>(&self) -> Option<&T>;
 
+    /// Method for accessing custom data from errors, failing with a description of why it is
+    /// missing.
+    ///
+    /// This trait is implemented for `eyre::Report`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::extensions::MissingReason;
+    /// use extension_eyre::{eyre::eyre, ExtensionExt, Extension};
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct ErrorCode(u32);
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let err = eyre!("payment declined").extension(ErrorCode(402));
+    /// assert_eq!(err.try_extension_ref::<ErrorCode>().unwrap(), &ErrorCode(402));
+    ///
+    /// let missing = eyre!("payment declined").try_extension_ref::<ErrorCode>().unwrap_err();
+    /// assert_eq!(missing.reason(), MissingReason::NotAttached);
+    /// assert_eq!(missing.type_name(), std::any::type_name::<ErrorCode>());
+    /// ```
+    ///
+    /// Reports created while another handler is installed can't carry extensions at all:
+    ///
+    /// ```rust
+    /// use extension_eyre::extensions::MissingReason;
+    /// use extension_eyre::{eyre, Extension};
+    ///
+    /// #[derive(Debug)]
+    /// pub struct ErrorCode(u32);
+    ///
+    /// // `extension_eyre::install()` was never called
+    /// let err = eyre::eyre!("payment declined");
+    /// let missing = err.try_extension_ref::<ErrorCode>().unwrap_err();
+    ///
+    /// assert_eq!(missing.reason(), MissingReason::ForeignHandler);
+    /// assert!(missing.to_string().contains("install"));
+    /// ```
+    fn try_extension_ref<T: Send + Sync + 'static>(&self) -> Result<&T, MissingExtension>;
+
     /// Method for accessing mutable custom data from errors.
     ///
     /// This trait is implemented for `eyre::Report`.
@@ -164,6 +222,114 @@ pub trait Extension: Sealed {
     /// ```
     fn extension_mut<T: Send + Sync + 'static>(&mut self) -> Option<&mut T>;
 
+    /// Method for accessing mutable custom data from errors, attaching `T::default()` first if
+    /// there is none.
+    ///
+    /// Returns `None` only if the error report wasn't created by extension-eyre's handler.
+    ///
+    /// This trait is implemented for `eyre::Report`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, Extension, eyre::Report};
+    ///
+    /// #[derive(Default)]
+    /// pub struct RetryCount(usize);
+    ///
+    /// fn fetch() -> Result<(), Report> {
+    ///     Err(eyre!("connection reset"))
+    /// }
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let mut err = fetch().unwrap_err();
+    /// for _ in 0..3 {
+    ///     err.extension_or_default::<RetryCount>().unwrap().0 += 1;
+    /// }
+    ///
+    /// assert_eq!(err.extension_ref::<RetryCount>().unwrap().0, 3);
+    /// ```
+    fn extension_or_default<T: Default + Send + Sync + 'static>(&mut self) -> Option<&mut T>;
+
+    /// Method for accessing custom data from errors, including reports nested in their source
+    /// chain.
+    ///
+    /// The report itself is searched first, then every [`NestedReport`] in its source chain, from
+    /// the outermost to the innermost. The first value of type `T` found is returned.
+    ///
+    /// This trait is implemented for `eyre::Report`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::extensions::NestedReport;
+    /// use extension_eyre::{eyre::eyre, ExtensionExt, Extension, eyre::Report};
+    /// use std::error::Error;
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct Retry(bool);
+    ///
+    /// #[derive(Debug)]
+    /// pub struct QueryError {
+    ///     source: NestedReport,
+    /// }
+    ///
+    /// impl std::fmt::Display for QueryError {
+    ///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    ///         f.write_str("query failed")
+    ///     }
+    /// }
+    ///
+    /// impl Error for QueryError {
+    ///     fn source(&self) -> Option<&(dyn Error + 'static)> {
+    ///         Some(&self.source)
+    ///     }
+    /// }
+    ///
+    /// fn connect() -> Result<(), Report> {
+    ///     Err(eyre!("connection reset")).extension(Retry(true))
+    /// }
+    ///
+    /// fn query() -> Result<(), Report> {
+    ///     connect().map_err(|inner| Report::new(QueryError { source: inner.into() }))
+    /// }
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let err = query().unwrap_err().wrap_err("loading users failed");
+    /// assert!(err.extension_ref::<Retry>().is_none());
+    /// assert_eq!(err.extension_ref_deep(), Some(&Retry(true)));
+    /// ```
+    fn extension_ref_deep<T: Send + Sync + 'static>(&self) -> Option<&T>;
+
+    /// Method for accessing mutable custom data from errors, including reports nested in them.
+    ///
+    /// Sources can only be borrowed immutably, so unlike [`Extension::extension_ref_deep`]
+    /// this only reaches a [`NestedReport`] that is the report's own error, or the error its
+    /// context wraps, and so on through further nesting. The outermost value of type `T` found
+    /// is returned.
+    ///
+    /// This trait is implemented for `eyre::Report`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::extensions::NestedReport;
+    /// use extension_eyre::{eyre::eyre, ExtensionExt, Extension, eyre::Report};
+    ///
+    /// pub struct Attempts(usize);
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let inner = eyre!("connection reset").extension(Attempts(1));
+    /// let mut err = Report::new(NestedReport::from(inner)).wrap_err("query failed");
+    ///
+    /// err.extension_mut_deep::<Attempts>().unwrap().0 += 1;
+    /// assert_eq!(err.extension_ref_deep::<Attempts>().unwrap().0, 2);
+    /// ```
+    fn extension_mut_deep<T: Send + Sync + 'static>(&mut self) -> Option<&mut T>;
+
     /// Method for accessing [`Extensions`] typemap within the error report.
     ///
     /// This trait is implemented for `eyre::Report`.
@@ -273,216 +439,2056 @@ pub trait Extension: Sealed {
     /// }
     /// ```
     fn extensions_mut(&mut self) -> Option<&mut Extensions>;
-}
-
-impl Extension for Report {
-    fn extension_ref<T: Send + Sync + 'static>(&self) -> Option<&T> {
-        if let Some(handler) = self.handler().downcast_ref::<crate::Handler>() {
-            return handler.extensions.get::<T>();
-        }
-
-        None
-    }
-
-    fn extension_mut<T: Send + Sync + 'static>(&mut self) -> Option<&mut T> {
-        if let Some(handler) = self.handler_mut().downcast_mut::<crate::Handler>() {
-            return handler.extensions.get_mut::<T>();
-        }
-
-        None
-    }
-
-    fn extensions_ref(&self) -> Option<&Extensions> {
-        if let Some(handler) = self.handler().downcast_ref::<crate::Handler>() {
-            return Some(&handler.extensions);
-        }
-
-        None
-    }
-
-    fn extensions_mut(&mut self) -> Option<&mut Extensions> {
-        if let Some(handler) = self.handler_mut().downcast_mut::<crate::Handler>() {
-            return Some(&mut handler.extensions);
-        }
-
-        None
-    }
-}
-
-/// Trait for attaching custom data to errors.
-///
-/// This trait is implemented for `eyre::Report` and `Result<T, E> where E: std::error::Error`.
-///
-/// ### Example
-///
-/// ```rust
-/// use extension_eyre::{eyre::eyre, ExtensionExt, Extension, eyre::Report};
-/// use std::process::Command;
-/// use tracing::instrument;
-///
-/// pub struct Retry;
-///
-/// trait Output {
-///     fn output2(&mut self) -> Result<String, Report>;
-/// }
-///
-/// impl Output for Command {
-///     #[instrument]
-///     fn output2(&mut self) -> Result<String, Report> {
-///         let output = self.output()?;
-///
-///         let stdout = String::from_utf8_lossy(&output.stdout);
-///
-///         if !output.status.success() {
-///             Err(eyre!("cmd exited with non-zero status code")).extension(Retry)
-///         } else {
-///             Ok(stdout.into())
-///         }
-///     }
-/// }
-/// ```
-pub trait ExtensionExt: Sealed {
-    #[allow(missing_docs)]
-    type Return;
 
-    /// Method for attaching custom data to errors.
+    /// Method for attaching custom data to errors while getting back the value it replaced.
     ///
-    /// This trait is implemented for `eyre::Report` and `Result<T, E> where E: std::error::Error`.
+    /// Unlike [`ExtensionExt::extension`], which silently overwrites an existing value of the
+    /// same type, this returns the previously attached value, if any.
+    ///
+    /// This trait is implemented for `eyre::Report`.
     ///
     /// ### Example
     ///
     /// ```rust
     /// use extension_eyre::{eyre::eyre, ExtensionExt, Extension, eyre::Report};
-    /// use std::process::Command;
-    /// use tracing::instrument;
     ///
-    /// pub struct Retry;
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct RequestId(u64);
     ///
-    /// trait Output {
-    ///     fn output2(&mut self) -> Result<String, Report>;
+    /// fn query() -> Result<(), Report> {
+    ///     Err(eyre!("connection reset")).extension(RequestId(1))
     /// }
     ///
-    /// impl Output for Command {
-    ///     #[instrument]
-    ///     fn output2(&mut self) -> Result<String, Report> {
-    ///         let output = self.output()?;
+    /// fn handle() -> Result<(), Report> {
+    ///     query().map_err(|mut err| {
+    ///         let previous = err.replace_extension(RequestId(2));
+    ///         assert_eq!(previous, Some(RequestId(1)));
+    ///         err
+    ///     })
+    /// }
     ///
-    ///         let stdout = String::from_utf8_lossy(&output.stdout);
+    /// extension_eyre::install().unwrap();
     ///
-    ///         if !output.status.success() {
-    ///             Err(eyre!("cmd exited with non-zero status code")).extension(Retry)
-    ///         } else {
-    ///             Ok(stdout.into())
-    ///         }
-    ///     }
-    /// }
+    /// let err = handle().unwrap_err();
+    /// assert_eq!(err.extension_ref::<RequestId>(), Some(&RequestId(2)));
     /// ```
-    fn extension<T: Send + Sync + 'static>(self, extension: T) -> Self::Return;
+    fn replace_extension<T: Send + Sync + 'static>(&mut self, extension: T) -> Option<T>;
 
-    /// Method for lazily attaching custom data to errors.
+    /// Method for moving custom data out of errors.
     ///
-    /// This trait is implemented for `eyre::Report` and `Result<T, E> where E: std::error::Error`.
+    /// The value is removed from the error report and returned by value, which makes this
+    /// useful for extensions that can't be cloned.
+    ///
+    /// This trait is implemented for `eyre::Report`.
     ///
     /// ### Example
     ///
     /// ```rust
     /// use extension_eyre::{eyre::eyre, ExtensionExt, Extension, eyre::Report};
-    /// use std::process::Command;
-    /// use tracing::instrument;
+    /// use std::sync::mpsc::{channel, Sender};
     ///
-    /// pub struct Retry(String);
+    /// pub struct Reply(Sender<String>);
     ///
-    /// trait Output {
-    ///     fn output2(&mut self) -> Result<String, Report>;
+    /// fn handle(reply: Sender<String>) -> Result<(), Report> {
+    ///     Err(eyre!("cmd exited with non-zero status code")).extension(Reply(reply))
     /// }
     ///
-    /// impl Output for Command {
-    ///     #[instrument]
-    ///     fn output2(&mut self) -> Result<String, Report> {
-    ///         let output = self.output()?;
+    /// extension_eyre::install().unwrap();
     ///
-    ///         let stdout = String::from_utf8_lossy(&output.stdout);
+    /// let (tx, rx) = channel();
+    /// let mut err = handle(tx).unwrap_err();
     ///
-    ///         if !output.status.success() {
-    ///             Err(eyre!("cmd exited with non-zero status code"))
-    ///                 .with_extension(|| Retry("Some big string".to_owned()))
-    ///         } else {
-    ///             Ok(stdout.into())
-    ///         }
-    ///     }
+    /// if let Some(Reply(reply)) = err.take_extension() {
+    ///     reply.send(err.to_string()).unwrap();
     /// }
+    ///
+    /// assert_eq!(rx.recv().unwrap(), "cmd exited with non-zero status code");
+    /// assert!(err.extension_ref::<Reply>().is_none());
+    /// assert!(err.take_extension::<Reply>().is_none());
     /// ```
-    fn with_extension<T: Send + Sync + 'static, F: FnOnce() -> T>(self, f: F) -> Self::Return;
+    fn take_extension<T: Send + Sync + 'static>(&mut self) -> Option<T>;
 
-    /// Method for removing attached data from errors if exists.
+    /// Method for keeping only the custom data for which `f` returns `true`.
     ///
-    /// This trait is implemented for `eyre::Report` and `Result<T, E> where E: std::error::Error`.
+    /// See [`Extensions::retain`]. This is a no-op if the error report wasn't created by
+    /// extension-eyre's handler.
+    ///
+    /// This trait is implemented for `eyre::Report`.
     ///
     /// ### Example
     ///
     /// ```rust
     /// use extension_eyre::{eyre::eyre, ExtensionExt, Extension, eyre::Report};
-    /// use std::process::Command;
-    /// use tracing::instrument;
+    /// use std::any::TypeId;
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct RequestId(u64);
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct Retry(bool);
+    /// pub struct DbConnection;
+    /// pub struct InternalPath(String);
+    ///
+    /// fn query() -> Result<(), Report> {
+    ///     Err(eyre!("connection reset"))
+    ///         .extension(RequestId(7))
+    ///         .extension(Retry(true))
+    ///         .extension(DbConnection)
+    ///         .extension(InternalPath("/srv/db".into()))
+    /// }
     ///
-    /// pub struct Retry;
+    /// extension_eyre::install().unwrap();
     ///
-    /// #[instrument]
-    /// fn read_file(path: &str) -> Result<String, Report> {
-    ///     Command::new("cat")
-    ///         .arg(path)
-    ///         .output2()
-    ///         .remove_extension::<Retry>()
-    /// }
+    /// let mut err = query().unwrap_err();
     ///
-    /// trait Output {
-    ///     fn output2(&mut self) -> Result<String, Report>;
-    /// }
+    /// // only forward the allow-listed extensions
+    /// let allowed = [TypeId::of::<RequestId>(), TypeId::of::<Retry>()];
+    /// err.retain_extensions(|type_id, _| allowed.contains(type_id));
     ///
-    /// impl Output for Command {
-    ///     #[instrument]
-    ///     fn output2(&mut self) -> Result<String, Report> {
-    ///         let output = self.output()?;
+    /// assert_eq!(err.extension_ref(), Some(&RequestId(7)));
+    /// assert_eq!(err.extension_ref(), Some(&Retry(true)));
+    /// assert!(err.extension_ref::<DbConnection>().is_none());
+    /// assert!(err.extension_ref::<InternalPath>().is_none());
+    /// ```
+    fn retain_extensions<F>(&mut self, f: F)
+    where
+        F: FnMut(&TypeId, &mut (dyn Any + Send + Sync)) -> bool;
+
+    /// Method for copying the custom data of errors that can be cloned.
     ///
-    ///         let stdout = String::from_utf8_lossy(&output.stdout);
+    /// See [`Extensions::clone_map`]: only extensions attached with
+    /// [`Extensions::insert_cloneable`] are copied. Returns `None` if the error report wasn't
+    /// created by extension-eyre's handler.
     ///
-    ///         if !output.status.success() {
-    ///             Err(eyre!("cmd exited with non-zero status code")).extension(Retry)
-    ///         } else {
-    ///             Ok(stdout.into())
-    ///         }
-    ///     }
+    /// This trait is implemented for `eyre::Report`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, ExtensionExt, Extension, eyre::Report};
+    ///
+    /// #[derive(Clone, Debug, PartialEq)]
+    /// pub struct RequestId(u64);
+    ///
+    /// fn handle() -> Result<(), Report> {
+    ///     let mut err = eyre!("connection reset");
+    ///     err.extensions_mut().unwrap().insert_cloneable(RequestId(7));
+    ///     Err(err)
     /// }
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let err = handle().unwrap_err();
+    ///
+    /// // one copy for the logs, the report itself becomes the response
+    /// let mut for_logs = err.clone_extensions().unwrap();
+    /// for_logs.insert(RequestId(8));
+    ///
+    /// assert_eq!(err.extension_ref(), Some(&RequestId(7)));
+    /// assert_eq!(for_logs.get(), Some(&RequestId(8)));
     /// ```
-    fn remove_extension<T: Send + Sync + 'static>(self) -> Self::Return;
-}
-
-impl ExtensionExt for Report {
-    type Return = Report;
-
-    fn extension<T: Send + Sync + 'static>(mut self, extension: T) -> Self::Return {
-        if let Some(handler) = self.handler_mut().downcast_mut::<crate::Handler>() {
-            handler.extensions.insert::<T>(extension);
-        }
+    fn clone_extensions(&self) -> Option<Extensions>;
 
-        self
-    }
-
-    fn with_extension<T: Send + Sync + 'static, F: FnOnce() -> T>(mut self, f: F) -> Self::Return {
-        if let Some(handler) = self.handler_mut().downcast_mut::<crate::Handler>() {
-            handler.extensions.insert::<T>(f());
-        }
+    /// Method for running the callbacks of `registry` for the custom data attached to errors.
+    ///
+    /// See [`Extensions::visit`]. Nothing runs if the error report wasn't created by
+    /// extension-eyre's handler.
+    ///
+    /// This trait is implemented for `eyre::Report`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::extensions::ExtensionVisitorRegistry;
+    /// use extension_eyre::{eyre::eyre, ExtensionExt, Extension};
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// pub struct StatusCode(u16);
+    ///
+    /// static SERVER_ERRORS: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let metrics = ExtensionVisitorRegistry::new().register(|status: &StatusCode| {
+    ///     if status.0 >= 500 {
+    ///         SERVER_ERRORS.fetch_add(1, Ordering::Relaxed);
+    ///     }
+    /// });
+    ///
+    /// eyre!("upstream failed").extension(StatusCode(502)).visit_extensions(&metrics);
+    /// eyre!("not found").extension(StatusCode(404)).visit_extensions(&metrics);
+    ///
+    /// assert_eq!(SERVER_ERRORS.load(Ordering::Relaxed), 1);
+    /// ```
+    fn visit_extensions(&self, registry: &ExtensionVisitorRegistry);
+
+    /// Method for moving the custom data and metadata of `other` into this error.
+    ///
+    /// Meant for reports that replace another one, e.g. `eyre!("failed to do X: {}", inner)`,
+    /// which starts out with nothing attached. Values already attached to this report win over
+    /// values of the same type or key from `other`; `other` is left without any. Does nothing if
+    /// either report wasn't created by extension-eyre's handler. See also [`wrap_report`].
+    ///
+    /// This trait is implemented for `eyre::Report`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, wrap_report, ExtensionExt, Extension, eyre::Report};
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct Retry(bool);
+    ///
+    /// fn read_block() -> Result<(), Report> {
+    ///     Err(eyre!("disk full")).extension(Retry(true))
+    /// }
+    ///
+    /// fn read_file() -> Result<(), Report> {
+    ///     read_block().map_err(|mut inner| {
+    ///         let mut outer = eyre!("failed to read block: {}", inner);
+    ///         outer.adopt_extensions_from(&mut inner);
+    ///         outer
+    ///     })
+    /// }
+    ///
+    /// fn load_config() -> Result<(), Report> {
+    ///     read_file().map_err(|err| wrap_report(err, "failed to load config"))
+    /// }
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let err = load_config().unwrap_err();
+    /// assert_eq!(err.to_string(), "failed to load config");
+    /// assert_eq!(err.extension_ref(), Some(&Retry(true)));
+    /// ```
+    fn adopt_extensions_from(&mut self, other: &mut Report);
+
+    /// Method for copying the cloneable custom data of `other` into this error.
+    ///
+    /// Copies the values [`Extensions::clone_map`] copies, leaving `other` as it is. Values
+    /// already attached to this report win over values of the same type from `other`. Does
+    /// nothing if either report wasn't created by extension-eyre's handler.
+    ///
+    /// This trait is implemented for `eyre::Report`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, Extension, ExtensionExt};
+    ///
+    /// #[derive(Debug, Clone, PartialEq)]
+    /// pub struct RequestId(u64);
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let mut inner = eyre!("connection reset");
+    /// inner.extensions_mut().unwrap().insert_cloneable(RequestId(7));
+    ///
+    /// let mut outer = eyre!("The service is unavailable, try again later");
+    /// outer.copy_extensions_from(&inner);
+    ///
+    /// assert_eq!(outer.extension_ref(), Some(&RequestId(7)));
+    /// assert_eq!(inner.extension_ref(), Some(&RequestId(7)));
+    /// ```
+    fn copy_extensions_from(&mut self, other: &Report);
+
+    /// Method for taking all custom data attached to this error, leaving it with none.
+    ///
+    /// Returns an empty map if the error report wasn't created by extension-eyre's handler.
+    ///
+    /// This trait is implemented for `eyre::Report`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, Extension, ExtensionExt};
+    ///
+    /// // not cloneable
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct Connection(u32);
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let mut inner = eyre!("connection reset").extension(Connection(3));
+    /// let mut outer = eyre!("The service is unavailable, try again later");
+    ///
+    /// outer.set_extensions(inner.take_extensions());
+    ///
+    /// assert_eq!(outer.extension_ref(), Some(&Connection(3)));
+    /// assert_eq!(inner.extension_ref::<Connection>(), None);
+    /// assert!(inner.extensions_ref().unwrap().is_empty());
+    /// ```
+    fn take_extensions(&mut self) -> Extensions;
+
+    /// Method for replacing all custom data attached to this error with `extensions`.
+    ///
+    /// `extensions` is dropped if the error report wasn't created by extension-eyre's handler.
+    /// See [`take_extensions`](Extension::take_extensions).
+    ///
+    /// This trait is implemented for `eyre::Report`.
+    fn set_extensions(&mut self, extensions: Extensions);
+
+    /// Method for downcasting the error to `E`, handing back the custom data attached to it
+    /// along with it.
+    ///
+    /// On success, returns the error and the extensions taken with
+    /// [`take_extensions`](Extension::take_extensions). On failure, returns the report
+    /// untouched.
+    ///
+    /// This trait is implemented for `eyre::Report`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::Report, Extension, ExtensionExt};
+    /// use std::io;
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct Retry(bool);
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let timed_out = || io::Error::new(io::ErrorKind::TimedOut, "read timed out");
+    ///
+    /// let report = Report::new(timed_out()).extension(Retry(true));
+    /// let (error, extensions) = report.downcast_with_extensions::<io::Error>().unwrap();
+    /// assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+    /// assert_eq!(extensions.get(), Some(&Retry(true)));
+    ///
+    /// let (_, extensions) = Report::new(timed_out())
+    ///     .downcast_with_extensions::<io::Error>()
+    ///     .unwrap();
+    /// assert!(extensions.is_empty());
+    ///
+    /// let report = Report::new(timed_out()).extension(Retry(true));
+    /// let report = report.downcast_with_extensions::<std::fmt::Error>().unwrap_err();
+    /// assert_eq!(report.to_string(), "read timed out");
+    /// assert_eq!(report.extension_ref(), Some(&Retry(true)));
+    /// ```
+    fn downcast_with_extensions<E>(self) -> Result<(E, Extensions), Report>
+    where
+        Self: Sized,
+        E: Display + fmt::Debug + Send + Sync + 'static;
+
+    /// Method for downcasting a reference to the error to `E`, along with a reference to its
+    /// custom data of type `X`, if any.
+    ///
+    /// Returns `None` if the error isn't an `E`.
+    ///
+    /// This trait is implemented for `eyre::Report`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::Report, Extension, ExtensionExt};
+    /// use extension_eyre::builtin::HttpStatus;
+    /// use std::io;
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let not_found = io::Error::new(io::ErrorKind::NotFound, "no such user");
+    /// let report = Report::new(not_found).status(404);
+    ///
+    /// match report.downcast_ref_and_extension::<io::Error, HttpStatus>() {
+    ///     Some((error, Some(status))) if error.kind() == io::ErrorKind::NotFound => {
+    ///         assert_eq!(status.0, 404);
+    ///     }
+    ///     _ => unreachable!(),
+    /// }
+    ///
+    /// let found = report.downcast_ref_and_extension::<std::fmt::Error, HttpStatus>();
+    /// assert!(found.is_none());
+    /// ```
+    fn downcast_ref_and_extension<E, X>(&self) -> Option<(&E, Option<&X>)>
+    where
+        E: Display + fmt::Debug + Send + Sync + 'static,
+        X: Send + Sync + 'static;
+
+    /// Method for accessing metadata attached with [`ExtensionExt::meta`].
+    ///
+    /// Metadata lives apart from typed extensions, so a key never matches an extension.
+    ///
+    /// This trait is implemented for `eyre::Report`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, ExtensionExt, Extension, eyre::Report};
+    ///
+    /// fn place_order(order_id: u64) -> Result<(), Report> {
+    ///     Err(eyre!("payment declined")).meta("order_id", order_id)
+    /// }
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let err = place_order(12345).unwrap_err();
+    /// assert_eq!(err.meta_ref("order_id").unwrap().to_string(), "12345");
+    /// assert!(err.meta_ref("user_id").is_none());
+    /// ```
+    fn meta_ref(&self, key: &str) -> Option<&dyn Display>;
+
+    /// Method for iterating over all metadata attached with [`ExtensionExt::meta`], in the
+    /// order the keys were first attached.
+    ///
+    /// This trait is implemented for `eyre::Report`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, ExtensionExt, Extension, eyre::Report};
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let err = eyre!("payment declined")
+    ///     .meta("order_id", 12345)
+    ///     .meta("user", "jack");
+    ///
+    /// let pairs = err
+    ///     .meta_iter()
+    ///     .map(|(key, value)| format!("{}={}", key, value))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(pairs, ["order_id=12345", "user=jack"]);
+    /// ```
+    fn meta_iter(&self) -> MetaIter<'_>;
+
+    /// Method for iterating over the extensions attached to the frames of the report's chain
+    /// with [`ExtensionExt::frame_extension`].
+    ///
+    /// Yields the index of each frame holding extensions, `0` being the outermost error as in
+    /// [`Report::chain`], with its extensions, outermost first.
+    ///
+    /// This trait is implemented for `eyre::Report`.
+    ///
+    /// See [`ExtensionExt::frame_extension`] for an example.
+    fn frame_extensions(&self) -> FrameExtensions<'_>;
+
+    /// Method for iterating over the errors of the report's chain, outermost first, each paired
+    /// with the extensions that apply to it.
+    ///
+    /// # Details
+    ///
+    /// - the outermost error comes with the extensions attached to the report
+    /// - a [`NestedReport`] source comes with the extensions attached to the nested report
+    /// - other errors come with the extensions attached to their frame with
+    ///   [`ExtensionExt::frame_extension`], counted within the innermost report they belong to,
+    ///   if any
+    ///
+    /// The extensions attached to the frame of the outermost error of a report are only
+    /// available through [`Extension::frame_extensions`]. Reports created while another handler
+    /// is installed yield `None` for each error.
+    ///
+    /// This trait is implemented for `eyre::Report`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::extensions::NestedReport;
+    /// use extension_eyre::{eyre::eyre, eyre::Report, Extension, ExtensionExt, WrapErr};
+    /// use std::error::Error;
+    ///
+    /// #[derive(Debug)]
+    /// pub struct QueryError {
+    ///     source: NestedReport,
+    /// }
+    ///
+    /// impl std::fmt::Display for QueryError {
+    ///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    ///         f.write_str("query failed")
+    ///     }
+    /// }
+    ///
+    /// impl Error for QueryError {
+    ///     fn source(&self) -> Option<&(dyn Error + 'static)> {
+    ///         Some(&self.source)
+    ///     }
+    /// }
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct Pool(&'static str);
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct Retry(bool);
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct Route(&'static str);
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let inner = eyre!("connection reset")
+    ///     .frame_extension(Retry(true))
+    ///     .wrap_err("pool exhausted")
+    ///     .extension(Pool("primary"));
+    /// let report = Report::new(QueryError { source: inner.into() }).extension(Route("/prices"));
+    ///
+    /// let mut chain = report.chain_with_extensions();
+    ///
+    /// let (error, extensions) = chain.next().unwrap();
+    /// assert_eq!(error.to_string(), "query failed");
+    /// assert_eq!(extensions.unwrap().get(), Some(&Route("/prices")));
+    ///
+    /// let (error, extensions) = chain.next().unwrap();
+    /// assert_eq!(error.to_string(), "pool exhausted");
+    /// assert_eq!(extensions.unwrap().get(), Some(&Pool("primary")));
+    ///
+    /// let (error, extensions) = chain.next().unwrap();
+    /// assert_eq!(error.to_string(), "connection reset");
+    /// assert_eq!(extensions.unwrap().get(), Some(&Retry(true)));
+    ///
+    /// assert!(chain.next().is_none());
+    /// ```
+    fn chain_with_extensions(&self) -> ChainWithExtensions<'_>;
+
+    /// The exit code the process should end with for this error: the attached
+    /// [`ExitCode`](crate::builtin::ExitCode), else 2 for a [`Fatal`](crate::builtin::Severity)
+    /// error, or 1.
+    ///
+    /// This trait is implemented for `eyre::Report`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::builtin::ExitCode;
+    /// use extension_eyre::{eyre::eyre, ExtensionExt, Extension, eyre::Report};
+    ///
+    /// fn app() -> Result<(), Report> {
+    ///     Err(eyre!("invalid arguments")).extension(ExitCode(2))
+    /// }
+    ///
+    /// fn real_main() -> std::process::ExitCode {
+    ///     match app() {
+    ///         Ok(()) => std::process::ExitCode::SUCCESS,
+    ///         Err(report) => {
+    ///             eprintln!("Error: {:?}", report);
+    ///             std::process::ExitCode::from(report.exit_code())
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// extension_eyre::install().unwrap();
+    /// assert_eq!(real_main(), std::process::ExitCode::from(2));
+    /// ```
+    fn exit_code(&self) -> u8;
+
+    /// Method for getting the [`ErrorCode`](crate::builtin::ErrorCode) attached to errors.
+    ///
+    /// Returns the code attached last, or `None` if there is none or the error report wasn't
+    /// created by extension-eyre's handler.
+    ///
+    /// This trait is implemented for `eyre::Report`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::builtin::ErrorCode;
+    /// use extension_eyre::{eyre::eyre, Extension, ExtensionExt};
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let report = eyre!("unknown flag --colour").extension(ErrorCode("E0002"));
+    /// assert_eq!(report.error_code(), Some("E0002"));
+    /// assert_eq!(eyre!("unexpected failure").error_code(), None);
+    /// ```
+    fn error_code(&self) -> Option<&'static str>;
+
+    /// Method for getting a hash identifying the failure an error report describes.
+    ///
+    /// The hash covers the messages of the report's source chain and its
+    /// [`ErrorCode`](crate::builtin::ErrorCode)s, like the [`ErrorKey`](crate::ErrorKey) of
+    /// `KeySpec::new().extension::<ErrorCode>()`. Reports of the same failure get the same
+    /// fingerprint whatever their backtraces, which makes it a key for deduplicating them, see
+    /// [`ReportDeduper`](crate::ReportDeduper). It's stable across runs of the same build.
+    ///
+    /// This trait is implemented for `eyre::Report`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::builtin::ErrorCode;
+    /// use extension_eyre::{eyre::eyre, Extension, ExtensionExt};
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let fingerprint = eyre!("connection reset").fingerprint();
+    /// assert_eq!(eyre!("connection reset").fingerprint(), fingerprint);
+    /// assert_ne!(eyre!("connection refused").fingerprint(), fingerprint);
+    ///
+    /// let coded = eyre!("connection reset").extension(ErrorCode("E42"));
+    /// assert_ne!(coded.fingerprint(), fingerprint);
+    /// ```
+    fn fingerprint(&self) -> u64;
+
+    /// Method for writing the error report to a crash report file, returning its path.
+    ///
+    /// The file is written like the ones for panics, in the directory set with
+    /// [`HookBuilder::capture_crash_reports`](crate::config::HookBuilder::capture_crash_reports),
+    /// and its path is printed to stderr. If it can't be written, the reason is printed to stderr
+    /// along with the report, and the error is returned. Fails with
+    /// [`io::ErrorKind::Unsupported`](std::io::ErrorKind::Unsupported) if crash reports weren't
+    /// enabled.
+    ///
+    /// This trait is implemented for `eyre::Report`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, Extension};
+    ///
+    /// let dir = std::env::temp_dir().join(format!("fatal-reports-{}", std::process::id()));
+    /// std::fs::create_dir_all(&dir).unwrap();
+    ///
+    /// extension_eyre::config::HookBuilder::default()
+    ///     .capture_crash_reports(&dir)
+    ///     .install()
+    ///     .unwrap();
+    ///
+    /// let report = eyre!("database is corrupted");
+    /// let path = report.write_crash_report().unwrap();
+    /// assert!(path.starts_with(&dir));
+    ///
+    /// let contents = std::fs::read_to_string(&path).unwrap();
+    /// assert!(contents.contains("database is corrupted"));
+    /// assert!(contents.contains("\nMetadata:\n"));
+    ///
+    /// // every report gets its own file
+    /// assert_ne!(report.write_crash_report().unwrap(), path);
+    ///
+    /// // falls back to stderr
+    /// std::fs::remove_dir_all(&dir).unwrap();
+    /// assert!(report.write_crash_report().is_err());
+    /// ```
+    fn write_crash_report(&self) -> std::io::Result<std::path::PathBuf>;
+
+    /// Method for getting the time the error report was created at, if it was captured.
+    ///
+    /// See [`CreatedAt`](crate::builtin::CreatedAt).
+    ///
+    /// This trait is implemented for `eyre::Report`.
+    #[cfg(feature = "time")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "time")))]
+    fn created_at(&self) -> Option<time::OffsetDateTime>;
+
+    /// Method for getting the backtrace of where the error report was created, if it was
+    /// captured.
+    ///
+    /// See [`CapturedBacktrace`](crate::builtin::CapturedBacktrace).
+    ///
+    /// This trait is implemented for `eyre::Report`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, Extension};
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// // not captured unless enabled on the hook
+    /// let report = eyre!("connection reset");
+    /// assert!(report.captured_backtrace().is_none());
+    /// ```
+    fn captured_backtrace(&self) -> Option<&std::backtrace::Backtrace>;
+}
+
+// The handler of `report`, if it was created by this crate's hook. Reports created by another
+// handler are recorded for `migration::foreign_handler_sightings`.
+#[track_caller]
+fn handler(report: &Report) -> Option<&crate::Handler> {
+    let handler = report.handler().downcast_ref::<crate::Handler>();
+    if handler.is_none() {
+        crate::migration::record(report, Location::caller());
+    }
+
+    handler
+}
+
+#[track_caller]
+fn handler_mut(report: &mut Report) -> Option<&mut crate::Handler> {
+    handler(report)?;
+    report.handler_mut().downcast_mut::<crate::Handler>()
+}
+
+impl Extension for Report {
+    #[track_caller]
+    fn extension_ref<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        if let Some(handler) = handler(self) {
+            return handler.get::<T>();
+        }
+
+        None
+    }
+
+    #[track_caller]
+    fn try_extension_ref<T: Send + Sync + 'static>(&self) -> Result<&T, MissingExtension> {
+        let handler = handler(self).ok_or_else(|| MissingExtension {
+            type_name: type_name::<T>(),
+            reason: MissingReason::ForeignHandler,
+        })?;
+
+        handler.get::<T>().ok_or_else(|| MissingExtension {
+            type_name: type_name::<T>(),
+            reason: MissingReason::NotAttached,
+        })
+    }
+
+    #[track_caller]
+    fn extension_mut<T: Send + Sync + 'static>(&mut self) -> Option<&mut T> {
+        if let Some(handler) = handler_mut(self) {
+            return handler.get_mut::<T>();
+        }
+
+        None
+    }
+
+    #[track_caller]
+    fn extension_or_default<T: Default + Send + Sync + 'static>(&mut self) -> Option<&mut T> {
+        handler_mut(self).map(|handler| handler.extensions.get_or_default::<T>())
+    }
+
+    #[track_caller]
+    fn extension_ref_deep<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        if let Some(value) = handler(self).and_then(|handler| handler.get::<T>()) {
+            return Some(value);
+        }
+
+        crate::chain::Chain::of_report(self)
+            .filter_map(|error| error.downcast_ref::<NestedReport>())
+            .find_map(|nested| handler(nested.report())?.get::<T>())
+    }
+
+    #[track_caller]
+    fn extension_mut_deep<T: Send + Sync + 'static>(&mut self) -> Option<&mut T> {
+        let found = handler(self).is_some_and(|handler| handler.get::<T>().is_some());
+        if found {
+            return handler_mut(self).and_then(|handler| handler.get_mut::<T>());
+        }
+
+        self.downcast_mut::<NestedReport>()?
+            .report_mut()
+            .extension_mut_deep()
+    }
+
+    #[track_caller]
+    fn extensions_ref(&self) -> Option<&Extensions> {
+        if let Some(handler) = handler(self) {
+            return Some(&handler.extensions);
+        }
+
+        None
+    }
+
+    #[track_caller]
+    fn extensions_mut(&mut self) -> Option<&mut Extensions> {
+        if let Some(handler) = handler_mut(self) {
+            return Some(&mut handler.extensions);
+        }
+
+        None
+    }
+
+    #[track_caller]
+    fn replace_extension<T: Send + Sync + 'static>(&mut self, extension: T) -> Option<T> {
+        if let Some(handler) = handler_mut(self) {
+            return handler.extensions.insert::<T>(extension);
+        }
+
+        None
+    }
+
+    #[track_caller]
+    fn take_extension<T: Send + Sync + 'static>(&mut self) -> Option<T> {
+        if let Some(handler) = handler_mut(self) {
+            return handler.extensions.remove::<T>();
+        }
+
+        None
+    }
+
+    #[track_caller]
+    fn retain_extensions<F>(&mut self, f: F)
+    where
+        F: FnMut(&TypeId, &mut (dyn Any + Send + Sync)) -> bool,
+    {
+        if let Some(handler) = handler_mut(self) {
+            handler.extensions.retain(f);
+        }
+    }
+
+    #[track_caller]
+    fn clone_extensions(&self) -> Option<Extensions> {
+        handler(self).map(|handler| handler.extensions.clone_map())
+    }
+
+    #[track_caller]
+    fn visit_extensions(&self, registry: &ExtensionVisitorRegistry) {
+        if let Some(handler) = handler(self) {
+            handler.extensions.visit(registry);
+        }
+    }
+
+    #[track_caller]
+    fn adopt_extensions_from(&mut self, other: &mut Report) {
+        let other = match handler_mut(other) {
+            Some(other) => other,
+            None => return,
+        };
+
+        if let Some(handler) = handler_mut(self) {
+            let mut extensions = std::mem::take(&mut other.extensions);
+            extensions.extend(std::mem::take(&mut handler.extensions));
+            handler.extensions = extensions;
+
+            handler.meta.adopt(std::mem::take(&mut other.meta));
+        }
+    }
+
+    #[track_caller]
+    fn copy_extensions_from(&mut self, other: &Report) {
+        let mut extensions = match handler(other) {
+            Some(other) => other.extensions.clone_map(),
+            None => return,
+        };
+
+        if let Some(handler) = handler_mut(self) {
+            extensions.extend(std::mem::take(&mut handler.extensions));
+            handler.extensions = extensions;
+        }
+    }
+
+    #[track_caller]
+    fn take_extensions(&mut self) -> Extensions {
+        handler_mut(self)
+            .map(|handler| std::mem::take(&mut handler.extensions))
+            .unwrap_or_default()
+    }
+
+    #[track_caller]
+    fn set_extensions(&mut self, extensions: Extensions) {
+        if let Some(handler) = handler_mut(self) {
+            handler.extensions = extensions;
+        }
+    }
+
+    #[track_caller]
+    fn downcast_with_extensions<E>(mut self) -> Result<(E, Extensions), Report>
+    where
+        E: Display + fmt::Debug + Send + Sync + 'static,
+    {
+        if !self.is::<E>() {
+            return Err(self);
+        }
+
+        let extensions = self.take_extensions();
+        match self.downcast::<E>() {
+            Ok(error) => Ok((error, extensions)),
+            Err(mut report) => {
+                report.set_extensions(extensions);
+                Err(report)
+            }
+        }
+    }
+
+    #[track_caller]
+    fn downcast_ref_and_extension<E, X>(&self) -> Option<(&E, Option<&X>)>
+    where
+        E: Display + fmt::Debug + Send + Sync + 'static,
+        X: Send + Sync + 'static,
+    {
+        let error = self.downcast_ref::<E>()?;
+        Some((error, self.extension_ref::<X>()))
+    }
+
+    #[track_caller]
+    fn meta_ref(&self, key: &str) -> Option<&dyn Display> {
+        if let Some(handler) = handler(self) {
+            return handler.meta.get(key);
+        }
+
+        None
+    }
+
+    #[track_caller]
+    fn meta_iter(&self) -> MetaIter<'_> {
+        if let Some(handler) = handler(self) {
+            return handler.meta.iter();
+        }
+
+        MetaIter::empty()
+    }
+
+    #[track_caller]
+    fn frame_extensions(&self) -> FrameExtensions<'_> {
+        if let Some(handler) = handler(self) {
+            let len = crate::chain::Chain::of_report(self).count();
+            return handler.frames.iter(len);
+        }
+
+        FrameExtensions::empty()
+    }
+
+    fn chain_with_extensions(&self) -> ChainWithExtensions<'_> {
+        ChainWithExtensions::new(self)
+    }
+
+    #[track_caller]
+    fn exit_code(&self) -> u8 {
+        use crate::builtin::{ExitCode, Severity};
+
+        if let Some(code) = self.extension_ref::<ExitCode>() {
+            return code.0;
+        }
+
+        match self.extension_ref::<Severity>() {
+            Some(Severity::Fatal) => 2,
+            _ => 1,
+        }
+    }
+
+    #[track_caller]
+    fn write_crash_report(&self) -> std::io::Result<std::path::PathBuf> {
+        let handler = handler(self);
+        let dir = handler.and_then(|handler| handler.config.crash_report_dir.as_ref());
+
+        match (handler, dir) {
+            (Some(handler), Some(dir)) => {
+                let rendered = format!("{:?}", self);
+                let (config, extensions) = (&handler.config, &handler.extensions);
+                let written = crate::crash::write_or_report(dir, &rendered, config, extensions);
+                if written.is_err() {
+                    eprintln!("{}", rendered);
+                }
+                written
+            }
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "crash reports aren't enabled",
+            )),
+        }
+    }
+
+    #[track_caller]
+    fn error_code(&self) -> Option<&'static str> {
+        self.extension_ref::<crate::builtin::ErrorCode>()
+            .map(|code| code.0)
+    }
+
+    fn fingerprint(&self) -> u64 {
+        crate::key::fingerprint(self)
+    }
+
+    #[cfg(feature = "time")]
+    #[track_caller]
+    fn created_at(&self) -> Option<time::OffsetDateTime> {
+        self.extension_ref::<crate::builtin::CreatedAt>()
+            .map(|created_at| created_at.0)
+    }
+
+    #[track_caller]
+    fn captured_backtrace(&self) -> Option<&std::backtrace::Backtrace> {
+        self.extension_ref::<crate::builtin::CapturedBacktrace>()
+            .map(|captured| &captured.0)
+    }
+}
+
+/// The error returned by [`Extension::try_extension_ref`] when a report has no value of the
+/// requested type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingExtension {
+    type_name: &'static str,
+    reason: MissingReason,
+}
+
+/// Why a report has no value of a requested type, see [`MissingExtension`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissingReason {
+    /// The report was created by another handler, so it can't carry extensions. Usually means
+    /// [`install`](crate::install) wasn't called before the report was created.
+    ForeignHandler,
+    /// No value of the type was attached to the report.
+    NotAttached,
+}
+
+impl MissingExtension {
+    /// The name of the requested type.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Why the value is missing.
+    pub fn reason(&self) -> MissingReason {
+        self.reason
+    }
+}
+
+impl fmt::Display for MissingExtension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.reason {
+            MissingReason::ForeignHandler => write!(
+                f,
+                "no extension of type `{}`: the report wasn't created by extension-eyre's handler, \
+                 was `extension_eyre::install()` called?",
+                self.type_name
+            ),
+            MissingReason::NotAttached => {
+                write!(f, "no extension of type `{}` attached", self.type_name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MissingExtension {}
+
+/// Wraps `report` with a higher level message, keeping everything attached to it.
+///
+/// Formatting a report into a new one, as in `eyre!("failed to do X: {}", inner)`, creates a
+/// report with nothing attached and flattens the source chain into a string. Wrapping keeps the
+/// chain, the extensions and the metadata of `report`; the new message becomes the report's
+/// `Display` output and `report`'s message its first cause.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::{eyre::eyre, wrap_report, ExtensionExt, Extension};
+///
+/// pub struct Retry;
+///
+/// extension_eyre::install().unwrap();
+///
+/// let inner = eyre!("connection reset").extension(Retry).meta("host", "db");
+/// let err = wrap_report(inner, "failed to fetch users");
+///
+/// assert_eq!(err.to_string(), "failed to fetch users");
+/// assert_eq!(err.root_cause().to_string(), "connection reset");
+/// assert!(err.extension_ref::<Retry>().is_some());
+/// assert_eq!(err.meta_ref("host").unwrap().to_string(), "db");
+/// ```
+pub fn wrap_report<D>(report: Report, message: D) -> Report
+where
+    D: Display + Send + Sync + 'static,
+{
+    report.wrap_err(message)
+}
+
+/// Trait for attaching custom data to errors.
+///
+/// This trait is implemented for `eyre::Report` and `Result<T, E> where E: std::error::Error`.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::{eyre::eyre, ExtensionExt, Extension, eyre::Report};
+/// use std::process::Command;
+/// use tracing::instrument;
+///
+/// pub struct Retry;
+///
+/// trait Output {
+///     fn output2(&mut self) -> Result<String, Report>;
+/// }
+///
+/// impl Output for Command {
+///     #[instrument]
+///     fn output2(&mut self) -> Result<String, Report> {
+///         let output = self.output()?;
+///
+///         let stdout = String::from_utf8_lossy(&output.stdout);
+///
+///         if !output.status.success() {
+///             Err(eyre!("cmd exited with non-zero status code")).extension(Retry)
+///         } else {
+///             Ok(stdout.into())
+///         }
+///     }
+/// }
+/// ```
+pub trait ExtensionExt: Sealed {
+    #[allow(missing_docs)]
+    type Return;
+
+    /// The error [`extension_with_err`](ExtensionExt::extension_with_err) computes extensions
+    /// from: `E` for `Result<T, E>`, and the report itself for `Report`.
+    type Error;
+
+    /// Method for attaching custom data to errors.
+    ///
+    /// The value is attached as [`Source::Explicit`]. An existing value of the same type is
+    /// replaced, unless the installed [`Precedence`](crate::config::Precedence) ranks the source
+    /// it came from higher.
+    ///
+    /// This trait is implemented for `eyre::Report` and `Result<T, E> where E: std::error::Error`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, ExtensionExt, Extension, eyre::Report};
+    /// use std::process::Command;
+    /// use tracing::instrument;
+    ///
+    /// pub struct Retry;
+    ///
+    /// trait Output {
+    ///     fn output2(&mut self) -> Result<String, Report>;
+    /// }
+    ///
+    /// impl Output for Command {
+    ///     #[instrument]
+    ///     fn output2(&mut self) -> Result<String, Report> {
+    ///         let output = self.output()?;
+    ///
+    ///         let stdout = String::from_utf8_lossy(&output.stdout);
+    ///
+    ///         if !output.status.success() {
+    ///             Err(eyre!("cmd exited with non-zero status code")).extension(Retry)
+    ///         } else {
+    ///             Ok(stdout.into())
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    fn extension<T: Send + Sync + 'static>(self, extension: T) -> Self::Return;
+
+    /// Method for lazily attaching custom data to errors.
+    ///
+    /// This trait is implemented for `eyre::Report` and `Result<T, E> where E: std::error::Error`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, ExtensionExt, Extension, eyre::Report};
+    /// use std::process::Command;
+    /// use tracing::instrument;
+    ///
+    /// pub struct Retry(String);
+    ///
+    /// trait Output {
+    ///     fn output2(&mut self) -> Result<String, Report>;
+    /// }
+    ///
+    /// impl Output for Command {
+    ///     #[instrument]
+    ///     fn output2(&mut self) -> Result<String, Report> {
+    ///         let output = self.output()?;
+    ///
+    ///         let stdout = String::from_utf8_lossy(&output.stdout);
+    ///
+    ///         if !output.status.success() {
+    ///             Err(eyre!("cmd exited with non-zero status code"))
+    ///                 .with_extension(|| Retry("Some big string".to_owned()))
+    ///         } else {
+    ///             Ok(stdout.into())
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    fn with_extension<T: Send + Sync + 'static, F: FnOnce() -> T>(self, f: F) -> Self::Return;
+
+    /// Method for attaching custom data computed from the error.
+    ///
+    /// For `Result<T, E>`, `f` is called with the original `E`, before it is converted into a
+    /// `Report`; for `Report`, with the report itself. `f` isn't called on `Ok`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{Extension, ExtensionExt};
+    /// use std::io;
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct IoKind(io::ErrorKind);
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let report = std::fs::read_to_string("/does/not/exist")
+    ///     .extension_with_err(|error| IoKind(error.kind()))
+    ///     .unwrap_err();
+    /// assert_eq!(report.extension_ref(), Some(&IoKind(io::ErrorKind::NotFound)));
+    ///
+    /// let report = report.extension_with_err(|report| report.chain().count());
+    /// assert_eq!(report.extension_ref::<usize>(), Some(&1));
+    ///
+    /// let ok = Ok::<_, io::Error>(5).extension_with_err(|_| -> IoKind { unreachable!() });
+    /// assert_eq!(ok.unwrap(), 5);
+    /// ```
+    fn extension_with_err<T, F>(self, f: F) -> Self::Return
+    where
+        T: Send + Sync + 'static,
+        F: FnOnce(&Self::Error) -> T;
+
+    /// Method for attaching custom data to errors only if no value of the same type is attached
+    /// yet.
+    ///
+    /// Lets outer layers provide a fallback without replacing a more specific value attached
+    /// deeper in the stack.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, eyre::Report, Extension, ExtensionExt};
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// pub enum ErrorCode {
+    ///     NotFound,
+    ///     Internal,
+    /// }
+    ///
+    /// fn load_user(found: bool) -> Result<(), Report> {
+    ///     if found {
+    ///         Err(eyre!("connection reset"))
+    ///     } else {
+    ///         Err(eyre!("no row with id 7")).extension(ErrorCode::NotFound)
+    ///     }
+    /// }
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// // the specific code set by the inner layer survives
+    /// let report = load_user(false).extension_or(ErrorCode::Internal).unwrap_err();
+    /// assert_eq!(report.extension_ref(), Some(&ErrorCode::NotFound));
+    ///
+    /// // the fallback applies when the inner layer set none
+    /// let report = load_user(true).extension_or(ErrorCode::Internal).unwrap_err();
+    /// assert_eq!(report.extension_ref(), Some(&ErrorCode::Internal));
+    /// ```
+    fn extension_or<T: Send + Sync + 'static>(self, extension: T) -> Self::Return;
+
+    /// Method for lazily attaching custom data to errors only if no value of the same type is
+    /// attached yet.
+    ///
+    /// `f` isn't called when a value is already attached or on `Ok`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, Extension, ExtensionExt};
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct Retry(u32);
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let report = eyre!("connection reset")
+    ///     .with_extension_or(|| Retry(3))
+    ///     .with_extension_or(|| -> Retry { unreachable!("already attached") });
+    /// assert_eq!(report.extension_ref(), Some(&Retry(3)));
+    /// ```
+    fn with_extension_or<T: Send + Sync + 'static, F: FnOnce() -> T>(self, f: F) -> Self::Return;
+
+    /// Method for attaching custom data to errors only when `cond` is true.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, Extension, ExtensionExt};
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct Retry;
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let idempotent = true;
+    /// let report = Err::<(), _>(eyre!("connection reset"))
+    ///     .extension_if(idempotent, Retry)
+    ///     .unwrap_err();
+    /// assert_eq!(report.extension_ref(), Some(&Retry));
+    ///
+    /// let report = eyre!("connection reset").extension_if(!idempotent, Retry);
+    /// assert_eq!(report.extension_ref::<Retry>(), None);
+    /// ```
+    fn extension_if<T: Send + Sync + 'static>(self, cond: bool, extension: T) -> Self::Return;
+
+    /// Method for lazily attaching custom data to errors only when `cond` is true.
+    ///
+    /// `f` isn't called when `cond` is false or on `Ok`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, Extension, ExtensionExt};
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct Retry(u32);
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let report = eyre!("connection reset").extension_if_with(true, || Retry(3));
+    /// assert_eq!(report.extension_ref(), Some(&Retry(3)));
+    ///
+    /// let report = Err::<(), _>(eyre!("connection reset"))
+    ///     .extension_if_with(false, || -> Retry { unreachable!() })
+    ///     .unwrap_err();
+    /// assert_eq!(report.extension_ref::<Retry>(), None);
+    ///
+    /// let ok = Ok::<_, std::io::Error>(5).extension_if_with(true, || -> Retry { unreachable!() });
+    /// assert_eq!(ok.unwrap(), 5);
+    /// ```
+    fn extension_if_with<T: Send + Sync + 'static, F: FnOnce() -> T>(
+        self,
+        cond: bool,
+        f: F,
+    ) -> Self::Return;
+
+    /// Wraps the error with `msg` and attaches `extension` to it in one call.
+    ///
+    /// Produces the same report as calling [`wrap_err`](crate::WrapErr::wrap_err) followed by
+    /// [`extension`](ExtensionExt::extension).
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::Report, Extension, ExtensionExt};
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// pub enum ConfigStage {
+    ///     Read,
+    ///     Parse,
+    /// }
+    ///
+    /// fn parse_port(config: &str) -> Result<u16, Report> {
+    ///     config
+    ///         .trim()
+    ///         .parse()
+    ///         .wrap_err_ext("reading config", ConfigStage::Parse)
+    /// }
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let report = parse_port("eighty").unwrap_err();
+    /// assert_eq!(report.to_string(), "reading config");
+    /// assert_eq!(report.chain().count(), 2);
+    /// assert_eq!(report.extension_ref(), Some(&ConfigStage::Parse));
+    ///
+    /// let report = report.wrap_err_ext("starting server", ConfigStage::Read);
+    /// assert_eq!(report.to_string(), "starting server");
+    /// assert_eq!(report.extension_ref(), Some(&ConfigStage::Read));
+    /// ```
+    fn wrap_err_ext<D, T>(self, msg: D, extension: T) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static,
+        T: Send + Sync + 'static;
+
+    /// Wraps the error with the message returned by `msg` and attaches `extension` to it in one
+    /// call.
+    ///
+    /// Produces the same report as calling [`wrap_err_with`](crate::WrapErr::wrap_err_with)
+    /// followed by [`extension`](ExtensionExt::extension). `msg` isn't called on `Ok`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{Extension, ExtensionExt};
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct Path(&'static str);
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let path = "/does/not/exist";
+    /// let report = std::fs::read_to_string(path)
+    ///     .wrap_err_with_ext(|| format!("reading {}", path), Path(path))
+    ///     .unwrap_err();
+    ///
+    /// assert_eq!(report.to_string(), "reading /does/not/exist");
+    /// assert_eq!(report.extension_ref(), Some(&Path(path)));
+    /// ```
+    fn wrap_err_with_ext<D, T, F>(self, msg: F, extension: T) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static,
+        T: Send + Sync + 'static,
+        F: FnOnce() -> D;
+
+    /// Replaces the outermost error with `context`, keeping the previous error as its source and
+    /// everything attached to the report.
+    ///
+    /// The report can then be downcast to `C`, while its extensions, metadata and chain stay as
+    /// they were. This is how a library swaps the error type it exposes at a boundary without
+    /// dropping what lower layers attached. Only `context`'s message is part of the chain, not
+    /// its own source.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, Extension, ExtensionExt};
+    /// use std::fmt;
+    ///
+    /// #[derive(Debug)]
+    /// pub enum StoreError {
+    ///     Unavailable,
+    /// }
+    ///
+    /// #[derive(Debug)]
+    /// pub struct ApiError(u16);
+    ///
+    /// impl fmt::Display for StoreError {
+    ///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    ///         f.write_str("store unavailable")
+    ///     }
+    /// }
+    ///
+    /// impl fmt::Display for ApiError {
+    ///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    ///         write!(f, "request failed with status {}", self.0)
+    ///     }
+    /// }
+    ///
+    /// impl std::error::Error for StoreError {}
+    /// impl std::error::Error for ApiError {}
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct Shard(u32);
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let report = Err::<(), _>(eyre!("connection reset"))
+    ///     .extension(Shard(7))
+    ///     .change_context(StoreError::Unavailable)
+    ///     .change_context(ApiError(503))
+    ///     .unwrap_err();
+    ///
+    /// let chain: Vec<_> = report.chain().map(ToString::to_string).collect();
+    /// assert_eq!(
+    ///     chain,
+    ///     [
+    ///         "request failed with status 503",
+    ///         "store unavailable",
+    ///         "connection reset",
+    ///     ]
+    /// );
+    /// assert_eq!(report.downcast_ref::<ApiError>().unwrap().0, 503);
+    /// assert_eq!(report.extension_ref(), Some(&Shard(7)));
+    /// ```
+    fn change_context<C>(self, context: C) -> Self::Return
+    where
+        C: std::error::Error + Send + Sync + 'static;
+
+    /// Method for attaching a string keyed piece of metadata to errors, without defining a type
+    /// for it.
+    ///
+    /// Metadata is shown in the report's `Debug` output as a `Metadata:` section with the keys
+    /// aligned. It lives apart from typed extensions: the key `"String"` and an extension of
+    /// type `String` don't interact. Attaching a key again replaces its value and keeps its
+    /// position in the section.
+    ///
+    /// This trait is implemented for `eyre::Report` and `Result<T, E> where E: std::error::Error`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, ExtensionExt, Extension, eyre::Report};
+    ///
+    /// fn place_order(order_id: u64) -> Result<(), Report> {
+    ///     Err(eyre!("payment declined"))
+    ///         .meta("order_id", order_id)
+    ///         .meta("user", "jack")
+    ///         .meta("order_id", "12345-retry")
+    ///         .extension(String::from("typed"))
+    /// }
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let err = place_order(12345).unwrap_err();
+    ///
+    /// // last write wins
+    /// assert_eq!(err.meta_ref("order_id").unwrap().to_string(), "12345-retry");
+    /// assert_eq!(err.meta_iter().count(), 2);
+    ///
+    /// // separate from typed extensions
+    /// assert_eq!(err.extension_ref::<String>().unwrap(), "typed");
+    /// assert!(err.meta_ref("alloc::string::String").is_none());
+    ///
+    /// let rendered = format!("{:?}", err);
+    /// assert!(rendered.contains("Metadata:\n   order_id : 12345-retry\n   user     : jack"));
+    /// ```
+    fn meta<K, V>(self, key: K, value: V) -> Self::Return
+    where
+        K: Into<Cow<'static, str>>,
+        V: Display + Send + Sync + 'static;
+
+    /// Method for attaching a [`MessageTemplate`](crate::builtin::MessageTemplate) for end users,
+    /// with placeholders filled from the report's metadata and extensions when it is shown.
+    ///
+    /// This trait is implemented for `eyre::Report` and `Result<T, E> where E: std::error::Error`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{builtin, eyre::eyre, ExtensionExt, eyre::Report};
+    ///
+    /// fn charge(payment_id: &'static str) -> Result<(), Report> {
+    ///     Err(eyre!("card processor returned 402"))
+    ///         .message_template("Payment {payment_id} could not be processed")
+    ///         .meta("payment_id", payment_id)
+    /// }
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let err = charge("pay_123").unwrap_err();
+    /// assert_eq!(
+    ///     builtin::user_message(&err).as_deref(),
+    ///     Some("Payment pay_123 could not be processed")
+    /// );
+    /// ```
+    fn message_template<M: Into<Cow<'static, str>>>(self, template: M) -> Self::Return;
+
+    /// Method for attaching the HTTP status code to respond with, as a
+    /// [`HttpStatus`](crate::builtin::HttpStatus).
+    ///
+    /// This trait is implemented for `eyre::Report` and `Result<T, E> where E: std::error::Error`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::builtin::HttpStatus;
+    /// use extension_eyre::{eyre::eyre, ExtensionExt, Extension, eyre::Report};
+    ///
+    /// fn find_user(id: u64) -> Result<(), Report> {
+    ///     Err(eyre!("user {} not found", id)).status(404)
+    /// }
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let err = find_user(7).unwrap_err();
+    /// assert_eq!(err.extension_ref(), Some(&HttpStatus(404)));
+    /// ```
+    fn status(self, code: u16) -> Self::Return;
+
+    /// Method for overriding where the report was created, e.g. for reports created by
+    /// macro-generated code.
+    ///
+    /// Replaces the [`ReportLocation`](crate::builtin::ReportLocation) of the report, along with
+    /// the location shown in its location section.
+    ///
+    /// This trait is implemented for `eyre::Report` and `Result<T, E> where E: std::error::Error`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "color-eyre")] {
+    /// use extension_eyre::builtin::ReportLocation;
+    /// use extension_eyre::config::{HookBuilder, Theme};
+    /// use extension_eyre::{eyre::eyre, Extension, ExtensionExt};
+    /// use std::panic::Location;
+    ///
+    /// HookBuilder::default().theme(Theme::new()).install().unwrap();
+    ///
+    /// // where the macro was invoked
+    /// let invoked_at = Location::caller();
+    ///
+    /// let created_at = line!() + 1;
+    /// let report = eyre!("connection reset").location(invoked_at);
+    ///
+    /// assert_eq!(report.extension_ref(), Some(&ReportLocation(invoked_at)));
+    ///
+    /// let rendered = format!("{:?}", report);
+    /// assert!(rendered.contains(&format!("{}:{}", file!(), invoked_at.line())));
+    /// assert!(!rendered.contains(&format!("{}:{}", file!(), created_at)));
+    /// # }
+    /// ```
+    fn location(self, location: &'static Location<'static>) -> Self::Return;
+
+    /// Method for attaching custom data to errors under a name, so several values of the same
+    /// type can be attached.
+    ///
+    /// This trait is implemented for `eyre::Report` and `Result<T, E> where E: std::error::Error`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, ExtensionExt, Extension, eyre::Report};
+    /// use extension_eyre::extensions::Key;
+    ///
+    /// const UPSTREAM_RESPONSE_BODY: Key<String> = Key::new("upstream_response_body");
+    /// const REQUEST_PATH: Key<String> = Key::new("request_path");
+    ///
+    /// fn proxy(path: &str) -> Result<(), Report> {
+    ///     Err(eyre!("upstream returned 502"))
+    ///         .extension_keyed(UPSTREAM_RESPONSE_BODY, "Bad Gateway".to_owned())
+    ///         .extension_keyed(REQUEST_PATH, path.to_owned())
+    /// }
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let err = proxy("/api/users").unwrap_err();
+    /// let extensions = err.extensions_ref().unwrap();
+    ///
+    /// assert_eq!(extensions.get_keyed(UPSTREAM_RESPONSE_BODY).unwrap(), "Bad Gateway");
+    /// assert_eq!(extensions.get_keyed(REQUEST_PATH).unwrap(), "/api/users");
+    /// assert!(err.extension_ref::<String>().is_none());
+    /// ```
+    fn extension_keyed<T: Send + Sync + 'static>(self, key: Key<T>, extension: T) -> Self::Return;
+
+    /// Method for attaching another value of a type without overwriting the ones already
+    /// attached.
+    ///
+    /// [`Extension::extension_ref`] returns the most recently pushed value; all of them can be
+    /// read back in order through [`Extensions::get_all`].
+    ///
+    /// This trait is implemented for `eyre::Report` and `Result<T, E> where E: std::error::Error`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, ExtensionExt, Extension, eyre::Report};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// pub struct AttemptInfo {
+    ///     attempt: u32,
+    ///     elapsed: Duration,
+    /// }
+    ///
+    /// fn fetch() -> Result<(), Report> {
+    ///     Err(eyre!("connection reset"))
+    /// }
+    ///
+    /// fn fetch_with_retries() -> Result<(), Report> {
+    ///     let start = Instant::now();
+    ///     let mut result = Ok(());
+    ///
+    ///     for attempt in 1..=3 {
+    ///         result = result.and(fetch()).push_extension(AttemptInfo {
+    ///             attempt,
+    ///             elapsed: start.elapsed(),
+    ///         });
+    ///     }
+    ///
+    ///     result
+    /// }
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let err = fetch_with_retries().unwrap_err();
+    /// let attempts = err
+    ///     .extensions_ref()
+    ///     .unwrap()
+    ///     .get_all::<AttemptInfo>()
+    ///     .map(|info| info.attempt)
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(attempts, [1, 2, 3]);
+    /// assert_eq!(err.extension_ref::<AttemptInfo>().unwrap().attempt, 3);
+    /// ```
+    fn push_extension<T: Send + Sync + 'static>(self, extension: T) -> Self::Return;
+
+    /// Method for attaching custom data to the most recent frame of the error's chain: the
+    /// context of the last `wrap_err`, or the error itself if it wasn't wrapped.
+    ///
+    /// The value stays with its frame as the report is wrapped further, and is shown indented
+    /// beneath that frame's cause in the report. [`Extension::frame_extensions`] lists the
+    /// values by frame. [`Extension::extension_ref`] finds them too, after the ones attached to
+    /// the whole report, outermost frame first.
+    ///
+    /// This trait is implemented for `eyre::Report` and `Result<T, E> where E: std::error::Error`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, Extension, ExtensionExt, WrapErr};
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct Retry(bool);
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let report = Err::<(), _>(eyre!("request timed out"))
+    ///     .frame_extension(Retry(true))
+    ///     .wrap_err("could not fetch the price list")
+    ///     .frame_extension(Retry(false))
+    ///     .wrap_err("could not render the dashboard")
+    ///     .unwrap_err();
+    ///
+    /// let frames = report
+    ///     .frame_extensions()
+    ///     .map(|(index, extensions)| (index, extensions.get::<Retry>()))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(frames, [(1, Some(&Retry(false))), (2, Some(&Retry(true)))]);
+    ///
+    /// // the outermost frame holding a value wins, and values attached to the report win over it
+    /// assert_eq!(report.extension_ref(), Some(&Retry(false)));
+    /// let report = report.extension(Retry(true));
+    /// assert_eq!(report.extension_ref(), Some(&Retry(true)));
+    ///
+    /// let rendered = format!("{:?}", report);
+    /// let price_list = rendered.find("could not fetch the price list").unwrap();
+    /// let timed_out = rendered.find("request timed out").unwrap();
+    /// let retry = rendered.find("\n      Retry\n").unwrap();
+    /// assert!(price_list < retry && retry < timed_out);
+    /// assert!(rendered[timed_out..].contains("\n      Retry\n"));
+    /// ```
+    fn frame_extension<T: Send + Sync + 'static>(self, extension: T) -> Self::Return;
+
+    /// Method for updating custom data already attached to errors.
+    ///
+    /// Does nothing if no value of type `T` is attached; use [`Extensions::modify`] to find out
+    /// whether there was one.
+    ///
+    /// This trait is implemented for `eyre::Report` and `Result<T, E> where E: std::error::Error`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, ExtensionExt, Extension, eyre::Report};
+    ///
+    /// pub struct Attempts(usize);
+    ///
+    /// fn lower(attach: bool) -> Result<(), Report> {
+    ///     let err = Err(eyre!("connection reset"));
+    ///     if attach {
+    ///         err.extension(Attempts(1))
+    ///     } else {
+    ///         err
+    ///     }
+    /// }
+    ///
+    /// fn middle(attach: bool) -> Result<(), Report> {
+    ///     lower(attach).update_extension::<Attempts, _>(|attempts| attempts.0 += 1)
+    /// }
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// assert_eq!(middle(true).unwrap_err().extension_ref::<Attempts>().unwrap().0, 2);
+    /// assert!(middle(false).unwrap_err().extension_ref::<Attempts>().is_none());
+    /// ```
+    fn update_extension<T, F>(self, f: F) -> Self::Return
+    where
+        T: Send + Sync + 'static,
+        F: FnOnce(&mut T);
+
+    /// Method for attaching every value of an [`Extensions`] map to errors at once.
+    ///
+    /// Values replace attached values of the same type, like [`Extensions::extend`]. The map is
+    /// conveniently built with the [`extensions!`](crate::extensions!) macro.
+    ///
+    /// This trait is implemented for `eyre::Report` and `Result<T, E> where E: std::error::Error`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, extensions, ExtensionExt, Extension, eyre::Report};
+    ///
+    /// pub struct Retry;
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct Counter(usize);
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct Tag(&'static str);
+    ///
+    /// fn query() -> Result<(), Report> {
+    ///     Err(eyre!("connection reset")).extensions(extensions![Retry, Counter(0), Tag("db")])
+    /// }
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let err = query().unwrap_err();
+    /// assert!(err.extension_ref::<Retry>().is_some());
+    /// assert_eq!(err.extension_ref(), Some(&Counter(0)));
+    /// assert_eq!(err.extension_ref(), Some(&Tag("db")));
+    /// ```
+    fn extensions(self, extensions: Extensions) -> Self::Return;
+
+    /// Method for removing attached data from errors if exists.
+    ///
+    /// This trait is implemented for `eyre::Report` and `Result<T, E> where E: std::error::Error`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, ExtensionExt, Extension, eyre::Report};
+    /// use std::process::Command;
+    /// use tracing::instrument;
+    ///
+    /// pub struct Retry;
+    ///
+    /// #[instrument]
+    /// fn read_file(path: &str) -> Result<String, Report> {
+    ///     Command::new("cat")
+    ///         .arg(path)
+    ///         .output2()
+    ///         .remove_extension::<Retry>()
+    /// }
+    ///
+    /// trait Output {
+    ///     fn output2(&mut self) -> Result<String, Report>;
+    /// }
+    ///
+    /// impl Output for Command {
+    ///     #[instrument]
+    ///     fn output2(&mut self) -> Result<String, Report> {
+    ///         let output = self.output()?;
+    ///
+    ///         let stdout = String::from_utf8_lossy(&output.stdout);
+    ///
+    ///         if !output.status.success() {
+    ///             Err(eyre!("cmd exited with non-zero status code")).extension(Retry)
+    ///         } else {
+    ///             Ok(stdout.into())
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    fn remove_extension<T: Send + Sync + 'static>(self) -> Self::Return;
+
+    /// Method for marking errors as sensitive so observers registered on the installed hook
+    /// skip them.
+    ///
+    /// This attaches the [`ObserversBypassed`](crate::ObserversBypassed) marker, so the
+    /// [reporter](crate::reporter) and the built-in [sinks](crate::sink) drop the report. Hooks
+    /// that run when a report is created have already seen it by then; use
+    /// [`quietly`](crate::quietly) to keep them from seeing it at all.
+    ///
+    /// This trait is implemented for `eyre::Report` and `Result<T, E> where E: std::error::Error`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, ExtensionExt, Extension, eyre::Report, ObserversBypassed};
+    ///
+    /// fn verify_signature() -> Result<(), Report> {
+    ///     Err(eyre!("signature mismatch")).bypass_observers()
+    /// }
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let err = verify_signature().unwrap_err();
+    /// assert!(err.extension_ref::<ObserversBypassed>().is_some());
+    /// ```
+    fn bypass_observers(self) -> Self::Return;
+
+    /// Method for removing all attached data from errors.
+    ///
+    /// This removes the report's extensions, its [metadata](ExtensionExt::meta) and the
+    /// extensions attached to its [frames](ExtensionExt::frame_extension). It is a no-op if the
+    /// error report wasn't created by extension-eyre's handler.
+    ///
+    /// This trait is implemented for `eyre::Report` and `Result<T, E> where E: std::error::Error`.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, ExtensionExt, Extension, eyre::Report};
+    ///
+    /// pub struct Retry;
+    /// pub struct Counter(usize);
+    /// pub struct UserId(u64);
+    ///
+    /// fn internal() -> Result<(), Report> {
+    ///     Err(eyre!("cmd exited with non-zero status code"))
+    ///         .extension(Retry)
+    ///         .extension(Counter(0))
+    ///         .extension(UserId(7))
+    ///         .meta("user", "jack")
+    ///         .frame_extension(Counter(1))
+    /// }
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let err = internal().clear_extensions().unwrap_err();
+    /// assert!(err.extensions_ref().unwrap().is_empty());
+    /// assert!(err.meta_iter().next().is_none());
+    /// assert!(err.frame_extensions().next().is_none());
+    ///
+    /// let rendered = format!("{:?}", err);
+    /// assert!(!rendered.contains("jack"));
+    /// assert!(!rendered.contains("Frame extensions"));
+    /// ```
+    fn clear_extensions(self) -> Self::Return;
+}
+
+impl ExtensionExt for Report {
+    type Return = Report;
+    type Error = Report;
+
+    #[track_caller]
+    fn extension<T: Send + Sync + 'static>(self, extension: T) -> Self::Return {
+        self.with_extension(|| extension)
+    }
+
+    #[track_caller]
+    fn with_extension<T: Send + Sync + 'static, F: FnOnce() -> T>(mut self, f: F) -> Self::Return {
+        if let Some(handler) = handler_mut(&mut self) {
+            handler.attach_with(Source::Explicit, f);
+        }
+
+        self
+    }
+
+    #[track_caller]
+    fn extension_with_err<T, F>(self, f: F) -> Self::Return
+    where
+        T: Send + Sync + 'static,
+        F: FnOnce(&Self::Error) -> T,
+    {
+        let extension = f(&self);
+        self.extension(extension)
+    }
+
+    #[track_caller]
+    fn extension_or<T: Send + Sync + 'static>(self, extension: T) -> Self::Return {
+        self.with_extension_or(|| extension)
+    }
+
+    #[track_caller]
+    fn with_extension_or<T: Send + Sync + 'static, F: FnOnce() -> T>(
+        mut self,
+        f: F,
+    ) -> Self::Return {
+        if let Some(handler) = handler_mut(&mut self) {
+            handler.extensions.insert_if_absent_with(f);
+        }
+
+        self
+    }
+
+    #[track_caller]
+    fn extension_if<T: Send + Sync + 'static>(self, cond: bool, extension: T) -> Self::Return {
+        self.extension_if_with(cond, || extension)
+    }
+
+    #[track_caller]
+    fn extension_if_with<T: Send + Sync + 'static, F: FnOnce() -> T>(
+        self,
+        cond: bool,
+        f: F,
+    ) -> Self::Return {
+        if cond {
+            self.with_extension(f)
+        } else {
+            self
+        }
+    }
+
+    #[track_caller]
+    fn wrap_err_ext<D, T>(self, msg: D, extension: T) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static,
+        T: Send + Sync + 'static,
+    {
+        self.wrap_err(msg).extension(extension)
+    }
+
+    #[track_caller]
+    fn wrap_err_with_ext<D, T, F>(self, msg: F, extension: T) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static,
+        T: Send + Sync + 'static,
+        F: FnOnce() -> D,
+    {
+        self.wrap_err(msg()).extension(extension)
+    }
+
+    #[track_caller]
+    fn change_context<C>(self, context: C) -> Self::Return
+    where
+        C: std::error::Error + Send + Sync + 'static,
+    {
+        // the context wraps the report's error in place, so the handler and its extensions stay
+        self.wrap_err(context)
+    }
+
+    #[track_caller]
+    fn meta<K, V>(mut self, key: K, value: V) -> Self::Return
+    where
+        K: Into<Cow<'static, str>>,
+        V: Display + Send + Sync + 'static,
+    {
+        if let Some(handler) = handler_mut(&mut self) {
+            handler.meta.insert(key.into(), Box::new(value));
+        }
+
+        self
+    }
+
+    #[track_caller]
+    fn message_template<M: Into<Cow<'static, str>>>(self, template: M) -> Self::Return {
+        self.extension(crate::builtin::MessageTemplate(template.into()))
+    }
+
+    #[track_caller]
+    fn status(self, code: u16) -> Self::Return {
+        self.extension(crate::builtin::HttpStatus(code))
+    }
+
+    fn location(mut self, location: &'static Location<'static>) -> Self::Return {
+        if let Some(handler) = handler_mut(&mut self) {
+            handler.set_location(location);
+        }
+
+        self
+    }
+
+    #[track_caller]
+    fn extension_keyed<T: Send + Sync + 'static>(
+        mut self,
+        key: Key<T>,
+        extension: T,
+    ) -> Self::Return {
+        if let Some(handler) = handler_mut(&mut self) {
+            handler.extensions.insert_keyed::<T>(key, extension);
+        }
+
+        self
+    }
+
+    #[track_caller]
+    fn push_extension<T: Send + Sync + 'static>(mut self, extension: T) -> Self::Return {
+        if let Some(handler) = handler_mut(&mut self) {
+            handler.extensions.push::<T>(extension);
+        }
+
+        self
+    }
+
+    #[track_caller]
+    fn frame_extension<T: Send + Sync + 'static>(mut self, extension: T) -> Self::Return {
+        // the most recent frame is the outermost error, counted from the innermost one
+        let depth = crate::chain::Chain::of_report(&self).count() - 1;
+        if let Some(handler) = handler_mut(&mut self) {
+            handler.frames.insert(depth, extension);
+        }
+
+        self
+    }
+
+    #[track_caller]
+    fn update_extension<T, F>(mut self, f: F) -> Self::Return
+    where
+        T: Send + Sync + 'static,
+        F: FnOnce(&mut T),
+    {
+        if let Some(handler) = handler_mut(&mut self) {
+            handler.extensions.modify(f);
+        }
 
         self
     }
 
+    #[track_caller]
+    fn extensions(mut self, extensions: Extensions) -> Self::Return {
+        if let Some(handler) = handler_mut(&mut self) {
+            handler.extensions.extend(extensions);
+        }
+
+        self
+    }
+
+    #[track_caller]
     fn remove_extension<T: Send + Sync + 'static>(mut self) -> Self::Return {
-        if let Some(handler) = self.handler_mut().downcast_mut::<crate::Handler>() {
+        if let Some(handler) = handler_mut(&mut self) {
             handler.extensions.remove::<T>();
         }
 
         self
     }
+
+    #[track_caller]
+    fn bypass_observers(self) -> Self::Return {
+        self.extension(crate::ObserversBypassed)
+    }
+
+    #[track_caller]
+    fn clear_extensions(mut self) -> Self::Return {
+        if let Some(handler) = handler_mut(&mut self) {
+            handler.extensions.clear();
+            handler.meta = Default::default();
+            handler.frames = Default::default();
+        }
+
+        self
+    }
 }
 
 impl<T, E> ExtensionExt for Result<T, E>
@@ -490,19 +2496,218 @@ where
     E: Into<Report>,
 {
     type Return = Result<T, Report>;
+    type Error = E;
 
+    #[track_caller]
     fn extension<Ext: Send + Sync + 'static>(self, extension: Ext) -> Self::Return {
-        self.map_err(|error| error.into())
-            .map_err(|report| report.extension::<Ext>(extension))
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => Err(Into::<Report>::into(error).extension::<Ext>(extension)),
+        }
     }
 
+    #[track_caller]
     fn with_extension<Ext: Send + Sync + 'static, F: FnOnce() -> Ext>(self, f: F) -> Self::Return {
-        self.map_err(|error| error.into())
-            .map_err(|report| report.with_extension(f))
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => Err(Into::<Report>::into(error).with_extension(f)),
+        }
+    }
+
+    #[track_caller]
+    fn extension_with_err<Ext, F>(self, f: F) -> Self::Return
+    where
+        Ext: Send + Sync + 'static,
+        F: FnOnce(&Self::Error) -> Ext,
+    {
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => {
+                let extension = f(&error);
+                Err(Into::<Report>::into(error).extension(extension))
+            }
+        }
+    }
+
+    #[track_caller]
+    fn extension_or<Ext: Send + Sync + 'static>(self, extension: Ext) -> Self::Return {
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => Err(Into::<Report>::into(error).extension_or(extension)),
+        }
+    }
+
+    #[track_caller]
+    fn with_extension_or<Ext: Send + Sync + 'static, F: FnOnce() -> Ext>(
+        self,
+        f: F,
+    ) -> Self::Return {
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => Err(Into::<Report>::into(error).with_extension_or(f)),
+        }
+    }
+
+    #[track_caller]
+    fn extension_if<Ext: Send + Sync + 'static>(self, cond: bool, extension: Ext) -> Self::Return {
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => Err(Into::<Report>::into(error).extension_if(cond, extension)),
+        }
+    }
+
+    #[track_caller]
+    fn extension_if_with<Ext: Send + Sync + 'static, F: FnOnce() -> Ext>(
+        self,
+        cond: bool,
+        f: F,
+    ) -> Self::Return {
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => Err(Into::<Report>::into(error).extension_if_with(cond, f)),
+        }
+    }
+
+    #[track_caller]
+    fn wrap_err_ext<D, Ext>(self, msg: D, extension: Ext) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static,
+        Ext: Send + Sync + 'static,
+    {
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => Err(Into::<Report>::into(error).wrap_err_ext(msg, extension)),
+        }
+    }
+
+    #[track_caller]
+    fn wrap_err_with_ext<D, Ext, F>(self, msg: F, extension: Ext) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static,
+        Ext: Send + Sync + 'static,
+        F: FnOnce() -> D,
+    {
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => Err(Into::<Report>::into(error).wrap_err_with_ext(msg, extension)),
+        }
+    }
+
+    #[track_caller]
+    fn change_context<C>(self, context: C) -> Self::Return
+    where
+        C: std::error::Error + Send + Sync + 'static,
+    {
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => Err(Into::<Report>::into(error).change_context(context)),
+        }
+    }
+
+    #[track_caller]
+    fn meta<K, V>(self, key: K, value: V) -> Self::Return
+    where
+        K: Into<Cow<'static, str>>,
+        V: Display + Send + Sync + 'static,
+    {
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => Err(Into::<Report>::into(error).meta(key, value)),
+        }
+    }
+
+    #[track_caller]
+    fn message_template<M: Into<Cow<'static, str>>>(self, template: M) -> Self::Return {
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => Err(Into::<Report>::into(error).message_template(template)),
+        }
+    }
+
+    #[track_caller]
+    fn status(self, code: u16) -> Self::Return {
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => Err(Into::<Report>::into(error).status(code)),
+        }
+    }
+
+    fn location(self, location: &'static Location<'static>) -> Self::Return {
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => Err(Into::<Report>::into(error).location(location)),
+        }
+    }
+
+    #[track_caller]
+    fn extension_keyed<Ext: Send + Sync + 'static>(
+        self,
+        key: Key<Ext>,
+        extension: Ext,
+    ) -> Self::Return {
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => Err(Into::<Report>::into(error).extension_keyed::<Ext>(key, extension)),
+        }
+    }
+
+    #[track_caller]
+    fn push_extension<Ext: Send + Sync + 'static>(self, extension: Ext) -> Self::Return {
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => Err(Into::<Report>::into(error).push_extension::<Ext>(extension)),
+        }
     }
 
+    #[track_caller]
+    fn frame_extension<Ext: Send + Sync + 'static>(self, extension: Ext) -> Self::Return {
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => Err(Into::<Report>::into(error).frame_extension::<Ext>(extension)),
+        }
+    }
+
+    #[track_caller]
+    fn update_extension<Ext, F>(self, f: F) -> Self::Return
+    where
+        Ext: Send + Sync + 'static,
+        F: FnOnce(&mut Ext),
+    {
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => Err(Into::<Report>::into(error).update_extension(f)),
+        }
+    }
+
+    #[track_caller]
+    fn extensions(self, extensions: Extensions) -> Self::Return {
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => Err(Into::<Report>::into(error).extensions(extensions)),
+        }
+    }
+
+    #[track_caller]
     fn remove_extension<Ext: Send + Sync + 'static>(self) -> Self::Return {
-        self.map_err(|error| error.into())
-            .map_err(|report| report.remove_extension::<Ext>())
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => Err(Into::<Report>::into(error).remove_extension::<Ext>()),
+        }
+    }
+
+    #[track_caller]
+    fn bypass_observers(self) -> Self::Return {
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => Err(Into::<Report>::into(error).bypass_observers()),
+        }
+    }
+
+    #[track_caller]
+    fn clear_extensions(self) -> Self::Return {
+        match self {
+            Ok(value) => Ok(value),
+            Err(error) => Err(Into::<Report>::into(error).clear_extensions()),
+        }
     }
 }
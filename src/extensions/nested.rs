@@ -0,0 +1,92 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::eyre::Report;
+
+/// A [`Report`] usable as the source of another error, keeping its extensions reachable.
+///
+/// `Report` doesn't implement [`Error`], so error types that hold one as their source hand out
+/// the report's inner error, without the handler carrying the extensions. Holding the report in
+/// a `NestedReport` instead lets
+/// [`Extension::extension_ref_deep`](crate::Extension::extension_ref_deep) find them.
+///
+/// A `NestedReport` displays as the report's message and continues the source chain with the
+/// report's sources.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::extensions::NestedReport;
+/// use extension_eyre::eyre::{eyre, Report};
+/// use std::error::Error;
+///
+/// #[derive(Debug)]
+/// pub struct QueryError {
+///     source: NestedReport,
+/// }
+///
+/// impl std::fmt::Display for QueryError {
+///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+///         f.write_str("query failed")
+///     }
+/// }
+///
+/// impl Error for QueryError {
+///     fn source(&self) -> Option<&(dyn Error + 'static)> {
+///         Some(&self.source)
+///     }
+/// }
+///
+/// extension_eyre::install().unwrap();
+///
+/// let inner = eyre!("connection reset").wrap_err("pool exhausted");
+/// let error = QueryError { source: inner.into() };
+///
+/// let chain = Report::new(error)
+///     .chain()
+///     .map(|e| e.to_string())
+///     .collect::<Vec<_>>();
+/// assert_eq!(chain, ["query failed", "pool exhausted", "connection reset"]);
+/// ```
+pub struct NestedReport(Report);
+
+impl NestedReport {
+    /// Returns the wrapped report.
+    pub fn into_inner(self) -> Report {
+        self.0
+    }
+
+    /// Returns a reference to the wrapped report.
+    pub fn report(&self) -> &Report {
+        &self.0
+    }
+
+    /// Returns a mutable reference to the wrapped report.
+    pub fn report_mut(&mut self) -> &mut Report {
+        &mut self.0
+    }
+}
+
+impl From<Report> for NestedReport {
+    fn from(report: Report) -> Self {
+        NestedReport(report)
+    }
+}
+
+impl fmt::Debug for NestedReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Display for NestedReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl Error for NestedReport {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.0.source()
+    }
+}
@@ -0,0 +1,66 @@
+use std::any::{type_name, Any, TypeId};
+use std::fmt;
+
+type Visit = dyn Fn(&(dyn Any + Send + Sync)) + Send + Sync;
+
+/// Callbacks to run for the extensions of registered types, see [`Extensions::visit`].
+///
+/// Any number of callbacks can be registered, for the same or different types. They run in the
+/// order they were registered.
+///
+/// [`Extensions::visit`]: super::Extensions::visit
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::extensions::ExtensionVisitorRegistry;
+///
+/// pub struct Retry(bool);
+/// pub struct StatusCode(u16);
+///
+/// let registry = ExtensionVisitorRegistry::new()
+///     .register(|retry: &Retry| println!("retry={}", retry.0))
+///     .register(|status: &StatusCode| println!("status={}", status.0));
+/// ```
+#[derive(Default)]
+pub struct ExtensionVisitorRegistry {
+    callbacks: Vec<(TypeId, &'static str, Box<Visit>)>,
+}
+
+impl ExtensionVisitorRegistry {
+    /// Creates a registry without callbacks.
+    pub fn new() -> Self {
+        ExtensionVisitorRegistry::default()
+    }
+
+    /// Adds a callback run for every extension of type `T`.
+    pub fn register<T, F>(mut self, callback: F) -> Self
+    where
+        T: Send + Sync + 'static,
+        F: Fn(&T) + Send + Sync + 'static,
+    {
+        let callback = move |value: &(dyn Any + Send + Sync)| {
+            if let Some(value) = (value as &(dyn Any + 'static)).downcast_ref::<T>() {
+                callback(value);
+            }
+        };
+
+        self.callbacks
+            .push((TypeId::of::<T>(), type_name::<T>(), Box::new(callback)));
+        self
+    }
+
+    pub(crate) fn callbacks(&self) -> impl Iterator<Item = (&TypeId, &Visit)> {
+        self.callbacks
+            .iter()
+            .map(|(type_id, _, callback)| (type_id, &**callback))
+    }
+}
+
+impl fmt::Debug for ExtensionVisitorRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set()
+            .entries(self.callbacks.iter().map(|(_, name, _)| name))
+            .finish()
+    }
+}
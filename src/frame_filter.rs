@@ -0,0 +1,32 @@
+//! Frame filters depending on the report being rendered, see
+//! [`HookBuilder::add_contextual_frame_filter`]
+//!
+//! color-eyre runs its frame filters while it renders a backtrace, with the frames only. The
+//! [`Handler`](crate::Handler) makes the report's extensions reachable from them for as long as
+//! it has color-eyre render the report.
+//!
+//! [`HookBuilder::add_contextual_frame_filter`]:
+//! crate::config::HookBuilder::add_contextual_frame_filter
+use color_eyre::config::{FilterCallback, Frame};
+use scoped_tls::scoped_thread_local;
+
+use crate::config::ContextualFilterCallback;
+use crate::extensions::Extensions;
+
+scoped_thread_local!(static RENDERING: Extensions);
+
+/// Calls `f` with `extensions` as the ones of the report being rendered on this thread.
+pub(crate) fn rendering<R>(extensions: &Extensions, f: impl FnOnce() -> R) -> R {
+    RENDERING.set(extensions, f)
+}
+
+/// A frame filter calling `filter` with the extensions of the report being rendered, if any.
+pub(crate) fn contextual(filter: Box<ContextualFilterCallback>) -> Box<FilterCallback> {
+    Box::new(move |frames: &mut Vec<&Frame>| {
+        if RENDERING.is_set() {
+            RENDERING.with(|extensions| filter(frames, Some(extensions)))
+        } else {
+            filter(frames, None)
+        }
+    })
+}
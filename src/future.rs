@@ -0,0 +1,226 @@
+//! Attaching extensions to the errors of futures
+//!
+//! [`ExtensionFutureExt`] mirrors [`ExtensionExt`](crate::ExtensionExt) for futures resolving to
+//! a `Result`: the extension is attached to the error once the future resolves to `Err`.
+//!
+//! ### Example
+//!
+//! ```rust
+//! use extension_eyre::{eyre::eyre, eyre::Report, future::ExtensionFutureExt, Extension};
+//!
+//! #[derive(Debug, PartialEq)]
+//! pub struct Retry;
+//!
+//! async fn fetch(ok: bool) -> Result<u32, Report> {
+//!     if ok {
+//!         Ok(7)
+//!     } else {
+//!         Err(eyre!("connection reset"))
+//!     }
+//! }
+//!
+//! fn assert_send<T: Send>(value: T) -> T {
+//!     value
+//! }
+//!
+//! extension_eyre::install().unwrap();
+//!
+//! let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+//!
+//! let report = runtime
+//!     .block_on(assert_send(fetch(false).extension(Retry)))
+//!     .unwrap_err();
+//! assert_eq!(report.extension_ref(), Some(&Retry));
+//!
+//! let value = runtime.block_on(fetch(true).with_extension(|| -> Retry { unreachable!() }));
+//! assert_eq!(value.unwrap(), 7);
+//! ```
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use pin_project_lite::pin_project;
+
+use crate::extensions::ExtensionExt;
+use crate::eyre::Report;
+
+/// Extension methods for futures resolving to `Result<T, E> where E: Into<Report>`.
+///
+/// The returned futures are `Send` when the inner future and the attached values are.
+pub trait ExtensionFutureExt<T, E>: Future<Output = Result<T, E>> + Sized
+where
+    E: Into<Report>,
+{
+    /// Attaches `extension` to the error once the future resolves to `Err`.
+    ///
+    /// See [`ExtensionExt::extension`].
+    fn extension<Ext: Send + Sync + 'static>(self, extension: Ext) -> WithExtension<Self, Ext>;
+
+    /// Attaches the value returned by `f` to the error once the future resolves to `Err`.
+    ///
+    /// `f` is only called on `Err`. See [`ExtensionExt::with_extension`].
+    fn with_extension<Ext, F>(self, f: F) -> WithExtensionFn<Self, F>
+    where
+        Ext: Send + Sync + 'static,
+        F: FnOnce() -> Ext;
+
+    /// Attaches the value `f` computes from the original error once the future resolves to
+    /// `Err`.
+    ///
+    /// `f` is only called on `Err`. See [`ExtensionExt::extension_with_err`].
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{future::ExtensionFutureExt, Extension};
+    /// use std::io;
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct IoKind(io::ErrorKind);
+    ///
+    /// async fn read(ok: bool) -> io::Result<u32> {
+    ///     if ok {
+    ///         Ok(7)
+    ///     } else {
+    ///         Err(io::Error::new(io::ErrorKind::TimedOut, "read timed out"))
+    ///     }
+    /// }
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    ///
+    /// let report = runtime
+    ///     .block_on(read(false).extension_with_err(|error| IoKind(error.kind())))
+    ///     .unwrap_err();
+    /// assert_eq!(report.extension_ref(), Some(&IoKind(io::ErrorKind::TimedOut)));
+    ///
+    /// let value = runtime.block_on(read(true).extension_with_err(|_| -> IoKind { unreachable!() }));
+    /// assert_eq!(value.unwrap(), 7);
+    /// ```
+    fn extension_with_err<Ext, F>(self, f: F) -> ExtensionWithErr<Self, F>
+    where
+        Ext: Send + Sync + 'static,
+        F: FnOnce(&E) -> Ext;
+}
+
+impl<Fut, T, E> ExtensionFutureExt<T, E> for Fut
+where
+    Fut: Future<Output = Result<T, E>>,
+    E: Into<Report>,
+{
+    fn extension<Ext: Send + Sync + 'static>(self, extension: Ext) -> WithExtension<Self, Ext> {
+        WithExtension {
+            future: self,
+            extension: Some(extension),
+        }
+    }
+
+    fn with_extension<Ext, F>(self, f: F) -> WithExtensionFn<Self, F>
+    where
+        Ext: Send + Sync + 'static,
+        F: FnOnce() -> Ext,
+    {
+        WithExtensionFn {
+            future: self,
+            f: Some(f),
+        }
+    }
+
+    fn extension_with_err<Ext, F>(self, f: F) -> ExtensionWithErr<Self, F>
+    where
+        Ext: Send + Sync + 'static,
+        F: FnOnce(&E) -> Ext,
+    {
+        ExtensionWithErr {
+            future: self,
+            f: Some(f),
+        }
+    }
+}
+
+pin_project! {
+    /// Future returned by [`ExtensionFutureExt::extension`].
+    #[must_use = "futures do nothing unless polled"]
+    #[derive(Debug)]
+    pub struct WithExtension<Fut, Ext> {
+        #[pin]
+        future: Fut,
+        extension: Option<Ext>,
+    }
+}
+
+impl<Fut, T, E, Ext> Future for WithExtension<Fut, Ext>
+where
+    Fut: Future<Output = Result<T, E>>,
+    E: Into<Report>,
+    Ext: Send + Sync + 'static,
+{
+    type Output = Result<T, Report>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let result = std::task::ready!(this.future.poll(cx));
+        let extension = this.extension.take().expect("polled after completion");
+
+        Poll::Ready(result.extension(extension))
+    }
+}
+
+pin_project! {
+    /// Future returned by [`ExtensionFutureExt::with_extension`].
+    #[must_use = "futures do nothing unless polled"]
+    #[derive(Debug)]
+    pub struct WithExtensionFn<Fut, F> {
+        #[pin]
+        future: Fut,
+        f: Option<F>,
+    }
+}
+
+impl<Fut, T, E, Ext, F> Future for WithExtensionFn<Fut, F>
+where
+    Fut: Future<Output = Result<T, E>>,
+    E: Into<Report>,
+    Ext: Send + Sync + 'static,
+    F: FnOnce() -> Ext,
+{
+    type Output = Result<T, Report>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let result = std::task::ready!(this.future.poll(cx));
+        let f = this.f.take().expect("polled after completion");
+
+        Poll::Ready(result.with_extension(f))
+    }
+}
+
+pin_project! {
+    /// Future returned by [`ExtensionFutureExt::extension_with_err`].
+    #[must_use = "futures do nothing unless polled"]
+    #[derive(Debug)]
+    pub struct ExtensionWithErr<Fut, F> {
+        #[pin]
+        future: Fut,
+        f: Option<F>,
+    }
+}
+
+impl<Fut, T, E, Ext, F> Future for ExtensionWithErr<Fut, F>
+where
+    Fut: Future<Output = Result<T, E>>,
+    E: Into<Report>,
+    Ext: Send + Sync + 'static,
+    F: FnOnce(&E) -> Ext,
+{
+    type Output = Result<T, Report>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let result = std::task::ready!(this.future.poll(cx));
+        let f = this.f.take().expect("polled after completion");
+
+        Poll::Ready(result.extension_with_err(f))
+    }
+}
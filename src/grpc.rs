@@ -0,0 +1,67 @@
+//! Responding to gRPC requests with error reports
+//!
+//! Orphan rules don't allow `impl From<Report> for tonic::Status`, so reports are converted with
+//! [`report_to_status`] instead.
+use tonic::{Code, Status};
+
+use crate::builtin::GrpcCode;
+use crate::extensions::Extension;
+use crate::eyre::Report;
+
+/// Converts `report` into the [`Status`] a tonic service responds with.
+///
+/// # Details
+///
+/// The status code is the attached [`GrpcCode`], or `Code::Internal` when there is none. The
+/// message is the report's top-level message, without its sources. The extensions inserted with
+/// [`Extensions::insert_serializable`](crate::extensions::Extensions::insert_serializable) are
+/// sent as the status details, serialized as a JSON object; the details are empty when there are
+/// none.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::builtin::GrpcCode;
+/// use extension_eyre::{eyre::eyre, grpc, Extension, ExtensionExt, WrapErr};
+/// use tonic::Code;
+///
+/// extension_eyre::install().unwrap();
+///
+/// let mut report = Err::<(), _>(eyre!("no row with id 7"))
+///     .wrap_err("user not found")
+///     .extension(GrpcCode(Code::NotFound))
+///     .unwrap_err();
+/// report.extensions_mut().unwrap().insert_serializable(7u64);
+///
+/// let status = grpc::report_to_status(report);
+/// assert_eq!(status.code(), Code::NotFound);
+/// assert_eq!(status.message(), "user not found");
+///
+/// let details: serde_json::Value = serde_json::from_slice(status.details()).unwrap();
+/// assert_eq!(details, serde_json::json!({ "u64": 7 }));
+///
+/// let status = grpc::report_to_status(eyre!("connection reset"));
+/// assert_eq!(status.code(), Code::Internal);
+/// assert_eq!(status.message(), "connection reset");
+/// assert!(status.details().is_empty());
+/// ```
+pub fn report_to_status(report: Report) -> Status {
+    let code = report
+        .extension_ref::<GrpcCode>()
+        .map_or(Code::Internal, |code| code.0);
+    let message = report.to_string();
+
+    let details = report
+        .extensions_ref()
+        .map(|extensions| extensions.to_json_value())
+        .filter(|extensions| extensions.as_object().is_some_and(|map| !map.is_empty()));
+
+    match details {
+        Some(details) => Status::with_details(
+            code,
+            message,
+            bytes::Bytes::from(details.to_string().into_bytes()),
+        ),
+        None => Status::new(code, message),
+    }
+}
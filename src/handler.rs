@@ -1,4 +1,128 @@
+use std::any::{Any, TypeId};
+use std::panic::Location;
+
+use crate::builtin::{
+    ErrorCode, Quiet, ReportLocation, SectionKind, Severity, Suggestion, Suppress, Verbose,
+};
+use crate::config::Source;
+use crate::section::SuggestionLine;
+use crate::trace::SectionedReport;
 use crate::Handler;
+use owo_colors::OwoColorize;
+
+impl Handler {
+    /// Attaches `value` on behalf of `source`, unless the value already attached for `T` comes
+    /// from a source the configured precedence ranks higher.
+    ///
+    /// `value` is only produced when it wins, and recorded as attached at the caller's location.
+    #[track_caller]
+    pub(crate) fn attach_with<T, F>(&mut self, source: Source, value: F)
+    where
+        T: Send + Sync + 'static,
+        F: FnOnce() -> T,
+    {
+        let wins = self.extensions.source::<T>().is_none_or(|existing| {
+            self.config
+                .precedence
+                .prefers_id(TypeId::of::<T>(), source, existing)
+        });
+
+        if wins {
+            let location = Location::caller();
+            self.extensions.insert_at(source, value(), location);
+        }
+    }
+
+    /// Records `location` as where the report was created, and shows it in the location section.
+    pub(crate) fn set_location(&mut self, location: &'static Location<'static>) {
+        self.location = Some(ReportLocation(location));
+        #[cfg(feature = "track-caller")]
+        self.inner.track_caller(location);
+    }
+
+    /// The value of type `T` attached to the report, or else to the outermost frame holding one.
+    pub(crate) fn get<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        // the location is kept out of the map, so it isn't listed in the `Extensions:` section
+        let location: &dyn Any = &self.location;
+        let location = || location.downcast_ref::<Option<T>>()?.as_ref();
+
+        self.extensions
+            .get::<T>()
+            .or_else(|| self.frames.get::<T>())
+            .or_else(location)
+    }
+
+    pub(crate) fn get_mut<T: Send + Sync + 'static>(&mut self) -> Option<&mut T> {
+        if self.extensions.get::<T>().is_some() {
+            return self.extensions.get_mut::<T>();
+        }
+
+        self.frames.get_mut::<T>()
+    }
+
+    /// The sections the [`Suppress`] and [`Quiet`] extensions leave out of the report.
+    fn suppressed_sections(&self) -> SectionKind {
+        let mut suppressed = self
+            .extensions
+            .get::<Suppress>()
+            .map_or(SectionKind::empty(), |suppress| suppress.0);
+
+        if self.extensions.get::<Quiet>().is_some() {
+            suppressed =
+                suppressed | SectionKind::SPANTRACE | SectionKind::BACKTRACE | SectionKind::ENV;
+        }
+
+        suppressed
+    }
+
+    /// Whether the report is rendered section by section instead of by the inner handler, to
+    /// leave out the `suppressed` sections or show the extensions attached to frames.
+    fn is_sectioned(&self, suppressed: SectionKind) -> bool {
+        let needed = !suppressed.is_empty() || !self.frames.is_empty();
+        needed && crate::trace::is_sectioned(self.inner.as_ref())
+    }
+
+    /// Whether the rendered error starts with a line break, like color-eyre's reports do.
+    fn starts_on_new_line(&self, suppressed: SectionKind) -> bool {
+        let sectioned = self.is_sectioned(suppressed);
+
+        #[cfg(feature = "color-eyre")]
+        return sectioned || self.inner.is::<color_eyre::Handler>();
+        #[cfg(not(feature = "color-eyre"))]
+        return sectioned;
+    }
+
+    /// Renders `error` with the inner handler, or section by section if some are `suppressed` or
+    /// frames hold extensions.
+    fn debug_inner(
+        &self,
+        error: &(dyn std::error::Error + 'static),
+        suppressed: SectionKind,
+        verbose: bool,
+        f: &mut core::fmt::Formatter<'_>,
+    ) -> core::fmt::Result {
+        if f.alternate() || !self.is_sectioned(suppressed) {
+            #[cfg(feature = "color-eyre")]
+            return crate::frame_filter::rendering(&self.extensions, || {
+                eyre::EyreHandler::debug(self.inner.as_ref(), error, f)
+            });
+            #[cfg(not(feature = "color-eyre"))]
+            return eyre::EyreHandler::debug(self.inner.as_ref(), error, f);
+        }
+
+        let report = SectionedReport {
+            error,
+            frames: &self.frames,
+            formatters: &self.config.formatters,
+            location: self.location.map(|ReportLocation(location)| location),
+            inner: self.inner.as_ref(),
+            traces: &self.traces,
+            verbose,
+            suppressed,
+        };
+        write!(f, "{}", report)
+    }
+}
 
 impl eyre::EyreHandler for Handler {
     fn debug(
@@ -6,11 +130,139 @@ impl eyre::EyreHandler for Handler {
         error: &(dyn std::error::Error + 'static),
         f: &mut core::fmt::Formatter<'_>,
     ) -> core::fmt::Result {
-        eyre::EyreHandler::debug(self.inner.as_ref(), error, f)
+        #[cfg(feature = "uuid")]
+        if let Some(id) = self.extensions.get::<crate::builtin::ReportId>() {
+            if !f.alternate() {
+                let style = self.config.extension_styles.report_id;
+                writeln!(f, "Report id: {}", id.style(style))?;
+            }
+        }
+
+        let suppressed = self.suppressed_sections();
+        let verbose = self.extensions.get::<Verbose>().is_some();
+
+        let severity = self.extensions.get::<Severity>().filter(|_| !f.alternate());
+        if let Some(severity) = severity {
+            if let Some(style) = self.config.severity_styles.get(severity) {
+                let title = severity.to_string().to_uppercase();
+                write!(f, "{}", crate::trace::header(&title).style(*style))?;
+                if !self.starts_on_new_line(suppressed) {
+                    writeln!(f)?;
+                }
+            }
+        }
+
+        match crate::chain::truncate(error, self.config.max_chain_depth) {
+            Some((truncated, depth)) => {
+                self.debug_inner(&truncated, suppressed, verbose, f)?;
+                write!(f, "\n\n(source chain truncated at depth {})", depth)?;
+            }
+            None => self.debug_inner(error, suppressed, verbose, f)?,
+        }
+
+        let sectioned = !f.alternate() && self.is_sectioned(suppressed);
+        if verbose && suppressed.is_empty() && !f.alternate() && !sectioned {
+            write!(f, "{}", self.traces)?;
+        }
+
+        if let Some(code) = self.extensions.get::<ErrorCode>() {
+            let style = self.config.extension_styles.error_code;
+            write!(f, "\n\nError code: {}", code.style(style))?;
+            if let Some(url) = &self.config.error_code_base_url {
+                let separator = if url.ends_with('/') { "" } else { "/" };
+                write!(f, " ({}{}{})", url, separator, code)?;
+            }
+        }
+
+        #[cfg(feature = "time")]
+        if let Some(created_at) = self.extensions.get::<crate::builtin::CreatedAt>() {
+            write!(f, "\n\nCreated at: {}", created_at)?;
+        }
+
+        if let Some(thread) = self.extensions.get::<crate::builtin::ThreadInfo>() {
+            write!(f, "\n\nThread: {}", thread)?;
+        }
+
+        if let Some(process) = self.extensions.get::<crate::builtin::ProcessInfo>() {
+            write!(f, "\n\nProcess: {}", process)?;
+        }
+
+        if let Some(env) = self.extensions.get::<crate::builtin::CapturedEnv>() {
+            let masked = env.masked(&self.config.redacted_env_vars);
+            write!(f, "\n\nEnvironment: {}", masked)?;
+        }
+
+        for section in self.sections.iter().filter(|section| !section.is_help()) {
+            write!(f, "\n\n{}", section.rendered(&self.config))?;
+        }
+
+        let mut separator = "\n\n";
+        for section in self.sections.iter().filter(|section| section.is_help()) {
+            write!(f, "{}{}", separator, section.rendered(&self.config))?;
+            separator = "\n";
+        }
+        for suggestion in self.extensions.get_all::<Suggestion>() {
+            let line = SuggestionLine(suggestion, self.config.extension_styles.suggestion);
+            write!(f, "{}{}", separator, line)?;
+            separator = "\n";
+        }
+
+        if self.config.display_extensions_section && !self.extensions.is_empty() {
+            write!(f, "\n\n{}", self.extensions.section(&self.config))?;
+        }
+
+        if !self.frames.is_empty() && !sectioned {
+            let len = crate::chain::Chain::new(error, self.config.max_chain_depth).count();
+            write!(f, "\n\nFrame extensions:")?;
+            for (index, extensions) in self.frames.iter(len) {
+                let summary = extensions.summary(&self.config.formatters);
+                write!(f, "\n   {}: {}", index, summary)?;
+            }
+        }
+
+        if !self.meta.is_empty() {
+            write!(f, "\n\n{}", self.meta)?;
+        }
+
+        #[cfg(feature = "issue-url")]
+        if let Some(url) = &self.config.issue_url {
+            let kind = crate::ErrorKind::Recoverable(error);
+            if !f.alternate() && (self.config.issue_filter)(kind, Some(&self.extensions)) {
+                let metadata = self
+                    .config
+                    .issue_metadata
+                    .iter()
+                    .filter_map(|(key, value)| Some((key.as_str(), value(&self.extensions)?)))
+                    .collect();
+
+                let issue = crate::issue::IssueSection {
+                    url,
+                    error,
+                    max_depth: self.config.max_chain_depth,
+                    metadata,
+                    inner: self.inner.downcast_ref::<color_eyre::Handler>(),
+                };
+                write!(f, "\n\n{}", issue)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn display(
+        &self,
+        error: &(dyn std::error::Error + 'static),
+        f: &mut core::fmt::Formatter<'_>,
+    ) -> core::fmt::Result {
+        // `{:#}` walks the whole source chain, so bound it the same way as `debug`
+        match crate::chain::truncate(error, self.config.max_chain_depth) {
+            Some((truncated, _)) => eyre::EyreHandler::display(self.inner.as_ref(), &truncated, f),
+            None => eyre::EyreHandler::display(self.inner.as_ref(), error, f),
+        }
     }
 
     #[cfg(feature = "track-caller")]
     fn track_caller(&mut self, location: &'static std::panic::Location<'static>) {
-        self.inner.track_caller(location);
+        self.set_location(location);
     }
 }
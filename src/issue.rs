@@ -0,0 +1,73 @@
+//! Issue urls generated by extension-eyre itself
+//!
+//! color-eyre fills the metadata table of its issue urls from values fixed when the hooks are
+//! built, so it can't include values from a report's [`Extensions`]. The eyre hook renders the
+//! issue section of reports itself instead, and leaves the one of panics to color-eyre.
+use std::error::Error;
+use std::fmt::{self, Write as _};
+
+use crate::chain::Chain;
+use crate::extensions::Extensions;
+
+/// Callback returning the value of an issue metadata entry for a report, if it has one.
+pub(crate) type IssueMetadataCallback = dyn Fn(&Extensions) -> Option<String> + Send + Sync;
+
+/// The "Consider reporting this error" section of a report.
+pub(crate) struct IssueSection<'a> {
+    pub(crate) url: &'a str,
+    pub(crate) error: &'a (dyn Error + 'static),
+    pub(crate) max_depth: usize,
+    pub(crate) metadata: Vec<(&'a str, String)>,
+    pub(crate) inner: Option<&'a color_eyre::Handler>,
+}
+
+impl fmt::Display for IssueSection<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut payload = String::from("Error:");
+        for (n, error) in Chain::new(self.error, self.max_depth).enumerate() {
+            write!(payload, "\n{:>4}: {}", n, error)?;
+        }
+
+        let mut body = String::new();
+        push_section(&mut body, "Error", &console(&payload))?;
+
+        if !self.metadata.is_empty() {
+            let mut table = String::from("|key|value|\n|--|--|\n");
+            for (key, value) in &self.metadata {
+                writeln!(table, "|**{}**|{}|", key, value)?;
+            }
+            push_section(&mut body, "Metadata", &table)?;
+        }
+
+        #[cfg(feature = "capture-spantrace")]
+        if let Some(span_trace) = self.inner.and_then(color_eyre::Handler::span_trace) {
+            let span_trace = format!("SpanTrace:\n{}", span_trace);
+            push_section(&mut body, "SpanTrace", &collapsed(&console(&span_trace)))?;
+        }
+
+        if let Some(backtrace) = self.inner.and_then(color_eyre::Handler::backtrace) {
+            let backtrace = format!("Backtrace:\n{:?}", backtrace);
+            push_section(&mut body, "Backtrace", &collapsed(&console(&backtrace)))?;
+        }
+
+        let params = [("title", "<autogenerated-issue>"), ("body", body.as_str())];
+        write!(f, "Consider reporting this error using this URL: ")?;
+        match url::Url::parse_with_params(self.url, &params) {
+            Ok(url) => write!(f, "{}", url),
+            Err(_) => write!(f, "{}", self.url),
+        }
+    }
+}
+
+fn push_section(body: &mut String, header: &str, section: &str) -> fmt::Result {
+    let separator = if body.is_empty() { "" } else { "\n\n" };
+    write!(body, "{}## {}\n{}", separator, header, section)
+}
+
+fn console(text: &str) -> String {
+    format!("```\n{}\n```", text)
+}
+
+fn collapsed(text: &str) -> String {
+    format!("\n<details>\n\n{}\n</details>", text)
+}
@@ -0,0 +1,118 @@
+use std::fmt;
+
+use serde_json::{json, Map, Value};
+
+use crate::builtin::ReportLocation;
+use crate::chain::Chain;
+use crate::extensions::Extension;
+use crate::eyre::Report;
+
+/// Renders `report` as a JSON object, for structured logs.
+///
+/// # Details
+///
+/// The object holds:
+///
+/// - `message`: the report's top-level message
+/// - `chain`: the messages of the report's error and its sources
+/// - `location`: the `file`, `line` and `column` the report was created at, when known
+/// - `span_trace`: the captured span trace, rendered without colors, when there is one
+/// - `extensions`: the extensions inserted with
+///   [`Extensions::insert_serializable`](crate::extensions::Extensions::insert_serializable)
+///
+/// # Examples
+///
+/// ```rust
+/// use extension_eyre::{eyre::eyre, Extension, WrapErr};
+///
+/// extension_eyre::install().unwrap();
+///
+/// let mut report = Err::<(), _>(eyre!("connection refused"))
+///     .wrap_err("could not load the cart")
+///     .unwrap_err();
+/// let extensions = report.extensions_mut().unwrap();
+/// extensions.insert_serializable(3u32);
+/// extensions.insert_serializable(String::from("GET /cart"));
+///
+/// let json = extension_eyre::report_to_json(&report);
+///
+/// assert_eq!(json["message"], "could not load the cart");
+/// assert_eq!(json["chain"], serde_json::json!(["could not load the cart", "connection refused"]));
+/// assert_eq!(json["location"]["file"], file!());
+/// assert_eq!(json["extensions"]["u32"], 3);
+/// assert_eq!(json["extensions"]["alloc::string::String"], "GET /cart");
+/// ```
+pub fn report_to_json(report: &Report) -> Value {
+    let mut object = Map::new();
+
+    object.insert("message".to_owned(), json!(report.to_string()));
+    object.insert(
+        "chain".to_owned(),
+        Chain::of_report(report)
+            .map(|error| json!(error.to_string()))
+            .collect(),
+    );
+
+    let handler = report.handler().downcast_ref::<crate::Handler>();
+
+    if let Some(ReportLocation(location)) = handler.and_then(|handler| handler.location) {
+        object.insert(
+            "location".to_owned(),
+            json!({
+                "file": location.file(),
+                "line": location.line(),
+                "column": location.column(),
+            }),
+        );
+    }
+
+    #[cfg(feature = "capture-spantrace")]
+    {
+        let span_trace = handler
+            .and_then(|handler| crate::trace::captured_span_trace(handler.inner.as_ref()))
+            .filter(|span_trace| span_trace.status() == tracing_error::SpanTraceStatus::CAPTURED);
+
+        if let Some(span_trace) = span_trace {
+            object.insert("span_trace".to_owned(), json!(span_trace.to_string()));
+        }
+    }
+
+    let extensions = report
+        .extensions_ref()
+        .map_or_else(|| json!({}), |extensions| extensions.to_json_value());
+    object.insert("extensions".to_owned(), extensions);
+
+    Value::Object(object)
+}
+
+/// Displays a report as the compact JSON of [`report_to_json`].
+///
+/// Meant for logging reports as structured fields.
+///
+/// # Examples
+///
+/// ```rust
+/// use extension_eyre::{eyre::eyre, JsonReport};
+///
+/// extension_eyre::install().unwrap();
+///
+/// let report = eyre!("connection refused");
+/// tracing::error!(report = %JsonReport(&report), "request failed");
+///
+/// let json: serde_json::Value = serde_json::from_str(&JsonReport(&report).to_string()).unwrap();
+/// assert_eq!(json["message"], "connection refused");
+/// ```
+#[derive(Clone, Copy)]
+pub struct JsonReport<'a>(pub &'a Report);
+
+impl fmt::Display for JsonReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", report_to_json(self.0))
+    }
+}
+
+impl fmt::Debug for JsonReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("JsonReport").field(&self.0).finish()
+    }
+}
@@ -0,0 +1,219 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use crate::builtin::ErrorCode;
+use crate::chain::Chain;
+use crate::extensions::{Extension, Extensions};
+use crate::eyre::Report;
+
+// Hashes the values of one keyed extension type, if the report has any.
+type HashFn = fn(&Extensions) -> Option<u64>;
+
+/// Describes which parts of a report make up its [`ErrorKey`].
+///
+/// By default a key is made of the messages in the report's source chain. Extension types
+/// added with [`extension`](KeySpec::extension) contribute their values as well.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::KeySpec;
+///
+/// #[derive(Hash, PartialEq, Eq)]
+/// pub struct Tenant(String);
+///
+/// // reports for the same tenant share a key whatever their message
+/// let spec = KeySpec::new().extension::<Tenant>().message_chain(false);
+/// ```
+#[derive(Clone)]
+pub struct KeySpec {
+    message_chain: bool,
+    extensions: Vec<(&'static str, HashFn)>,
+}
+
+impl KeySpec {
+    /// Creates a spec that keys reports by their message chain only.
+    pub fn new() -> Self {
+        KeySpec {
+            message_chain: true,
+            extensions: Vec::new(),
+        }
+    }
+
+    /// Sets whether the messages of the report's source chain are part of the key.
+    pub fn message_chain(mut self, include: bool) -> Self {
+        self.message_chain = include;
+        self
+    }
+
+    /// Adds the values of extension type `T` to the key.
+    ///
+    /// A report without a value of type `T` gets a key that differs from every report that has
+    /// one. When several values of `T` were pushed, all of them are part of the key.
+    pub fn extension<T: Hash + Eq + Send + Sync + 'static>(mut self) -> Self {
+        self.extensions
+            .push((std::any::type_name::<T>(), hash_extension::<T>));
+        self
+    }
+}
+
+impl Default for KeySpec {
+    fn default() -> Self {
+        KeySpec::new()
+    }
+}
+
+impl fmt::Debug for KeySpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeySpec")
+            .field("message_chain", &self.message_chain)
+            .field(
+                "extensions",
+                &self
+                    .extensions
+                    .iter()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+/// Identifies a failure for deduplication, so reports describing the same failure can share a
+/// map entry.
+///
+/// # Details
+///
+/// The key only holds material derived from the report as described by its [`KeySpec`]: the
+/// messages of the source chain and a hash of each keyed extension's values. Backtraces, span
+/// traces and extensions that aren't part of the spec don't affect it.
+///
+/// With the `serde` feature enabled, the key material can be serialized, e.g. to persist a
+/// dedup cache.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::{eyre::eyre, ErrorKey, ExtensionExt, KeySpec};
+/// use std::collections::HashMap;
+///
+/// #[derive(Hash, PartialEq, Eq)]
+/// pub struct Tenant(&'static str);
+///
+/// extension_eyre::install().unwrap();
+///
+/// let spec = KeySpec::new().extension::<Tenant>();
+/// let mut occurrences = HashMap::<ErrorKey, usize>::new();
+///
+/// // same failure from two places, so only the backtraces differ
+/// let first = eyre!("connection reset").extension(Tenant("acme"));
+/// let second = eyre!("connection reset").extension(Tenant("acme"));
+/// let other_tenant = eyre!("connection reset").extension(Tenant("globex"));
+///
+/// for report in [&first, &second, &other_tenant] {
+///     *occurrences.entry(ErrorKey::from_report(report, &spec)).or_default() += 1;
+/// }
+///
+/// assert_eq!(occurrences[&ErrorKey::from_report(&first, &spec)], 2);
+/// assert_eq!(occurrences[&ErrorKey::from_report(&other_tenant, &spec)], 1);
+/// ```
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ErrorKey {
+    chain: Option<Vec<String>>,
+    extensions: Vec<(&'static str, Option<u64>)>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    spec: KeySpec,
+}
+
+impl ErrorKey {
+    /// Derives the key of `report` according to `spec`.
+    pub fn from_report(report: &Report, spec: &KeySpec) -> Self {
+        let chain = if spec.message_chain {
+            Some(Chain::of_report(report).map(|e| e.to_string()).collect())
+        } else {
+            None
+        };
+
+        let extensions = spec
+            .extensions
+            .iter()
+            .map(|&(name, hash)| (name, report.extensions_ref().and_then(hash)))
+            .collect();
+
+        ErrorKey {
+            chain,
+            extensions,
+            spec: spec.clone(),
+        }
+    }
+
+    /// Returns whether `report` has this key, using the spec this key was created with.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, ErrorKey, ExtensionExt, KeySpec};
+    ///
+    /// #[derive(Hash, PartialEq, Eq)]
+    /// pub struct StatusCode(u16);
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let spec = KeySpec::new().extension::<StatusCode>();
+    /// let key = ErrorKey::from_report(&eyre!("upstream failed").extension(StatusCode(502)), &spec);
+    ///
+    /// assert!(key.matches(&eyre!("upstream failed").extension(StatusCode(502))));
+    /// assert!(!key.matches(&eyre!("upstream failed").extension(StatusCode(504))));
+    /// assert!(!key.matches(&eyre!("upstream failed")));
+    /// assert!(!key.matches(&eyre!("upstream timed out").extension(StatusCode(502))));
+    /// ```
+    pub fn matches(&self, report: &Report) -> bool {
+        *self == ErrorKey::from_report(report, &self.spec)
+    }
+}
+
+impl PartialEq for ErrorKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.chain == other.chain && self.extensions == other.extensions
+    }
+}
+
+impl Eq for ErrorKey {}
+
+impl Hash for ErrorKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.chain.hash(state);
+        self.extensions.hash(state);
+    }
+}
+
+impl fmt::Debug for ErrorKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ErrorKey")
+            .field("chain", &self.chain)
+            .field("extensions", &self.extensions)
+            .finish()
+    }
+}
+
+/// The fingerprint of `report`, see [`Extension::fingerprint`].
+pub(crate) fn fingerprint(report: &Report) -> u64 {
+    let spec = KeySpec::new().extension::<ErrorCode>();
+
+    let mut hasher = DefaultHasher::new();
+    ErrorKey::from_report(report, &spec).hash(&mut hasher);
+    hasher.finish()
+}
+
+fn hash_extension<T: Hash + Send + Sync + 'static>(extensions: &Extensions) -> Option<u64> {
+    let mut values = extensions.get_all::<T>().peekable();
+    values.peek()?;
+
+    // `DefaultHasher::new` always uses the same keys, so the material is stable across runs of
+    // the same build.
+    let mut hasher = DefaultHasher::new();
+    values.for_each(|value| value.hash(&mut hasher));
+    Some(hasher.finish())
+}
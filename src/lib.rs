@@ -66,9 +66,44 @@
 //!
 //! ```toml
 //! [dependencies]
+//! extension-eyre = { version = "0.6", default-features = false, features = ["color-eyre"] }
+//! ```
+//!
+//! ### Using extensions without color-eyre
+//!
+//! Disabling the `color-eyre` feature as well drops `color-eyre` and its backtrace machinery,
+//! e.g. for WASM targets. Reports are then rendered by [`eyre::DefaultHandler`], followed by the
+//! sections of this crate, and panics by a plain panic message. [`Extension`], [`ExtensionExt`]
+//! and [`Extensions`](extensions::Extensions) work the same, while the settings of
+//! [`HookBuilder`](config::HookBuilder) that configure color-eyre are left out.
+//!
+//! ```toml
+//! [dependencies]
 //! extension-eyre = { version = "0.6", default-features = false }
 //! ```
 //!
+//! ```rust
+//! use extension_eyre::builtin::ErrorCode;
+//! use extension_eyre::{eyre::eyre, Extension, ExtensionExt};
+//!
+//! extension_eyre::install().unwrap();
+//!
+//! let mut report = Err::<(), _>(eyre!("disk full"))
+//!     .extension(ErrorCode("E42"))
+//!     .unwrap_err();
+//! assert_eq!(report.extension_ref::<ErrorCode>(), Some(&ErrorCode("E42")));
+//!
+//! *report.extension_mut::<ErrorCode>().unwrap() = ErrorCode("E43");
+//! assert_eq!(report.take_extension::<ErrorCode>(), Some(ErrorCode("E43")));
+//! assert_eq!(report.extension_ref::<ErrorCode>(), None);
+//!
+//! let handler = report.handler().downcast_ref::<extension_eyre::Handler>().unwrap();
+//! #[cfg(not(feature = "color-eyre"))]
+//! assert!(handler.is::<extension_eyre::eyre::DefaultHandler>());
+//! #[cfg(feature = "color-eyre")]
+//! assert!(handler.is::<color_eyre::Handler>());
+//! ```
+//!
 //! ### Disabling SpanTrace capture by default
 //!
 //! extension-eyre defaults to capturing span traces. This is because `SpanTrace`
@@ -240,6 +275,7 @@
 //! [`examples/custom_section.rs`]:
 //!
 //! ```rust
+//! # #[cfg(feature = "color-eyre")] {
 //! use extension_eyre::{eyre::eyre, SectionExt, Section, eyre::Report};
 //! use std::process::Command;
 //! use tracing::instrument;
@@ -265,6 +301,7 @@
 //!         }
 //!     }
 //! }
+//! # }
 //! ```
 //!
 //! ---
@@ -337,7 +374,7 @@
 //! [`backtrace::Backtrace`]: https://docs.rs/backtrace/*/backtrace/struct.Backtrace.html
 //! [`tracing_error::SpanTrace`]: https://docs.rs/tracing-error/*/tracing_error/struct.SpanTrace.html
 //! [`color-spantrace`]: https://github.com/yaahc/color-spantrace
-//! [`Section`]: https://docs.rs/color-eyre/*/extension_eyre/trait.Section.html
+//! [`Section`]: https://docs.rs/extension-eyre/*/extension_eyre/trait.Section.html
 //! [`eyre::Report`]: https://docs.rs/eyre/*/eyre/struct.Report.html
 //! [`eyre::Result`]: https://docs.rs/eyre/*/eyre/type.Result.html
 //! [`Handler`]: https://docs.rs/color-eyre/*/extension_eyre/struct.Handler.html
@@ -353,7 +390,6 @@
     rust_2018_idioms,
     unreachable_pub,
     bad_style,
-    const_err,
     dead_code,
     improper_ctypes,
     non_shorthand_field_patterns,
@@ -361,7 +397,8 @@
     overflowing_literals,
     path_statements,
     patterns_in_fns_without_body,
-    private_in_public,
+    private_interfaces,
+    private_bounds,
     unconditional_recursion,
     unused,
     unused_allocation,
@@ -374,26 +411,178 @@
 use std::ops::Deref;
 use std::ops::DerefMut;
 
-pub use color_eyre::owo_colors;
-pub use eyre;
-#[doc(hidden)]
-pub use eyre::Report;
-#[doc(hidden)]
-pub use eyre::Result;
+pub use owo_colors;
+// `extern crate` only defines `eyre` in the type namespace, leaving the macro namespace free for
+// this crate's own `eyre!`.
+#[allow(unused_extern_crates)]
+pub extern crate eyre;
+pub use eyre::{ContextCompat, Report, Result, WrapErr};
 #[cfg(feature = "capture-spantrace")]
 #[doc(hidden)]
 pub use Handler as Context;
 
+mod analysis;
+#[cfg(feature = "anyhow")]
+mod anyhow_interop;
+pub mod builtin;
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub mod catalog;
+mod chain;
+mod compact;
+mod crash;
+mod dedup;
 pub mod config;
 pub mod extensions;
+#[cfg(feature = "futures")]
+#[cfg_attr(docsrs, doc(cfg(feature = "futures")))]
+pub mod future;
+#[cfg(feature = "tonic")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tonic")))]
+pub mod grpc;
+#[cfg(feature = "color-eyre")]
+mod frame_filter;
 mod handler;
+#[cfg(feature = "issue-url")]
+mod issue;
+#[cfg(feature = "serde")]
+mod json;
+mod key;
+#[cfg(feature = "tracing")]
+mod log;
+mod macros;
+mod main_result;
+#[cfg(feature = "matchers")]
+#[cfg_attr(docsrs, doc(cfg(feature = "matchers")))]
+pub mod matchers;
+#[cfg(feature = "tower")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tower")))]
+pub mod middleware;
+#[cfg(feature = "miette")]
+mod miette_interop;
+pub mod migration;
+#[cfg(feature = "opentelemetry")]
+#[cfg_attr(docsrs, doc(cfg(feature = "opentelemetry")))]
+pub mod otel;
+mod panic_extensions;
+#[cfg(feature = "serde")]
+mod panic_format;
+#[cfg(not(feature = "color-eyre"))]
+mod plain;
+mod precedence;
 pub(crate) mod private;
+mod quiet;
+mod recent;
+mod redaction;
+pub mod reporter;
+mod retry;
+mod scope;
+mod section;
+#[cfg(feature = "sentry")]
+#[cfg_attr(docsrs, doc(cfg(feature = "sentry")))]
+pub mod sentry;
+pub mod sink;
+#[cfg(feature = "futures")]
+#[cfg_attr(docsrs, doc(cfg(feature = "futures")))]
+pub mod stream;
+mod styles;
+mod trace;
+#[cfg(any(feature = "axum", feature = "actix"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "axum", feature = "actix"))))]
+pub mod web;
 
+#[cfg(feature = "color-eyre")]
+#[cfg_attr(docsrs, doc(cfg(feature = "color-eyre")))]
 pub use color_eyre::IndentedSection;
-pub use extensions::{Extension, ExtensionExt};
+#[cfg(feature = "issue-url")]
+#[cfg_attr(docsrs, doc(cfg(feature = "issue-url")))]
+pub use color_eyre::ErrorKind;
+pub use analysis::{analyze, ReportAnalysis};
+#[cfg(feature = "anyhow")]
+#[cfg_attr(docsrs, doc(cfg(feature = "anyhow")))]
+pub use anyhow_interop::{from_anyhow, AnyhowExt};
+pub use compact::Compact;
+pub use dedup::ReportDeduper;
+pub use extensions::{wrap_report, Extension, ExtensionExt};
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub use json::{report_to_json, JsonReport};
+pub use key::{ErrorKey, KeySpec};
+pub use main_result::MainResult;
+#[cfg(feature = "miette")]
+#[cfg_attr(docsrs, doc(cfg(feature = "miette")))]
+pub use miette_interop::{from_miette, to_miette};
+pub use panic_extensions::{set_panic_extension, with_panic_extensions};
+pub use quiet::{quietly, ObserversBypassed};
+pub use recent::{recent_errors, RecentError};
+pub use retry::retry;
+#[cfg(feature = "tokio")]
+pub use retry::retry_async;
+#[cfg(feature = "tokio")]
+pub use scope::scope_async;
+pub use scope::{scope, ScopeGuard};
 #[doc(hidden)]
-pub use color_eyre::section::Section as Help;
-pub use color_eyre::section::{Section, SectionExt};
+pub use section::Section as Help;
+#[cfg(feature = "color-eyre")]
+#[cfg_attr(docsrs, doc(cfg(feature = "color-eyre")))]
+pub use color_eyre::section::SectionExt;
+pub use section::Section;
+
+/// The traits and macros most code using this crate needs, in one import.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::prelude::*;
+///
+/// pub struct Retry(bool);
+///
+/// fn read_config() -> Result<String> {
+///     std::fs::read_to_string("fake_file")
+///         .wrap_err("Unable to read config")
+///         .extension(Retry(true))
+/// }
+///
+/// extension_eyre::install()?;
+///
+/// let report = read_config().unwrap_err();
+/// assert!(matches!(report.extension_ref(), Some(Retry(true))));
+/// # Ok::<(), Report>(())
+/// ```
+pub mod prelude {
+    pub use crate::{bail, bail_ext, ensure, ensure_ext, eyre, report};
+    pub use crate::{ContextCompat, Report, Result, WrapErr};
+    pub use crate::{Extension, ExtensionExt, Section};
+    #[cfg(feature = "color-eyre")]
+    pub use crate::SectionExt;
+}
+
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(feature = "tracing")]
+    pub use crate::log::LogFields;
+    #[cfg(feature = "tracing")]
+    pub use tracing;
+
+    /// Installs the default error hook unless a hook has already been installed.
+    ///
+    /// Called by this crate's macros before they create a report.
+    pub fn auto_install() {
+        static AUTO_INSTALL: std::sync::Once = std::sync::Once::new();
+
+        AUTO_INSTALL.call_once(|| {
+            if crate::config::is_installed() {
+                return;
+            }
+
+            // Fails if the application installed a hook itself, which is exactly when we
+            // should leave things alone.
+            if let Ok((_, eyre_hook)) = crate::config::HookBuilder::default().try_into_hooks() {
+                let _ = eyre_hook.install();
+            }
+        });
+    }
+}
 
 /// A custom handler type for [`eyre::Report`] which provides colorful error
 /// reports and [`tracing-error`] support.
@@ -403,13 +592,63 @@ pub use color_eyre::section::{Section, SectionExt};
 /// This type is not intended to be used directly, prefer using it via the
 /// [`extension_eyre::Report`] and [`extension_eyre::Result`] type aliases.
 ///
+/// Every `EyreHandler` method is forwarded to the wrapped color-eyre handler, so reports format
+/// like color-eyre's apart from the sections this crate adds to `{:?}`.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::eyre::{EyreHandler, Report};
+/// use std::error::Error;
+/// use std::fmt;
+///
+/// #[derive(Debug)]
+/// struct Timeout(Option<Box<Timeout>>);
+///
+/// impl fmt::Display for Timeout {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         f.write_str("request timed out")
+///     }
+/// }
+///
+/// impl Error for Timeout {
+///     fn source(&self) -> Option<&(dyn Error + 'static)> {
+///         self.0.as_deref().map(|source| source as _)
+///     }
+/// }
+///
+/// struct Display<'a>(&'a dyn EyreHandler, &'a (dyn Error + 'static));
+///
+/// impl fmt::Display for Display<'_> {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         self.0.display(self.1, f)
+///     }
+/// }
+///
+/// extension_eyre::install().unwrap();
+///
+/// let report = Report::new(Timeout(Some(Box::new(Timeout(None)))));
+/// let handler = report.handler().downcast_ref::<extension_eyre::Handler>().unwrap();
+/// let color_eyre: &dyn EyreHandler = &***handler;
+///
+/// let plain = Display(color_eyre, report.as_ref());
+/// assert_eq!(format!("{}", report), format!("{}", plain));
+/// assert_eq!(format!("{:#}", report), format!("{:#}", plain));
+/// ```
+///
 /// [`eyre::Report`]: https://docs.rs/eyre/*/eyre/struct.Report.html
 /// [`tracing-error`]: https://docs.rs/tracing-error
 /// [`extension_eyre::Report`]: type.Report.html
 /// [`extension_eyre::Result`]: type.Result.html
 pub struct Handler {
     extensions: extensions::Extensions,
+    meta: extensions::Meta,
+    frames: extensions::Frames,
+    sections: Vec<section::HelpInfo>,
     inner: Box<dyn eyre::EyreHandler>,
+    traces: trace::Traces,
+    location: Option<builtin::ReportLocation>,
+    config: std::sync::Arc<config::HandlerConfig>,
 }
 
 impl Deref for Handler {
@@ -426,14 +665,31 @@ impl DerefMut for Handler {
     }
 }
 
-/// The kind of type erased error being reported
-#[cfg(feature = "issue-url")]
-#[cfg_attr(docsrs, doc(cfg(feature = "issue-url")))]
-pub enum ErrorKind<'a> {
-    /// A non recoverable error aka `panic!`
-    NonRecoverable(&'a dyn std::any::Any),
-    /// A recoverable error aka `impl std::error::Error`
-    Recoverable(&'a (dyn std::error::Error + 'static)),
+/// Install the default panic and error report hooks and run `f` as the body of `main`
+///
+/// Prints the error `f` returns and exits with its [`exit_code`](Extension::exit_code), see
+/// [`HookBuilder::run`](config::HookBuilder::run).
+///
+/// # Examples
+///
+/// ```rust
+/// use extension_eyre::builtin::ExitCode;
+/// use extension_eyre::ExtensionExt;
+///
+/// // usually returned from `main`
+/// let code = extension_eyre::run(|| {
+///     let config = std::fs::read_to_string("fake_file").extension(ExitCode(78))?;
+///     println!("{}", config);
+///     Ok(())
+/// });
+///
+/// assert_eq!(code, std::process::ExitCode::from(78));
+/// ```
+pub fn run<F>(f: F) -> std::process::ExitCode
+where
+    F: FnOnce() -> Result<(), crate::eyre::Report>,
+{
+    config::HookBuilder::default().run(f)
 }
 
 /// Install the default panic and error report hooks
@@ -445,9 +701,10 @@ pub enum ErrorKind<'a> {
 /// before any errors could be encountered.
 ///
 /// Only the first install will succeed. Calling this function after another
-/// report handler has been installed will cause an error. **Note**: This
-/// function _must_ be called before any `eyre::Report`s are constructed to
-/// prevent the default handler from being installed.
+/// report handler has been installed will cause an
+/// [`InstallError::AlreadyInstalled`](config::InstallError::AlreadyInstalled) error, which tells
+/// whether the installed handler is ours. **Note**: This function _must_ be called before any
+/// `eyre::Report`s are constructed to prevent the default handler from being installed.
 ///
 /// Installing a global theme in `color_spantrace` manually (by calling
 /// `color_spantrace::set_theme` or `color_spantrace::colorize` before
@@ -465,6 +722,50 @@ pub enum ErrorKind<'a> {
 ///     # Ok(())
 /// }
 /// ```
-pub fn install() -> Result<(), crate::eyre::Report> {
+pub fn install() -> Result<(), config::InstallError> {
     config::HookBuilder::default().install()
 }
+
+/// Install the default hooks unless the hooks of this crate are installed already
+///
+/// # Details
+///
+/// Meant for tests, which can't tell which of them installs the hooks first. Unlike [`install`],
+/// it succeeds when the hooks of this crate were installed before, by any thread. It still fails
+/// when another eyre hook was installed.
+///
+/// # Examples
+///
+/// ```rust
+/// let threads: Vec<_> = (0..8)
+///     .map(|_| std::thread::spawn(extension_eyre::test_install))
+///     .collect();
+///
+/// for thread in threads {
+///     thread.join().unwrap().unwrap();
+/// }
+///
+/// extension_eyre::test_install().unwrap();
+/// assert!(extension_eyre::is_installed());
+/// ```
+pub fn test_install() -> Result<(), config::InstallError> {
+    match install() {
+        Err(config::InstallError::AlreadyInstalled { ours: true }) => Ok(()),
+        result => result,
+    }
+}
+
+/// Whether the eyre hook of this crate was installed
+///
+/// # Examples
+///
+/// ```rust
+/// assert!(!extension_eyre::is_installed());
+///
+/// extension_eyre::install().unwrap();
+///
+/// assert!(extension_eyre::is_installed());
+/// ```
+pub fn is_installed() -> bool {
+    config::is_installed()
+}
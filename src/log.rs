@@ -0,0 +1,155 @@
+//! Emitting reports as structured [`tracing`] events, see [`log_report!`](crate::log_report)
+use crate::chain::Chain;
+use crate::extensions::Extension;
+use crate::eyre::Report;
+
+/// Emits a [`tracing`] event at `level` describing `report`, with the fields of the report as
+/// structured fields of the event.
+///
+/// # Details
+///
+/// The event is recorded with these fields, followed by the message and any fields given after
+/// `report`, like for [`tracing::event!`]:
+///
+/// - `error`: the report's message
+/// - `error.chain`: the messages of the report's error and its sources, joined with `": "`
+/// - `error.code`: the report's [`ErrorCode`](crate::builtin::ErrorCode), if any
+/// - `error.report_id`: the report's [`ReportId`](crate::builtin::ReportId), if any
+/// - `error.extensions`: the report's extensions on one line, with the values of those shown in
+///   its `Extensions:` section, like `Retry, Route=GET /prices`, if any
+///
+/// Tracing field names are fixed at compile time, so the extensions share the one
+/// `error.extensions` field. Redacted extensions are named without their value.
+///
+/// Reports created while another handler is installed only get the `error` and `error.chain`
+/// fields.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::{eyre::eyre, log_report, Extension, ExtensionExt};
+/// use extension_eyre::builtin::ErrorCode;
+/// use std::sync::{Arc, Mutex};
+/// use tracing::field::{Field, Visit};
+/// use tracing::{Event, Level, Subscriber};
+/// use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+///
+/// #[derive(Clone, Default)]
+/// struct Fields(Arc<Mutex<Vec<(String, String)>>>);
+///
+/// impl Visit for Fields {
+///     fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+///         let value = format!("{:?}", value);
+///         self.0.lock().unwrap().push((field.name().to_owned(), value));
+///     }
+///
+///     fn record_str(&mut self, field: &Field, value: &str) {
+///         self.0.lock().unwrap().push((field.name().to_owned(), value.to_owned()));
+///     }
+/// }
+///
+/// impl<S: Subscriber> Layer<S> for Fields {
+///     fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+///         event.record(&mut self.clone());
+///     }
+/// }
+///
+/// extension_eyre::install().unwrap();
+///
+/// let mut report = eyre!("connection reset")
+///     .wrap_err("could not fetch the price list")
+///     .extension(ErrorCode("E42"));
+/// report
+///     .extensions_mut()
+///     .unwrap()
+///     .insert_display(String::from("GET /prices"));
+///
+/// let fields = Fields::default();
+/// let subscriber = tracing_subscriber::registry().with(fields.clone());
+/// tracing::subscriber::with_default(subscriber, || {
+///     log_report!(Level::WARN, report, attempt = 3, "giving up on {}", "prices");
+/// });
+///
+/// let fields = fields.0.lock().unwrap().clone();
+/// let field = |name: &str| {
+///     let value = fields.iter().find(|(field, _)| field == name);
+///     value.map(|(_, value)| value.as_str())
+/// };
+/// assert_eq!(field("error"), Some("could not fetch the price list"));
+/// assert_eq!(
+///     field("error.chain"),
+///     Some("could not fetch the price list: connection reset")
+/// );
+/// assert_eq!(field("error.code"), Some("E42"));
+/// assert_eq!(field("error.report_id"), None);
+/// assert_eq!(field("error.extensions"), Some("ErrorCode, String=GET /prices"));
+/// assert_eq!(field("attempt"), Some("3"));
+/// assert_eq!(field("message"), Some("giving up on prices"));
+/// ```
+#[macro_export]
+#[cfg_attr(docsrs, doc(cfg(feature = "tracing")))]
+macro_rules! log_report {
+    ($level:expr, $report:expr, $($arg:tt)+) => {{
+        let fields = $crate::__private::LogFields::of(&$report);
+        $crate::__private::tracing::event!(
+            $level,
+            error = %fields.message,
+            error.chain = %fields.chain,
+            error.code = fields.code,
+            error.report_id = fields.report_id.as_ref().map(::std::string::String::as_str),
+            error.extensions = fields.extensions.as_ref().map(::std::string::String::as_str),
+            $($arg)+
+        )
+    }};
+    ($level:expr, $report:expr) => {
+        $crate::log_report!($level, $report, "error report")
+    };
+}
+
+/// The fields [`log_report!`](crate::log_report) records for a report.
+#[doc(hidden)]
+pub struct LogFields {
+    pub message: String,
+    pub chain: String,
+    pub code: Option<&'static str>,
+    pub report_id: Option<String>,
+    pub extensions: Option<String>,
+}
+
+impl LogFields {
+    pub fn of(report: &Report) -> Self {
+        let mut fields = LogFields {
+            message: report.to_string(),
+            chain: Chain::of_report(report)
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(": "),
+            code: None,
+            report_id: None,
+            extensions: None,
+        };
+
+        let handler = match report.handler().downcast_ref::<crate::Handler>() {
+            Some(handler) => handler,
+            None => return fields,
+        };
+
+        fields.code = report.error_code();
+        #[cfg(feature = "uuid")]
+        {
+            fields.report_id = handler
+                .extensions
+                .get::<crate::builtin::ReportId>()
+                .map(ToString::to_string);
+        }
+        let extensions = handler
+            .extensions
+            .summary(&handler.config.formatters)
+            .to_string();
+        if !extensions.is_empty() {
+            fields.extensions = Some(extensions);
+        }
+
+        fields
+    }
+}
@@ -0,0 +1,294 @@
+//! Report constructing macros that install extension-eyre's hook on first use
+//!
+//! These mirror the macros from [`eyre`] but make sure extension-eyre's error hook is installed
+//! before the report is created, so reports built before `main` gets to call
+//! [`install`](crate::install) still carry a [`Handler`](crate::Handler) and accept extensions.
+//! The report itself is still created by `eyre` at the call site, so `#[track_caller]` locations
+//! point at the caller rather than into this crate.
+
+/// Construct an ad-hoc error from a string, a format string with arguments, or an error value.
+///
+/// Behaves like [`eyre::eyre!`], except that extension-eyre's error hook is installed first if no
+/// hook has been installed yet.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::prelude::*;
+///
+/// pub struct Retry(bool);
+///
+/// // no call to `extension_eyre::install()`
+/// let report = eyre!("cmd exited with non-zero status code").extension(Retry(true));
+///
+/// assert!(matches!(report.extension_ref::<Retry>(), Some(Retry(true))));
+/// ```
+///
+/// The hook installed by the application is kept:
+///
+/// ```rust
+/// use extension_eyre::prelude::*;
+///
+/// pub struct Retry(bool);
+///
+/// extension_eyre::install().unwrap();
+///
+/// let report = extension_eyre::eyre!("cmd exited with non-zero status code").extension(Retry(true));
+/// assert!(matches!(report.extension_ref::<Retry>(), Some(Retry(true))));
+/// ```
+#[macro_export]
+macro_rules! eyre {
+    ($($args:tt)+) => {{
+        $crate::__private::auto_install();
+        $crate::eyre::eyre!($($args)+)
+    }};
+}
+
+/// Return early with an error.
+///
+/// Behaves like [`eyre::bail!`], except that extension-eyre's error hook is installed first if no
+/// hook has been installed yet.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::prelude::*;
+///
+/// fn read_config(path: &str) -> Result<String> {
+///     if path.is_empty() {
+///         bail!("no config path given");
+///     }
+///     # Ok(path.to_owned())
+///     // ...
+/// }
+///
+/// let report = read_config("").unwrap_err().extension(42usize);
+/// assert_eq!(report.to_string(), "no config path given");
+/// assert_eq!(report.extension_ref::<usize>(), Some(&42));
+/// ```
+#[macro_export]
+macro_rules! bail {
+    ($($args:tt)+) => {{
+        $crate::__private::auto_install();
+        $crate::eyre::bail!($($args)+)
+    }};
+}
+
+/// Return early with an error if a condition is not satisfied.
+///
+/// Behaves like [`eyre::ensure!`], except that extension-eyre's error hook is installed first if
+/// no hook has been installed yet.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::prelude::*;
+///
+/// fn check_user(user: u32) -> Result<()> {
+///     ensure!(user == 0, "only user 0 is allowed");
+///     Ok(())
+/// }
+///
+/// assert!(check_user(0).is_ok());
+///
+/// let report = check_user(1).unwrap_err().extension(1u32);
+/// assert_eq!(report.to_string(), "only user 0 is allowed");
+/// assert_eq!(report.extension_ref::<u32>(), Some(&1));
+/// ```
+#[macro_export]
+macro_rules! ensure {
+    ($cond:expr $(,)?) => {
+        $crate::ensure!($cond, concat!("Condition failed: `", stringify!($cond), "`"))
+    };
+    ($cond:expr, $($args:tt)+) => {
+        if !$cond {
+            $crate::bail!($($args)+);
+        }
+    };
+}
+
+/// Construct an ad-hoc error carrying one or more extensions.
+///
+/// Takes `ext = [..]` with a list of extensions attached in order, followed by the arguments of
+/// [`eyre!`](crate::eyre); without the list it behaves exactly like `eyre!`.
+///
+/// Extensions can only be attached when extension-eyre's hook created the report. If the
+/// application installed a different hook, the report is created as usual and the extensions are
+/// silently dropped.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::prelude::*;
+///
+/// #[derive(Debug, PartialEq)]
+/// pub struct Retry;
+///
+/// #[derive(Debug, PartialEq)]
+/// pub struct Code(u32);
+///
+/// let name = "inbox";
+/// let report = report!(ext = [Retry, Code(42)], "failed to sync {}", name);
+///
+/// assert_eq!(report.to_string(), "failed to sync inbox");
+/// assert_eq!(report.extension_ref(), Some(&Retry));
+/// assert_eq!(report.extension_ref(), Some(&Code(42)));
+///
+/// let report = report!("failed to sync {}", name);
+/// assert_eq!(report.to_string(), "failed to sync inbox");
+/// assert!(report.extensions_ref().unwrap().is_empty());
+/// ```
+#[macro_export]
+macro_rules! report {
+    (ext = [$($ext:expr),* $(,)?], $($args:tt)+) => {{
+        #[allow(unused_mut)]
+        let mut report = $crate::eyre!($($args)+);
+        $(report = $crate::ExtensionExt::extension(report, $ext);)*
+        report
+    }};
+    ($($args:tt)+) => {
+        $crate::eyre!($($args)+)
+    };
+}
+
+/// Return early with an error carrying one or more extensions.
+///
+/// The first argument is the extension to attach, or `ext = [..]` with a list of extensions
+/// attached in order; the rest is passed to [`bail!`](crate::bail) unchanged.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::prelude::*;
+///
+/// #[derive(Debug, PartialEq)]
+/// pub struct Retry(bool);
+///
+/// #[derive(Debug, PartialEq)]
+/// pub struct Path(String);
+///
+/// fn read_config(path: &str) -> Result<String> {
+///     if path.is_empty() {
+///         bail_ext!(Retry(false), "no config path given");
+///     }
+///     if !path.ends_with(".toml") {
+///         bail_ext!(ext = [Retry(false), Path(path.to_owned())], "{} is not a toml file", path);
+///     }
+///     # Ok(path.to_owned())
+///     // ...
+/// }
+///
+/// let report = read_config("").unwrap_err();
+/// assert_eq!(report.to_string(), "no config path given");
+/// assert_eq!(report.extension_ref(), Some(&Retry(false)));
+///
+/// let report = read_config("config.json").unwrap_err();
+/// assert_eq!(report.to_string(), "config.json is not a toml file");
+/// assert_eq!(report.extension_ref(), Some(&Retry(false)));
+/// assert_eq!(report.extension_ref(), Some(&Path("config.json".to_owned())));
+/// ```
+#[macro_export]
+macro_rules! bail_ext {
+    (ext = [$($ext:expr),* $(,)?], $($args:tt)+) => {
+        return ::core::result::Result::Err($crate::report!(ext = [$($ext),*], $($args)+))
+    };
+    ($ext:expr, $($args:tt)+) => {
+        $crate::bail_ext!(ext = [$ext], $($args)+)
+    };
+}
+
+/// Return early with an error carrying one or more extensions if a condition is not satisfied.
+///
+/// Takes the extensions like [`bail_ext!`](crate::bail_ext) after the condition, and the message
+/// like [`ensure!`](crate::ensure).
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::prelude::*;
+///
+/// #[derive(Debug, PartialEq)]
+/// pub struct Forbidden;
+///
+/// #[derive(Debug, PartialEq)]
+/// pub struct User(u32);
+///
+/// fn check_user(user: u32) -> Result<()> {
+///     ensure_ext!(user < 10, Forbidden);
+///     ensure_ext!(user == 0, ext = [Forbidden, User(user)], "only user 0 is allowed");
+///     Ok(())
+/// }
+///
+/// assert!(check_user(0).is_ok());
+///
+/// let report = check_user(1).unwrap_err();
+/// assert_eq!(report.to_string(), "only user 0 is allowed");
+/// assert_eq!(report.extension_ref(), Some(&User(1)));
+///
+/// let report = check_user(10).unwrap_err();
+/// assert_eq!(report.to_string(), "Condition failed: `user < 10`");
+/// assert_eq!(report.extension_ref(), Some(&Forbidden));
+/// assert_eq!(report.extension_ref::<User>(), None);
+/// ```
+///
+/// The list of extensions is separated from the message by a comma:
+///
+/// ```compile_fail
+/// use extension_eyre::prelude::*;
+///
+/// fn check_user(user: u32) -> Result<()> {
+///     ensure_ext!(user == 0, ext = [1u8, 2u16] "only user 0 is allowed");
+///     Ok(())
+/// }
+/// ```
+#[macro_export]
+macro_rules! ensure_ext {
+    ($cond:expr, ext = [$($ext:expr),* $(,)?] $(,)?) => {
+        $crate::ensure_ext!(
+            $cond,
+            ext = [$($ext),*],
+            concat!("Condition failed: `", stringify!($cond), "`")
+        )
+    };
+    ($cond:expr, ext = [$($ext:expr),* $(,)?], $($args:tt)+) => {
+        if !$cond {
+            $crate::bail_ext!(ext = [$($ext),*], $($args)+);
+        }
+    };
+    ($cond:expr, $ext:expr $(,)?) => {
+        $crate::ensure_ext!($cond, ext = [$ext])
+    };
+    ($cond:expr, $ext:expr, $($args:tt)+) => {
+        $crate::ensure_ext!($cond, ext = [$ext], $($args)+)
+    };
+}
+
+/// Build an [`Extensions`](crate::extensions::Extensions) map holding the given values.
+///
+/// Each value is [inserted](crate::extensions::Extensions::insert) in order, so a later value
+/// replaces an earlier one of the same type.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::extensions;
+///
+/// pub struct Retry;
+/// pub struct Counter(usize);
+///
+/// let ext = extensions![Retry, Counter(0), "db", Counter(1)];
+///
+/// assert_eq!(ext.len(), 3);
+/// assert!(matches!(ext.get(), Some(Counter(1))));
+/// assert_eq!(ext.get::<&str>(), Some(&"db"));
+/// assert!(extensions![].is_empty());
+/// ```
+#[macro_export]
+macro_rules! extensions {
+    ($($value:expr),* $(,)?) => {{
+        #[allow(unused_mut)]
+        let mut extensions = $crate::extensions::Extensions::new();
+        $(extensions.insert($value);)*
+        extensions
+    }};
+}
@@ -0,0 +1,73 @@
+use std::process::{ExitCode, Termination};
+
+use crate::extensions::Extension;
+use crate::eyre::Report;
+
+/// The outcome of `main`, ending the process with the exit code of its report
+///
+/// # Details
+///
+/// Returning a `MainResult` from `main` works like returning `Result<(), Report>`, except that on
+/// error the process exits with the report's [`exit_code`](Extension::exit_code) instead of 1:
+/// the attached [`ExitCode`](crate::builtin::ExitCode), else 2 for a
+/// [`Fatal`](crate::builtin::Severity) error. The report is printed to stderr like `main` prints
+/// errors, through the installed handler. `Ok(())` exits successfully without printing anything.
+///
+/// Converting a result installs the default hooks unless a hook was installed before, so errors
+/// converted into reports on the way are rendered by this crate even if
+/// [`install`](crate::install) wasn't called.
+///
+/// # Examples
+///
+/// ```rust
+/// use extension_eyre::builtin::{ExitCode, Severity};
+/// use extension_eyre::{eyre::eyre, ExtensionExt, MainResult};
+/// use std::process::Termination;
+///
+/// fn app() -> Result<(), std::io::Error> {
+///     std::fs::read_to_string("fake_file").map(drop)
+/// }
+///
+/// // usually `fn main() -> MainResult { app().into() }`
+/// let result = MainResult::from(app());
+/// assert!(extension_eyre::is_installed());
+/// assert_eq!(result.report(), std::process::ExitCode::FAILURE);
+///
+/// let result = MainResult::from(Err(eyre!("invalid arguments").extension(ExitCode(64))));
+/// assert_eq!(result.report(), std::process::ExitCode::from(64));
+///
+/// let result = MainResult::from(Err(eyre!("disk full").extension(Severity::Fatal)));
+/// assert_eq!(result.report(), std::process::ExitCode::from(2));
+///
+/// let result = MainResult::from(Ok::<(), std::io::Error>(()));
+/// assert_eq!(result.report(), std::process::ExitCode::SUCCESS);
+///
+/// // the hooks were installed once, by the first conversion
+/// assert!(extension_eyre::install().is_err());
+/// ```
+#[must_use = "return it from `main` to end the process with its exit code"]
+#[derive(Debug)]
+pub struct MainResult(pub Result<(), Report>);
+
+impl<E> From<Result<(), E>> for MainResult
+where
+    E: Into<Report>,
+{
+    #[track_caller]
+    fn from(result: Result<(), E>) -> Self {
+        crate::__private::auto_install();
+        MainResult(result.map_err(Into::into))
+    }
+}
+
+impl Termination for MainResult {
+    fn report(self) -> ExitCode {
+        match self.0 {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(report) => {
+                eprintln!("Error: {:?}", report);
+                ExitCode::from(report.exit_code())
+            }
+        }
+    }
+}
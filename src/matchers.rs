@@ -0,0 +1,354 @@
+//! [`googletest`] matchers for asserting on error reports
+//!
+//! The matchers in this module implement [`googletest::matcher::Matcher`] for
+//! `&Report` and `&Result<T, Report>`, so they compose with the rest of the
+//! `googletest` ecosystem (`verify_that!`, `expect_that!`, `err(..)`, `all!`, ...).
+//!
+//! ### Example
+//!
+//! ```rust
+//! use extension_eyre::builtin::ErrorCode;
+//! use extension_eyre::{eyre::eyre, ExtensionExt, eyre::Report};
+//! use extension_eyre::matchers::{chain_contains, error_code, extension_eq, has_extension};
+//! use googletest::prelude::*;
+//!
+//! #[derive(Debug, PartialEq)]
+//! pub struct Retry(bool);
+//!
+//! extension_eyre::install().unwrap();
+//!
+//! let report: Report = eyre!("connection reset")
+//!     .extension(Retry(true))
+//!     .extension(ErrorCode("E1042"));
+//!
+//! verify_that!(&report, has_extension::<Retry>()).unwrap();
+//! verify_that!(&report, error_code("E1042")).unwrap();
+//! verify_that!(&report, extension_eq(Retry(true))).unwrap();
+//! verify_that!(&report, chain_contains("reset")).unwrap();
+//!
+//! let result: Result<(), Report> = Err(report);
+//! verify_that!(&result, has_extension::<Retry>()).unwrap();
+//! ```
+//!
+//! [`googletest`]: https://docs.rs/googletest
+
+use std::any::type_name;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+use googletest::description::Description;
+use googletest::matcher::{Matcher, MatcherBase, MatcherResult};
+
+use crate::chain::Chain;
+use crate::eyre::Report;
+use crate::extensions::Extension;
+
+/// Matches a report that carries an extension of type `T`.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::{eyre::eyre, ExtensionExt, eyre::Report};
+/// use extension_eyre::matchers::has_extension;
+/// use googletest::prelude::*;
+///
+/// pub struct Retry;
+/// pub struct Counter(usize);
+///
+/// extension_eyre::install().unwrap();
+///
+/// let report: Report = eyre!("cmd exited with non-zero status code").extension(Retry);
+/// verify_that!(&report, has_extension::<Retry>()).unwrap();
+///
+/// let failure = verify_that!(&report, has_extension::<Counter>()).unwrap_err();
+/// assert!(failure.description.contains("Expected: has an extension of type"));
+/// assert!(failure.description.contains("::Counter`"));
+/// assert!(failure.description.contains("which has 1 extension attached: Extensions {"));
+/// assert!(failure.description.contains("::Retry\""));
+/// ```
+pub fn has_extension<T: Send + Sync + 'static>() -> HasExtensionMatcher<T> {
+    HasExtensionMatcher {
+        _marker: PhantomData,
+    }
+}
+
+/// Matches a report that carries an extension equal to `expected`.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::{eyre::eyre, ExtensionExt, eyre::Report};
+/// use extension_eyre::matchers::extension_eq;
+/// use googletest::prelude::*;
+///
+/// #[derive(Debug, PartialEq)]
+/// pub struct Counter(usize);
+///
+/// extension_eyre::install().unwrap();
+///
+/// let report: Report = eyre!("cmd exited with non-zero status code").extension(Counter(3));
+/// verify_that!(&report, extension_eq(Counter(3))).unwrap();
+///
+/// let failure = verify_that!(&report, extension_eq(Counter(4))).unwrap_err();
+/// assert!(failure.description.contains("Expected: has extension equal to Counter(4)"));
+/// assert!(failure.description.contains("which has extension Counter(3)"));
+/// ```
+pub fn extension_eq<T: PartialEq + Debug + Send + Sync + 'static>(
+    expected: T,
+) -> ExtensionEqMatcher<T> {
+    ExtensionEqMatcher { expected }
+}
+
+/// Matches a report where any error in its chain displays a message containing `substr`.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::{eyre::eyre, eyre::WrapErr, eyre::Report};
+/// use extension_eyre::matchers::chain_contains;
+/// use googletest::prelude::*;
+///
+/// extension_eyre::install().unwrap();
+///
+/// let result: Result<(), Report> =
+///     Err(eyre!("cmd exited with non-zero status code")).wrap_err("Unable to read config");
+///
+/// verify_that!(&result, chain_contains("non-zero status")).unwrap();
+///
+/// let failure = verify_that!(&result, chain_contains("timed out")).unwrap_err();
+/// assert!(failure
+///     .description
+///     .contains("Expected: is an error which has an error in its chain containing \"timed out\""));
+/// assert!(failure
+///     .description
+///     .contains("which has chain [\"Unable to read config\", \"cmd exited with non-zero status code\"]"));
+/// ```
+pub fn chain_contains<S: Into<String>>(substr: S) -> ChainContainsMatcher {
+    ChainContainsMatcher {
+        substr: substr.into(),
+    }
+}
+
+/// Matches a report that carries the [`ErrorCode`](crate::builtin::ErrorCode) `code`.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::builtin::ErrorCode;
+/// use extension_eyre::{eyre::eyre, ExtensionExt, eyre::Report};
+/// use extension_eyre::matchers::error_code;
+/// use googletest::prelude::*;
+///
+/// extension_eyre::install().unwrap();
+///
+/// let report: Report = eyre!("no row with id 7").extension(ErrorCode("E1042"));
+/// verify_that!(&report, error_code("E1042")).unwrap();
+///
+/// let failure = verify_that!(&report, error_code("E1043")).unwrap_err();
+/// assert!(failure.description.contains("Expected: has error code \"E1043\""));
+/// assert!(failure.description.contains("which has error code \"E1042\""));
+///
+/// let failure = verify_that!(&eyre!("connection reset"), error_code("E1042")).unwrap_err();
+/// assert!(failure.description.contains("which has no error code"));
+/// ```
+pub fn error_code<S: Into<String>>(code: S) -> ErrorCodeMatcher {
+    ErrorCodeMatcher { code: code.into() }
+}
+
+/// Matcher returned by [`has_extension`].
+pub struct HasExtensionMatcher<T> {
+    _marker: PhantomData<fn() -> T>,
+}
+
+/// Matcher returned by [`extension_eq`].
+pub struct ExtensionEqMatcher<T> {
+    expected: T,
+}
+
+/// Matcher returned by [`chain_contains`].
+pub struct ChainContainsMatcher {
+    substr: String,
+}
+
+/// Matcher returned by [`error_code`].
+pub struct ErrorCodeMatcher {
+    code: String,
+}
+
+impl<T> MatcherBase for HasExtensionMatcher<T> {}
+impl<T> MatcherBase for ExtensionEqMatcher<T> {}
+impl MatcherBase for ChainContainsMatcher {}
+impl MatcherBase for ErrorCodeMatcher {}
+
+/// Internal counterpart of [`Matcher`] implemented against a `Report`.
+///
+/// The public `Matcher` impls for `&Report` and `&Result<_, Report>` are generated from this.
+trait ReportMatcher {
+    fn matches_report(&self, report: &Report) -> bool;
+
+    fn describe_report(&self, sense: MatcherResult) -> Description;
+
+    fn explain_report(&self, report: &Report) -> Description;
+}
+
+impl<T: Send + Sync + 'static> ReportMatcher for HasExtensionMatcher<T> {
+    fn matches_report(&self, report: &Report) -> bool {
+        report.extension_ref::<T>().is_some()
+    }
+
+    fn describe_report(&self, sense: MatcherResult) -> Description {
+        match sense {
+            MatcherResult::Match => format!("has an extension of type `{}`", type_name::<T>()),
+            MatcherResult::NoMatch => {
+                format!("doesn't have an extension of type `{}`", type_name::<T>())
+            }
+        }
+        .into()
+    }
+
+    fn explain_report(&self, report: &Report) -> Description {
+        explain_extensions(report)
+    }
+}
+
+impl<T: PartialEq + Debug + Send + Sync + 'static> ReportMatcher for ExtensionEqMatcher<T> {
+    fn matches_report(&self, report: &Report) -> bool {
+        report.extension_ref::<T>() == Some(&self.expected)
+    }
+
+    fn describe_report(&self, sense: MatcherResult) -> Description {
+        match sense {
+            MatcherResult::Match => format!("has extension equal to {:?}", self.expected),
+            MatcherResult::NoMatch => format!("doesn't have extension equal to {:?}", self.expected),
+        }
+        .into()
+    }
+
+    fn explain_report(&self, report: &Report) -> Description {
+        match report.extension_ref::<T>() {
+            Some(actual) => format!("which has extension {:?}", actual).into(),
+            None => explain_extensions(report),
+        }
+    }
+}
+
+impl ReportMatcher for ChainContainsMatcher {
+    fn matches_report(&self, report: &Report) -> bool {
+        Chain::of_report(report).any(|error| error.to_string().contains(&self.substr))
+    }
+
+    fn describe_report(&self, sense: MatcherResult) -> Description {
+        match sense {
+            MatcherResult::Match => {
+                format!("has an error in its chain containing {:?}", self.substr)
+            }
+            MatcherResult::NoMatch => {
+                format!("has no error in its chain containing {:?}", self.substr)
+            }
+        }
+        .into()
+    }
+
+    fn explain_report(&self, report: &Report) -> Description {
+        let chain = Chain::of_report(report)
+            .map(|error| error.to_string())
+            .collect::<Vec<_>>();
+
+        format!("which has chain {:?}", chain).into()
+    }
+}
+
+impl ReportMatcher for ErrorCodeMatcher {
+    fn matches_report(&self, report: &Report) -> bool {
+        report.error_code() == Some(self.code.as_str())
+    }
+
+    fn describe_report(&self, sense: MatcherResult) -> Description {
+        match sense {
+            MatcherResult::Match => format!("has error code {:?}", self.code),
+            MatcherResult::NoMatch => format!("doesn't have error code {:?}", self.code),
+        }
+        .into()
+    }
+
+    fn explain_report(&self, report: &Report) -> Description {
+        match report.error_code() {
+            Some(code) => format!("which has error code {:?}", code).into(),
+            None if report.extensions_ref().is_none() => explain_extensions(report),
+            None => "which has no error code".into(),
+        }
+    }
+}
+
+/// Explains which extensions a report carries, for mismatch output.
+fn explain_extensions(report: &Report) -> Description {
+    match report.extensions_ref() {
+        None => "which does not use extension-eyre's handler; was `install()` called?".into(),
+        Some(extensions) if extensions.is_empty() => "which has no extensions attached".into(),
+        Some(extensions) => format!(
+            "which has {} extension{} attached: {:?}",
+            extensions.len(),
+            if extensions.len() == 1 { "" } else { "s" },
+            extensions
+        )
+        .into(),
+    }
+}
+
+macro_rules! impl_matcher {
+    ($name:ident $(<$param:ident>)? $(where $($bounds:tt)+)?) => {
+        impl<'a, $($param,)?> Matcher<&'a Report> for $name $(<$param>)?
+        $(where $($bounds)+)?
+        {
+            fn matches(&self, actual: &'a Report) -> MatcherResult {
+                self.matches_report(actual).into()
+            }
+
+            fn describe(&self, sense: MatcherResult) -> Description {
+                self.describe_report(sense)
+            }
+
+            fn explain_match(&self, actual: &'a Report) -> Description {
+                self.explain_report(actual)
+            }
+        }
+
+        impl<'a, V: Debug, $($param,)?> Matcher<&'a Result<V, Report>> for $name $(<$param>)?
+        $(where $($bounds)+)?
+        {
+            fn matches(&self, actual: &'a Result<V, Report>) -> MatcherResult {
+                match actual {
+                    Ok(_) => MatcherResult::NoMatch,
+                    Err(report) => self.matches_report(report).into(),
+                }
+            }
+
+            fn describe(&self, sense: MatcherResult) -> Description {
+                match sense {
+                    MatcherResult::Match => {
+                        format!("is an error which {}", self.describe_report(sense)).into()
+                    }
+                    MatcherResult::NoMatch => format!(
+                        "is a success or is an error which {}",
+                        self.describe_report(sense)
+                    )
+                    .into(),
+                }
+            }
+
+            fn explain_match(&self, actual: &'a Result<V, Report>) -> Description {
+                match actual {
+                    Ok(_) => "which is a success".into(),
+                    Err(report) => Description::new()
+                        .text("which is an error")
+                        .nested(self.explain_report(report)),
+                }
+            }
+        }
+    };
+}
+
+impl_matcher!(HasExtensionMatcher<T> where T: Send + Sync + 'static);
+impl_matcher!(ExtensionEqMatcher<T> where T: PartialEq + Debug + Send + Sync + 'static);
+impl_matcher!(ChainContainsMatcher);
+impl_matcher!(ErrorCodeMatcher);
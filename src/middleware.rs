@@ -0,0 +1,232 @@
+//! Seeding the extensions of reports from the requests a [`tower`](https://docs.rs/tower)
+//! service handles
+//!
+//! [`ExtensionSeedLayer`] computes extensions from each request and runs the inner service's
+//! future in a [`scope_async`](crate::scope_async) holding them, so every report created while
+//! the request is handled carries them. The layer can also copy values of chosen types from the
+//! request's [`http::Extensions`], see [`ExtensionSeedLayer::copy_http_extension`].
+//!
+//! ### Example
+//!
+//! ```rust
+//! use extension_eyre::middleware::ExtensionSeedLayer;
+//! use extension_eyre::{eyre::eyre, extensions::Extensions, Extension, Report};
+//! use http::{Request, Response};
+//! use tower::{service_fn, ServiceBuilder, ServiceExt};
+//!
+//! #[derive(Debug, Clone, PartialEq)]
+//! pub struct RequestId(String);
+//!
+//! #[derive(Debug, Clone, PartialEq)]
+//! pub struct Route(&'static str);
+//!
+//! extension_eyre::install().unwrap();
+//!
+//! let seed = ExtensionSeedLayer::new(|request: &Request<String>| {
+//!     let mut extensions = Extensions::new();
+//!     if let Some(id) = request.headers().get("x-request-id") {
+//!         let id = id.to_str().unwrap_or_default().to_owned();
+//!         extensions.insert_cloneable(RequestId(id));
+//!     }
+//!     extensions
+//! })
+//! .copy_http_extension::<Route>();
+//!
+//! let service = ServiceBuilder::new()
+//!     .layer(seed)
+//!     .service(service_fn(|_: Request<String>| async {
+//!         Err::<Response<String>, Report>(eyre!("database unavailable"))
+//!     }));
+//!
+//! let mut request = Request::get("/users/7")
+//!     .header("x-request-id", "req-42")
+//!     .body(String::new())
+//!     .unwrap();
+//! request.extensions_mut().insert(Route("/users/:id"));
+//!
+//! let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+//! let report = runtime.block_on(service.oneshot(request)).unwrap_err();
+//!
+//! assert_eq!(report.extension_ref(), Some(&RequestId("req-42".to_owned())));
+//! assert_eq!(report.extension_ref(), Some(&Route("/users/:id")));
+//! ```
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use http::Request;
+use pin_project_lite::pin_project;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::extensions::Extensions;
+use crate::scope::{scoped, Scoped};
+
+/// Copies the value of type `T` in `from`, if any, into `to` as a cloneable extension.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::extensions::Extensions;
+///
+/// #[derive(Debug, Clone, PartialEq)]
+/// pub struct Tenant(u32);
+///
+/// let mut request = http::Extensions::new();
+/// request.insert(Tenant(3));
+///
+/// let mut extensions = Extensions::new();
+/// extension_eyre::middleware::copy_http_extension::<Tenant>(&request, &mut extensions);
+/// assert_eq!(extensions.get(), Some(&Tenant(3)));
+/// ```
+pub fn copy_http_extension<T>(from: &http::Extensions, to: &mut Extensions)
+where
+    T: Clone + Send + Sync + 'static,
+{
+    if let Some(value) = from.get::<T>() {
+        to.insert_cloneable(value.clone());
+    }
+}
+
+type Copier = fn(&http::Extensions, &mut Extensions);
+
+/// A [`Layer`] attaching extensions computed from each request to the reports created while
+/// handling it.
+///
+/// # Details
+///
+/// `seed` is called with every request before it is passed to the inner service, and the
+/// extensions it returns, along with the values copied from the request's `http::Extensions`,
+/// are in scope while the inner service's future runs. Like for [`scope_async`], only the values
+/// inserted with [`Extensions::insert_cloneable`] are copied into reports.
+///
+/// See the [module docs](self) for an example.
+///
+/// [`scope_async`]: crate::scope_async
+pub struct ExtensionSeedLayer<F> {
+    seed: Arc<F>,
+    copiers: Arc<Vec<Copier>>,
+}
+
+impl<F> ExtensionSeedLayer<F> {
+    /// Creates a layer attaching the extensions returned by `seed` for each request.
+    pub fn new(seed: F) -> Self {
+        ExtensionSeedLayer {
+            seed: Arc::new(seed),
+            copiers: Arc::new(Vec::new()),
+        }
+    }
+
+    /// Also attaches the value of type `T` in each request's `http::Extensions`, if any.
+    ///
+    /// See [`copy_http_extension`].
+    pub fn copy_http_extension<T>(mut self) -> Self
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        Arc::make_mut(&mut self.copiers).push(copy_http_extension::<T>);
+        self
+    }
+}
+
+impl<F> Clone for ExtensionSeedLayer<F> {
+    fn clone(&self) -> Self {
+        ExtensionSeedLayer {
+            seed: self.seed.clone(),
+            copiers: self.copiers.clone(),
+        }
+    }
+}
+
+impl<F> fmt::Debug for ExtensionSeedLayer<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtensionSeedLayer")
+            .field("copied_types", &self.copiers.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, F> Layer<S> for ExtensionSeedLayer<F> {
+    type Service = ExtensionSeed<S, F>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ExtensionSeed {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// The [`Service`] created by [`ExtensionSeedLayer`].
+pub struct ExtensionSeed<S, F> {
+    inner: S,
+    layer: ExtensionSeedLayer<F>,
+}
+
+impl<S: Clone, F> Clone for ExtensionSeed<S, F> {
+    fn clone(&self) -> Self {
+        ExtensionSeed {
+            inner: self.inner.clone(),
+            layer: self.layer.clone(),
+        }
+    }
+}
+
+impl<S: fmt::Debug, F> fmt::Debug for ExtensionSeed<S, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtensionSeed")
+            .field("inner", &self.inner)
+            .field("layer", &self.layer)
+            .finish()
+    }
+}
+
+impl<S, F, B> Service<Request<B>> for ExtensionSeed<S, F>
+where
+    S: Service<Request<B>>,
+    F: Fn(&Request<B>) -> Extensions,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let mut extensions = (self.layer.seed)(&request);
+        for copy in self.layer.copiers.iter() {
+            copy(request.extensions(), &mut extensions);
+        }
+
+        ResponseFuture {
+            future: scoped(extensions, self.inner.call(request)),
+        }
+    }
+}
+
+pin_project! {
+    /// Future returned by [`ExtensionSeed`].
+    #[must_use = "futures do nothing unless polled"]
+    pub struct ResponseFuture<Fut: Future> {
+        #[pin]
+        future: Scoped<Fut>,
+    }
+}
+
+impl<Fut: Future> Future for ResponseFuture<Fut> {
+    type Output = Fut::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.project().future.poll(cx)
+    }
+}
+
+impl<Fut: Future> fmt::Debug for ResponseFuture<Fut> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseFuture").finish_non_exhaustive()
+    }
+}
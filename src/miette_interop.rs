@@ -0,0 +1,226 @@
+//! Converting reports to and from [`miette::Report`]s, see [`to_miette`] and [`from_miette`]
+use std::any::type_name;
+use std::error::Error;
+use std::fmt;
+
+use miette::Diagnostic;
+
+use crate::builtin::{ErrorCode, Severity, Suggestion};
+use crate::extensions::ExtensionExt;
+use crate::eyre::Report;
+use crate::section::Section;
+
+/// Converts a report into a [`miette::Report`], keeping its source chain.
+///
+/// # Details
+///
+/// The diagnostic shows the report's error, and the errors below it as its causes. Besides:
+///
+/// - its [`help`](Diagnostic::help) holds the report's [`Suggestion`]s, one per line
+/// - its [`code`](Diagnostic::code) is the report's [`ErrorCode`], with a [`url`](Diagnostic::url)
+///   if [`HookBuilder::error_code_base_url`](crate::config::HookBuilder::error_code_base_url) is
+///   set
+/// - its [`severity`](Diagnostic::severity) follows the report's [`Severity`]
+/// - the other extensions shown in the report's `Extensions:` section become
+///   [`related`](Diagnostic::related) advices, like `alloc::string::String: GET /prices`
+///
+/// Redacted extensions are left out.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::builtin::ErrorCode;
+/// use extension_eyre::{eyre::eyre, Extension, ExtensionExt, Section, WrapErr};
+/// use miette::NarratableReportHandler;
+///
+/// extension_eyre::install().unwrap();
+///
+/// let mut report = Err::<(), _>(eyre!("connection reset"))
+///     .wrap_err("could not fetch the price list")
+///     .extension(ErrorCode("E42"))
+///     .suggestion("check the network")
+///     .unwrap_err();
+/// report
+///     .extensions_mut()
+///     .unwrap()
+///     .insert_display(String::from("GET /prices"));
+///
+/// let diagnostic = extension_eyre::to_miette(report);
+/// let mut rendered = String::new();
+/// NarratableReportHandler::new()
+///     .render_report(&mut rendered, diagnostic.as_ref())
+///     .unwrap();
+///
+/// let lines: Vec<_> = rendered.lines().map(str::trim).collect();
+/// assert_eq!(lines[0], "could not fetch the price list");
+/// assert!(lines.contains(&"Caused by: connection reset"));
+/// assert!(lines.contains(&"diagnostic help: check the network"));
+/// assert!(lines.contains(&"diagnostic code: E42"));
+/// assert!(lines.contains(&"Advice: alloc::string::String: GET /prices"));
+///
+/// let report = extension_eyre::from_miette(diagnostic);
+/// let chain: Vec<_> = report.chain().map(ToString::to_string).collect();
+/// assert_eq!(chain, ["could not fetch the price list", "connection reset"]);
+/// assert_eq!(report.meta_ref("code").unwrap().to_string(), "E42");
+/// ```
+pub fn to_miette(report: Report) -> miette::Report {
+    let mut diagnostic = ReportDiagnostic {
+        report,
+        code: None,
+        url: None,
+        help: None,
+        severity: None,
+        related: Vec::new(),
+    };
+
+    let handler = match diagnostic.report.handler().downcast_ref::<crate::Handler>() {
+        Some(handler) => handler,
+        None => return miette::Report::new(diagnostic),
+    };
+    let extensions = &handler.extensions;
+
+    if let Some(code) = extensions.get::<ErrorCode>() {
+        diagnostic.code = Some(code.0);
+        if let Some(url) = &handler.config.error_code_base_url {
+            let separator = if url.ends_with('/') { "" } else { "/" };
+            diagnostic.url = Some(format!("{}{}{}", url, separator, code));
+        }
+    }
+
+    let suggestions: Vec<_> = extensions.get_all::<Suggestion>().map(|s| &*s.0).collect();
+    if !suggestions.is_empty() {
+        diagnostic.help = Some(suggestions.join("\n"));
+    }
+
+    diagnostic.severity = extensions.get::<Severity>().map(|severity| match severity {
+        Severity::Warning => miette::Severity::Warning,
+        Severity::Error | Severity::Fatal => miette::Severity::Error,
+    });
+
+    diagnostic.related = extensions
+        .displayed(&handler.config.formatters)
+        .into_iter()
+        .filter(|&(name, _)| name != type_name::<ErrorCode>() && name != type_name::<Suggestion>())
+        .map(|(type_name, value)| ExtensionDiagnostic { type_name, value })
+        .collect();
+
+    miette::Report::new(diagnostic)
+}
+
+/// Converts a [`miette::Report`] into a report, keeping its source chain.
+///
+/// The report is created like any other, so the installed hook attaches its defaults and runs
+/// its extension hooks. The diagnostic's [`help`](Diagnostic::help) is attached as a
+/// [`Suggestion`], and its [`code`](Diagnostic::code) as the `code`
+/// [metadata](ExtensionExt::meta).
+///
+/// See [`to_miette`] for an example.
+#[track_caller]
+pub fn from_miette(diagnostic: miette::Report) -> Report {
+    let help = diagnostic.help().map(|help| help.to_string());
+    let code = diagnostic.code().map(|code| code.to_string());
+
+    let mut report = Report::new(MietteError(diagnostic));
+    if let Some(help) = help {
+        report = report.suggestion(help);
+    }
+    if let Some(code) = code {
+        report = report.meta("code", code);
+    }
+
+    report
+}
+
+#[derive(Debug)]
+struct ReportDiagnostic {
+    report: Report,
+    code: Option<&'static str>,
+    url: Option<String>,
+    help: Option<String>,
+    severity: Option<miette::Severity>,
+    related: Vec<ExtensionDiagnostic>,
+}
+
+impl fmt::Display for ReportDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the error's own message, the handler would render the whole chain with `{:#}`
+        fmt::Display::fmt(&*self.report, f)
+    }
+}
+
+impl Error for ReportDiagnostic {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.report.source()
+    }
+}
+
+impl Diagnostic for ReportDiagnostic {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.code.map(|code| Box::new(code) as _)
+    }
+
+    fn severity(&self) -> Option<miette::Severity> {
+        self.severity
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.help.as_ref().map(|help| Box::new(help) as _)
+    }
+
+    fn url<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.url.as_ref().map(|url| Box::new(url) as _)
+    }
+
+    fn related<'a>(&'a self) -> Option<Box<dyn Iterator<Item = &'a dyn Diagnostic> + 'a>> {
+        if self.related.is_empty() {
+            return None;
+        }
+
+        Some(Box::new(
+            self.related
+                .iter()
+                .map(|related| related as &dyn Diagnostic),
+        ))
+    }
+}
+
+#[derive(Debug)]
+struct ExtensionDiagnostic {
+    type_name: &'static str,
+    value: String,
+}
+
+impl fmt::Display for ExtensionDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.type_name, self.value)
+    }
+}
+
+impl Error for ExtensionDiagnostic {}
+
+impl Diagnostic for ExtensionDiagnostic {
+    fn severity(&self) -> Option<miette::Severity> {
+        Some(miette::Severity::Advice)
+    }
+}
+
+// Stands for the outermost error of a `miette::Report`, handing out the ones below it as sources.
+struct MietteError(miette::Report);
+
+impl fmt::Debug for MietteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for MietteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&*self.0, f)
+    }
+}
+
+impl Error for MietteError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.0.source()
+    }
+}
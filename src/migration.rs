@@ -0,0 +1,112 @@
+//! Finding code that still creates reports without this crate's handler
+//!
+//! Reports created while another handler is installed, e.g. by a stray `color_eyre::install()`,
+//! silently ignore every extension attached to them. With
+//! [`HookBuilder::report_foreign_handlers`](crate::config::HookBuilder::report_foreign_handlers)
+//! enabled, each [`Extension`](crate::Extension) or [`ExtensionExt`](crate::ExtensionExt) call on
+//! such a report is recorded as a [`Sighting`], once per call site.
+//!
+//! ### Example
+//!
+//! ```rust
+//! use extension_eyre::{eyre, migration, Extension, ExtensionExt};
+//!
+//! pub struct Retry;
+//!
+//! // some other crate installed its own handler first
+//! struct Foreign;
+//!
+//! impl eyre::EyreHandler for Foreign {
+//!     fn debug(
+//!         &self,
+//!         error: &(dyn std::error::Error + 'static),
+//!         f: &mut std::fmt::Formatter<'_>,
+//!     ) -> std::fmt::Result {
+//!         write!(f, "{}", error)
+//!     }
+//! }
+//!
+//! eyre::set_hook(Box::new(|_| Box::new(Foreign))).unwrap();
+//!
+//! let installed = extension_eyre::config::HookBuilder::default()
+//!     .report_foreign_handlers(true)
+//!     .install();
+//! assert!(installed.is_err());
+//!
+//! let mut lines = Vec::new();
+//! for attempt in 0..3 {
+//!     let report = eyre::eyre!("attempt {} failed", attempt);
+//!     lines.push(line!() + 1);
+//!     let report = report.extension(Retry);
+//!     assert!(report.extension_ref::<Retry>().is_none());
+//! }
+//!
+//! let sightings = migration::foreign_handler_sightings();
+//! assert_eq!(sightings.len(), 2);
+//!
+//! // recorded once, for the first report that reached the call site
+//! assert_eq!(sightings[0].message, "attempt 0 failed");
+//! assert_eq!(sightings[0].location.file(), file!());
+//! assert_eq!(sightings[0].location.line(), lines[0]);
+//! assert_eq!(sightings[1].location.line(), lines[0] + 1);
+//! ```
+use std::panic::Location;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::eyre::Report;
+
+/// How many sightings are kept; later ones are discarded.
+const MAX_SIGHTINGS: usize = 256;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static SIGHTINGS: Mutex<Vec<Sighting>> = Mutex::new(Vec::new());
+
+/// A call site that used extensions on a report created by another handler.
+#[derive(Debug, Clone)]
+pub struct Sighting {
+    /// The message of the first report seen at this call site.
+    pub message: String,
+    /// Where the extension method was called.
+    pub location: &'static Location<'static>,
+}
+
+/// Returns the call sites recorded so far, in the order they were first seen.
+///
+/// Only the first 256 call sites are kept. Always empty unless
+/// [`HookBuilder::report_foreign_handlers`](crate::config::HookBuilder::report_foreign_handlers)
+/// was enabled.
+pub fn foreign_handler_sightings() -> Vec<Sighting> {
+    lock().clone()
+}
+
+pub(crate) fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Records `location` as having used extensions on `report`, a report from another handler.
+pub(crate) fn record(report: &Report, location: &'static Location<'static>) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let mut sightings = lock();
+    let seen = sightings.iter().any(|sighting| {
+        sighting.location.file() == location.file()
+            && sighting.location.line() == location.line()
+            && sighting.location.column() == location.column()
+    });
+
+    if !seen && sightings.len() < MAX_SIGHTINGS {
+        sightings.push(Sighting {
+            message: report.to_string(),
+            location,
+        });
+    }
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Vec<Sighting>> {
+    SIGHTINGS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
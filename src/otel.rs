@@ -0,0 +1,136 @@
+//! Recording reports on [OpenTelemetry](https://opentelemetry.io) spans through
+//! [`opentelemetry`]
+//!
+//! [`record_report`] marks a span as failed with a report:
+//!
+//! - every error of the chain is recorded as an `exception` event, outermost first
+//! - the span's status is set to an error described by the report's message
+//! - extensions shown in the report's `Extensions:` section and serializable extensions become
+//!   span attributes named by their type after a prefix, [`DEFAULT_PREFIX`] unless given to
+//!   [`record_report_with_prefix`]
+//!
+//! Serialized numbers, strings and booleans are recorded as such, other values as JSON.
+//! Redacted extensions are left out.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use extension_eyre::{eyre::eyre, Extension, ExtensionExt, WrapErr};
+//! use extension_eyre::builtin::ErrorCode;
+//! use opentelemetry::trace::{Status, TraceContextExt, Tracer, TracerProvider};
+//! use opentelemetry::{KeyValue, Value};
+//! use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider};
+//!
+//! struct Password(&'static str);
+//!
+//! impl std::fmt::Display for Password {
+//!     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+//!         f.write_str(self.0)
+//!     }
+//! }
+//!
+//! extension_eyre::config::HookBuilder::default()
+//!     .redaction_policy(|_, type_name| type_name.ends_with("::Password"))
+//!     .install()
+//!     .unwrap();
+//!
+//! let mut report = Err::<(), _>(eyre!("connection reset"))
+//!     .wrap_err("could not fetch the price list")
+//!     .extension(ErrorCode("E42"))
+//!     .unwrap_err();
+//! let extensions = report.extensions_mut().unwrap();
+//! extensions.insert_display(String::from("GET /prices"));
+//! extensions.insert_serializable(3u32);
+//! extensions.insert_display(Password("hunter2"));
+//!
+//! let exporter = InMemorySpanExporter::default();
+//! let provider = SdkTracerProvider::builder()
+//!     .with_simple_exporter(exporter.clone())
+//!     .build();
+//! provider.tracer("prices").in_span("fetch", |cx| {
+//!     extension_eyre::otel::record_report(&cx.span(), &report);
+//! });
+//!
+//! let span = &exporter.get_finished_spans().unwrap()[0];
+//! assert_eq!(span.status, Status::error("could not fetch the price list"));
+//!
+//! let messages: Vec<_> = span.events.iter().map(|event| &event.attributes[0]).collect();
+//! assert_eq!(
+//!     messages,
+//!     [
+//!         &KeyValue::new("exception.message", "could not fetch the price list"),
+//!         &KeyValue::new("exception.message", "connection reset"),
+//!     ]
+//! );
+//!
+//! let attribute = |key: &str| {
+//!     let attribute = span.attributes.iter().find(|attribute| attribute.key.as_str() == key);
+//!     attribute.map(|attribute| attribute.value.clone())
+//! };
+//! assert_eq!(attribute("error.ext.alloc::string::String"), Some(Value::from("GET /prices")));
+//! assert_eq!(attribute("error.ext.u32"), Some(Value::I64(3)));
+//! assert_eq!(attribute("error.ext.rust_out::Password"), None);
+//! assert_eq!(span.attributes.len(), 2);
+//! ```
+use std::collections::BTreeMap;
+
+use opentelemetry::trace::{SpanRef, Status};
+use opentelemetry::{KeyValue, Value};
+
+use crate::chain::Chain;
+use crate::eyre::Report;
+
+/// The prefix of the attributes [`record_report`] records extensions as
+pub const DEFAULT_PREFIX: &str = "error.ext.";
+
+/// Records `report` on `span`, naming extension attributes after [`DEFAULT_PREFIX`]
+///
+/// See the [module docs](self) for what is recorded.
+pub fn record_report(span: &SpanRef<'_>, report: &Report) {
+    record_report_with_prefix(span, report, DEFAULT_PREFIX)
+}
+
+/// Records `report` on `span`, naming extension attributes after `prefix`
+///
+/// See the [module docs](self) for what is recorded.
+pub fn record_report_with_prefix(span: &SpanRef<'_>, report: &Report, prefix: &str) {
+    for error in Chain::of_report(report) {
+        span.record_error(error);
+    }
+    span.set_status(Status::error(report.to_string()));
+
+    let handler = match report.handler().downcast_ref::<crate::Handler>() {
+        Some(handler) => handler,
+        None => return,
+    };
+    let extensions = &handler.extensions;
+
+    // a type both displayed and serializable is recorded once, with its serialized value
+    let mut attributes = BTreeMap::new();
+    for (type_name, value) in extensions.displayed(&handler.config.formatters) {
+        attributes.insert(type_name.to_owned(), Value::from(value));
+    }
+    if let serde_json::Value::Object(serialized) = extensions.to_json_value() {
+        for (key, value) in serialized {
+            attributes.insert(key, attribute_value(value));
+        }
+    }
+
+    span.set_attributes(
+        attributes
+            .into_iter()
+            .map(|(key, value)| KeyValue::new(format!("{}{}", prefix, key), value)),
+    );
+}
+
+fn attribute_value(value: serde_json::Value) -> Value {
+    match value {
+        serde_json::Value::Bool(value) => Value::Bool(value),
+        serde_json::Value::Number(number) => match number.as_i64() {
+            Some(value) => Value::I64(value),
+            None => Value::F64(number.as_f64().unwrap_or(f64::NAN)),
+        },
+        serde_json::Value::String(value) => Value::from(value),
+        value => Value::from(value.to_string()),
+    }
+}
@@ -0,0 +1,138 @@
+//! Extensions for panics, kept per thread
+//!
+//! Panics don't go through a `Report`, so there is no handler to attach values to. Values set
+//! with [`set_panic_extension`] live in a thread-local map instead. When a panic fires, the
+//! installed [`PanicHook`](crate::config::PanicHook) moves the map of the panicking thread into
+//! the [`PanicReport`](crate::config::PanicReport), and moves it back once the report is done.
+//! The report renders the values in a section of its own, see
+//! [`HookBuilder::panic_extensions_section`](crate::config::HookBuilder::panic_extensions_section).
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::extensions::Extensions;
+
+thread_local! {
+    static EXTENSIONS: RefCell<Extensions> = RefCell::new(Extensions::new());
+    // The map of the panic being reported on this thread, while the panic hook runs.
+    static REPORTING: RefCell<Option<Rc<Extensions>>> = const { RefCell::new(None) };
+}
+
+/// Attaches `value` to any panic on the current thread, replacing the value of the same type
+/// attached before.
+///
+/// # Examples
+///
+/// ```rust
+/// use extension_eyre::config::{HookBuilder, PanicReport};
+/// use std::sync::mpsc::channel;
+/// use std::sync::Mutex;
+///
+/// #[derive(Debug, Clone, Copy, PartialEq)]
+/// pub struct RequestId(u64);
+///
+/// let (tx, rx) = channel();
+/// let tx = Mutex::new(tx);
+///
+/// let (panic_hook, _) = HookBuilder::default()
+///     .panic_sink(move |report: &PanicReport<'_>| {
+///         let request = report.extensions().get::<RequestId>().copied();
+///         tx.lock().unwrap().send(request).unwrap();
+///     })
+///     .into_hooks();
+/// panic_hook.install();
+///
+/// // at the start of handling a request
+/// extension_eyre::set_panic_extension(RequestId(7));
+///
+/// let _ = std::panic::catch_unwind(|| {
+///     // deep in third-party code
+///     panic!("index out of bounds");
+/// });
+///
+/// assert_eq!(rx.recv().unwrap(), Some(RequestId(7)));
+///
+/// // the values stay attached for the next panic
+/// extension_eyre::with_panic_extensions(|extensions| {
+///     assert_eq!(extensions.get::<RequestId>(), Some(&RequestId(7)));
+/// });
+/// ```
+pub fn set_panic_extension<T: Send + Sync + 'static>(value: T) {
+    with_panic_extensions(|extensions| {
+        extensions.insert(value);
+    });
+}
+
+/// Calls `f` with the map of values attached to panics on the current thread.
+///
+/// # Examples
+///
+/// ```rust
+/// pub struct RequestId(u64);
+/// pub struct Tenant(&'static str);
+///
+/// extension_eyre::with_panic_extensions(|extensions| {
+///     extensions.insert(RequestId(7));
+///     extensions.insert(Tenant("acme"));
+/// });
+///
+/// // done with the request
+/// extension_eyre::with_panic_extensions(|extensions| extensions.clear());
+/// ```
+pub fn with_panic_extensions<R, F: FnOnce(&mut Extensions) -> R>(f: F) -> R {
+    EXTENSIONS.with(|extensions| f(&mut extensions.borrow_mut()))
+}
+
+/// Moves the current thread's map out for the report of a panic.
+pub(crate) fn snapshot() -> Rc<Extensions> {
+    // the panic may have fired while the map was borrowed
+    let extensions = EXTENSIONS
+        .try_with(|extensions| {
+            extensions
+                .try_borrow_mut()
+                .map(|mut extensions| std::mem::take(&mut *extensions))
+                .unwrap_or_default()
+        })
+        .unwrap_or_default();
+
+    let snapshot = Rc::new(extensions);
+    let _ = REPORTING.try_with(|reporting| *reporting.borrow_mut() = Some(snapshot.clone()));
+    snapshot
+}
+
+/// Moves a map taken by [`snapshot`] back, keeping values attached while the panic was reported.
+pub(crate) fn restore(snapshot: Rc<Extensions>) {
+    let _ = REPORTING.try_with(|reporting| reporting.borrow_mut().take());
+
+    if let Ok(mut restored) = Rc::try_unwrap(snapshot) {
+        let _ = EXTENSIONS.try_with(|extensions| {
+            if let Ok(mut extensions) = extensions.try_borrow_mut() {
+                restored.extend(std::mem::take(&mut *extensions));
+                *extensions = restored;
+            }
+        });
+    }
+}
+
+/// Calls `f` with the map of the panic being reported on this thread, or the map of values
+/// attached to panics on this thread if none is.
+pub(crate) fn with_current<R, F: FnOnce(&Extensions) -> R>(f: F) -> R {
+    let reporting = REPORTING
+        .try_with(|reporting| reporting.borrow().clone())
+        .ok()
+        .flatten();
+
+    if let Some(reporting) = reporting {
+        return f(&reporting);
+    }
+
+    let mut f = Some(f);
+    let attached = EXTENSIONS.try_with(|extensions| match extensions.try_borrow() {
+        Ok(extensions) => Some((f.take().expect("called once"))(&extensions)),
+        Err(_) => None,
+    });
+
+    match attached {
+        Ok(Some(result)) => result,
+        _ => (f.take().expect("called once"))(&Extensions::new()),
+    }
+}
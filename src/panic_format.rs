@@ -0,0 +1,83 @@
+//! The JSON format of panic reports, see [`HookBuilder::panic_format`]
+//!
+//! The backtrace of a panic is captured and filtered by color-eyre while it renders the human
+//! format. To list the same frames, the JSON format renders the human one first, with a last
+//! frame filter recording the frames every other filter kept.
+//!
+//! [`HookBuilder::panic_format`]: crate::config::HookBuilder::panic_format
+use std::cell::RefCell;
+use std::fmt;
+
+#[cfg(feature = "color-eyre")]
+use color_eyre::config::Frame;
+use serde_json::{json, Map, Value};
+
+use crate::config::PanicReport;
+
+thread_local! {
+    static FRAMES: RefCell<Option<Vec<Value>>> = const { RefCell::new(None) };
+}
+
+/// How the panic hook renders panic reports.
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PanicFormat {
+    /// The colored, multi-line report of color-eyre.
+    #[default]
+    Human,
+    /// A single line JSON object.
+    Json,
+}
+
+/// Frame filter recording the frames left by the filters before it, while a JSON report is
+/// rendered on this thread.
+#[cfg(feature = "color-eyre")]
+pub(crate) fn record_frames(frames: &mut Vec<&Frame>) {
+    let _ = FRAMES.try_with(|recorded| {
+        if let Some(recorded) = recorded.borrow_mut().as_mut() {
+            recorded.extend(frames.iter().map(|frame| {
+                json!({
+                    "n": frame.n,
+                    "name": frame.name,
+                    "file": frame.filename.as_ref().map(|file| file.display().to_string()),
+                    "line": frame.lineno,
+                })
+            }));
+        }
+    });
+}
+
+/// Renders `report` as a JSON object on a single line.
+pub(crate) fn write_json(
+    report: &PanicReport<'_>,
+    human: &dyn fmt::Display,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    let _ = FRAMES.try_with(|recorded| *recorded.borrow_mut() = Some(Vec::new()));
+    let _ = human.to_string();
+    let frames = FRAMES
+        .try_with(|recorded| recorded.borrow_mut().take())
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+
+    let mut object = Map::new();
+    object.insert("message".to_owned(), json!(report.payload_str()));
+
+    if let Some(location) = report.location() {
+        object.insert(
+            "location".to_owned(),
+            json!({
+                "file": location.file(),
+                "line": location.line(),
+                "column": location.column(),
+            }),
+        );
+    }
+
+    object.insert("thread".to_owned(), json!(std::thread::current().name()));
+    object.insert("backtrace".to_owned(), Value::Array(frames));
+    object.insert("extensions".to_owned(), report.extensions().to_json_value());
+
+    write!(f, "{}", Value::Object(object))
+}
@@ -0,0 +1,162 @@
+//! Stand-ins for the hooks of color-eyre, used when its feature is disabled
+//!
+//! They mirror the parts of color-eyre's `config` API the hooks of this crate build on: reports
+//! are rendered by [`eyre::DefaultHandler`], and panics by a plain version of color-eyre's panic
+//! message.
+//!
+//! ### Example
+//!
+//! Extensions make the same round trips through reports as with color-eyre:
+//!
+//! ```rust
+//! # #[cfg(not(feature = "color-eyre"))] {
+//! use extension_eyre::builtin::ErrorCode;
+//! use extension_eyre::{eyre::eyre, eyre::Report, Extension, ExtensionExt, WrapErr};
+//!
+//! #[derive(Debug, PartialEq)]
+//! pub struct Retry(bool);
+//! #[derive(Debug, PartialEq)]
+//! pub struct Attempt(u32);
+//!
+//! fn read_config() -> Result<(), Report> {
+//!     Err(eyre!("disk full"))
+//!         .extension(ErrorCode("E42"))
+//!         .extension(Retry(true))
+//!         .frame_extension(Attempt(1))
+//! }
+//!
+//! extension_eyre::install().unwrap();
+//!
+//! let mut report = read_config().wrap_err("could not load the config").unwrap_err();
+//! assert_eq!(report.extension_ref(), Some(&ErrorCode("E42")));
+//! assert_eq!(report.extension_ref(), Some(&Retry(true)));
+//! assert_eq!(report.extension_ref(), Some(&Attempt(1)));
+//!
+//! report.extension_mut::<Retry>().unwrap().0 = false;
+//! assert_eq!(report.take_extension(), Some(Retry(false)));
+//! assert_eq!(report.extension_ref::<Retry>(), None);
+//!
+//! let extensions = report.take_extensions();
+//! assert_eq!(extensions.get(), Some(&ErrorCode("E42")));
+//! assert!(report.extensions_ref().unwrap().is_empty());
+//!
+//! let report = eyre!("retry failed").extensions(extensions);
+//! assert_eq!(report.extension_ref(), Some(&ErrorCode("E42")));
+//! # }
+//! ```
+use std::fmt;
+use std::panic::PanicHookInfo;
+
+use crate::config::InnerHookCallback;
+
+/// Stand-in for color-eyre's builder, which leaves nothing to configure.
+///
+/// The settings of [`HookBuilder`](crate::config::HookBuilder) that attach extensions keep
+/// working on top of it.
+///
+/// ### Example
+///
+/// ```rust
+/// # #[cfg(not(feature = "color-eyre"))] {
+/// use extension_eyre::{eyre::Report, Extension};
+/// use std::io;
+///
+/// #[derive(Debug, Clone, PartialEq)]
+/// pub struct Service(&'static str);
+/// #[derive(Debug, PartialEq)]
+/// pub struct Retry(bool);
+///
+/// extension_eyre::config::HookBuilder::default()
+///     .default_extension(Service("billing"))
+///     .map_error_extension(|error: &io::Error| Retry(error.kind() == io::ErrorKind::TimedOut))
+///     .install()
+///     .unwrap();
+///
+/// let report = Report::new(io::Error::new(io::ErrorKind::TimedOut, "read timed out"));
+/// assert_eq!(report.extension_ref(), Some(&Service("billing")));
+/// assert_eq!(report.extension_ref(), Some(&Retry(true)));
+/// # }
+/// ```
+pub(crate) struct HookBuilder;
+
+impl HookBuilder {
+    pub(crate) fn new() -> Self {
+        HookBuilder
+    }
+
+    pub(crate) fn blank() -> Self {
+        HookBuilder
+    }
+
+    pub(crate) fn try_into_hooks(self) -> Result<(PanicHook, EyreHook), crate::eyre::Report> {
+        Ok((PanicHook, EyreHook))
+    }
+}
+
+/// Renders reports with [`eyre::DefaultHandler`], followed by the sections of this crate.
+///
+/// ### Example
+///
+/// ```rust
+/// # #[cfg(not(feature = "color-eyre"))] {
+/// use extension_eyre::{eyre::eyre, ExtensionExt, WrapErr};
+///
+/// pub struct Tenant(&'static str);
+///
+/// extension_eyre::config::HookBuilder::default()
+///     .extension_formatter(|tenant: &Tenant, f| f.write_str(tenant.0))
+///     .install()
+///     .unwrap();
+///
+/// let report = Err::<(), _>(eyre!("disk full"))
+///     .extension(Tenant("acme"))
+///     .meta("path", "/etc/app.toml")
+///     .wrap_err("could not load the config")
+///     .unwrap_err();
+///
+/// let rendered = format!("{:?}", report);
+/// assert!(rendered.starts_with("could not load the config"));
+/// assert!(rendered.contains("disk full"));
+/// assert!(rendered.contains(&format!("{}: acme", std::any::type_name::<Tenant>())));
+/// assert!(rendered.contains("Metadata:\n   path : /etc/app.toml"));
+/// assert!(!rendered.contains('\u{1b}'));
+/// # }
+/// ```
+pub(crate) struct EyreHook;
+
+impl EyreHook {
+    pub(crate) fn into_eyre_hook(self) -> Box<InnerHookCallback> {
+        Box::new(eyre::DefaultHandler::default_with)
+    }
+}
+
+pub(crate) struct PanicHook;
+
+impl PanicHook {
+    pub(crate) fn panic_report<'a>(&'a self, panic_info: &'a PanicHookInfo<'_>) -> PanicReport<'a> {
+        PanicReport { panic_info }
+    }
+}
+
+pub(crate) struct PanicReport<'a> {
+    panic_info: &'a PanicHookInfo<'a>,
+}
+
+impl fmt::Display for PanicReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "The application panicked (crashed).")?;
+
+        let payload = self.panic_info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("<non string panic payload>");
+        writeln!(f, "Message:  {}", message)?;
+
+        match self.panic_info.location() {
+            Some(location) => write!(f, "Location: {}:{}", location.file(), location.line()),
+            None => write!(f, "Location: <unknown>"),
+        }
+    }
+}
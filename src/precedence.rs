@@ -0,0 +1,194 @@
+use std::any::{type_name, TypeId};
+use std::collections::HashMap;
+
+/// Where an extension attached to a report came from.
+///
+/// When two sources set the same extension type, the installed [`Precedence`] decides which
+/// value the report keeps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Source {
+    /// Attached by the caller, e.g. through [`ExtensionExt::extension`](crate::ExtensionExt::extension).
+    Explicit,
+    /// Attached by a scope active while the report was created.
+    Scope,
+    /// Attached by a hook classifying reports as they are created.
+    Classifier,
+    /// Attached to every report by the installed hook.
+    Default,
+}
+
+const DEFAULT_ORDER: [Source; 4] = [
+    Source::Explicit,
+    Source::Scope,
+    Source::Classifier,
+    Source::Default,
+];
+
+/// Decides which [`Source`] wins when two of them set the same extension type.
+///
+/// # Details
+///
+/// An order lists sources from strongest to weakest. The default order is
+///
+/// | rank | source                  |
+/// |------|-------------------------|
+/// | 1    | [`Source::Explicit`]    |
+/// | 2    | [`Source::Scope`]       |
+/// | 3    | [`Source::Classifier`]  |
+/// | 4    | [`Source::Default`]     |
+///
+/// Sources left out of a configured order rank below the listed ones, in default order. A value
+/// from a source replaces an existing value from the same or a weaker source and is discarded
+/// when the existing value comes from a stronger one. Orders can be set for all types and
+/// overridden for individual types, see
+/// [`HookBuilder::precedence`](crate::config::HookBuilder::precedence) and
+/// [`HookBuilder::precedence_for`](crate::config::HookBuilder::precedence_for).
+///
+/// # Examples
+///
+/// Every pairwise conflict, under the default order and a reversed one with a per-type
+/// override:
+///
+/// ```rust
+/// use extension_eyre::config::{Precedence, Source::*};
+///
+/// pub struct Retry;
+/// pub struct Severity;
+///
+/// // (existing, incoming, winner)
+/// let default_order = [
+///     (Explicit, Scope, Explicit),
+///     (Explicit, Classifier, Explicit),
+///     (Explicit, Default, Explicit),
+///     (Scope, Explicit, Explicit),
+///     (Scope, Classifier, Scope),
+///     (Scope, Default, Scope),
+///     (Classifier, Explicit, Explicit),
+///     (Classifier, Scope, Scope),
+///     (Classifier, Default, Classifier),
+///     (Default, Explicit, Explicit),
+///     (Default, Scope, Scope),
+///     (Default, Classifier, Classifier),
+/// ];
+///
+/// let reversed = [
+///     (Explicit, Scope, Scope),
+///     (Explicit, Classifier, Classifier),
+///     (Explicit, Default, Default),
+///     (Scope, Explicit, Scope),
+///     (Scope, Classifier, Classifier),
+///     (Scope, Default, Default),
+///     (Classifier, Explicit, Classifier),
+///     (Classifier, Scope, Classifier),
+///     (Classifier, Default, Default),
+///     (Default, Explicit, Default),
+///     (Default, Scope, Default),
+///     (Default, Classifier, Default),
+/// ];
+///
+/// // classifiers decide the severity, everything else keeps the default order
+/// let severity_first = [
+///     (Explicit, Classifier, Classifier),
+///     (Classifier, Explicit, Classifier),
+///     (Scope, Default, Scope),
+///     (Default, Explicit, Explicit),
+/// ];
+///
+/// let precedence = Precedence::default();
+/// for &(existing, incoming, winner) in &default_order {
+///     let wins = precedence.prefers::<Retry>(incoming, existing);
+///     assert_eq!(wins, winner == incoming, "{:?} over {:?}", incoming, existing);
+/// }
+///
+/// let precedence = Precedence::new([Default, Classifier, Scope, Explicit]);
+/// for &(existing, incoming, winner) in &reversed {
+///     let wins = precedence.prefers::<Retry>(incoming, existing);
+///     assert_eq!(wins, winner == incoming, "{:?} over {:?}", incoming, existing);
+/// }
+///
+/// let precedence = Precedence::default().with_type::<Severity, _>([Classifier]);
+/// for &(existing, incoming, winner) in &severity_first {
+///     let wins = precedence.prefers::<Severity>(incoming, existing);
+///     assert_eq!(wins, winner == incoming, "{:?} over {:?}", incoming, existing);
+/// }
+/// assert_eq!(precedence.order_for::<Severity>(), [Classifier, Explicit, Scope, Default]);
+/// assert_eq!(precedence.order_for::<Retry>(), [Explicit, Scope, Classifier, Default]);
+/// ```
+#[derive(Clone)]
+pub struct Precedence {
+    order: Vec<Source>,
+    per_type: HashMap<TypeId, (&'static str, Vec<Source>)>,
+}
+
+impl Precedence {
+    /// Creates a precedence using `order` for every type.
+    pub fn new<I: IntoIterator<Item = Source>>(order: I) -> Self {
+        Precedence {
+            order: complete(order),
+            per_type: HashMap::new(),
+        }
+    }
+
+    /// Uses `order` for extensions of type `T` instead of the order shared by all types.
+    pub fn with_type<T: 'static, I: IntoIterator<Item = Source>>(mut self, order: I) -> Self {
+        self.per_type
+            .insert(TypeId::of::<T>(), (type_name::<T>(), complete(order)));
+        self
+    }
+
+    /// Sets the order shared by all types without a per-type order.
+    pub(crate) fn set_order<I: IntoIterator<Item = Source>>(&mut self, order: I) {
+        self.order = complete(order);
+    }
+
+    /// The sources, strongest first, that arbitrate extensions of type `T`.
+    pub fn order_for<T: 'static>(&self) -> &[Source] {
+        self.order_for_id(TypeId::of::<T>())
+    }
+
+    /// Returns whether a value of type `T` from `incoming` replaces one from `existing`.
+    pub fn prefers<T: 'static>(&self, incoming: Source, existing: Source) -> bool {
+        self.prefers_id(TypeId::of::<T>(), incoming, existing)
+    }
+
+    pub(crate) fn prefers_id(&self, type_id: TypeId, incoming: Source, existing: Source) -> bool {
+        let order = self.order_for_id(type_id);
+        let rank = |source| order.iter().position(|&s| s == source);
+
+        rank(incoming) <= rank(existing)
+    }
+
+    fn order_for_id(&self, type_id: TypeId) -> &[Source] {
+        self.per_type
+            .get(&type_id)
+            .map_or(&self.order, |(_, order)| order)
+    }
+}
+
+impl Default for Precedence {
+    fn default() -> Self {
+        Precedence::new(DEFAULT_ORDER)
+    }
+}
+
+impl std::fmt::Debug for Precedence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Precedence")
+            .field("order", &self.order)
+            .field("per_type", &self.per_type.values().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Removes duplicates from `order` and appends the sources it leaves out, in default order.
+fn complete<I: IntoIterator<Item = Source>>(order: I) -> Vec<Source> {
+    let mut complete = Vec::with_capacity(DEFAULT_ORDER.len());
+
+    for source in order.into_iter().chain(DEFAULT_ORDER) {
+        if !complete.contains(&source) {
+            complete.push(source);
+        }
+    }
+
+    complete
+}
@@ -0,0 +1,79 @@
+use std::cell::Cell;
+
+use crate::eyre::Report;
+
+thread_local! {
+    static QUIET: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Marker extension attached to reports that skipped the installed hook's observers.
+///
+/// Reports created inside [`quietly`] carry this marker, as do reports that called
+/// [`ExtensionExt::bypass_observers`](crate::ExtensionExt::bypass_observers).
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::{eyre::eyre, Extension, ObserversBypassed};
+///
+/// extension_eyre::install().unwrap();
+///
+/// let report = extension_eyre::quietly(|| eyre!("audit log tampered with"));
+/// assert!(report.extension_ref::<ObserversBypassed>().is_some());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObserversBypassed;
+
+/// Creates a report without running the installed hook's observers.
+///
+/// # Details
+///
+/// Extension hooks, error mappers, observers, metrics and recent errors registered on the
+/// [`HookBuilder`](crate::config::HookBuilder) are skipped for every report created while `f`
+/// runs on the current thread. Such reports are tagged with [`ObserversBypassed`], which the
+/// [reporter](crate::reporter) and the built-in [sinks](crate::sink) check to skip them too. The previous state is restored when `f` returns or unwinds.
+///
+/// # Examples
+///
+/// ```rust
+/// use extension_eyre::{eyre::eyre, Extension, ObserversBypassed};
+///
+/// extension_eyre::install().unwrap();
+///
+/// let sensitive = extension_eyre::quietly(|| eyre!("audit log tampered with"));
+/// let normal = eyre!("cmd exited with non-zero status code");
+///
+/// assert!(sensitive.extension_ref::<ObserversBypassed>().is_some());
+/// assert!(normal.extension_ref::<ObserversBypassed>().is_none());
+///
+/// // the flag is reset even if `f` panics
+/// let _ = std::panic::catch_unwind(|| extension_eyre::quietly(|| panic!("oops")));
+/// assert!(eyre!("after").extension_ref::<ObserversBypassed>().is_none());
+/// ```
+pub fn quietly<F: FnOnce() -> Report>(f: F) -> Report {
+    let _guard = QuietGuard::enter();
+    f()
+}
+
+/// Returns whether reports created right now on this thread should skip observers.
+pub(crate) fn is_active() -> bool {
+    QUIET.with(Cell::get)
+}
+
+struct QuietGuard {
+    previous: bool,
+}
+
+impl QuietGuard {
+    fn enter() -> Self {
+        QuietGuard {
+            previous: QUIET.with(|quiet| quiet.replace(true)),
+        }
+    }
+}
+
+impl Drop for QuietGuard {
+    fn drop(&mut self) {
+        QUIET.with(|quiet| quiet.set(self.previous));
+    }
+}
@@ -0,0 +1,108 @@
+//! The recently created reports kept for [`HookBuilder::record_recent_errors`]
+//!
+//! [`HookBuilder::record_recent_errors`]: crate::config::HookBuilder::record_recent_errors
+use std::collections::VecDeque;
+use std::error::Error;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::SystemTime;
+
+use crate::chain::Chain;
+use crate::extensions::Extensions;
+
+static RECENT: Mutex<VecDeque<RecentError>> = Mutex::new(VecDeque::new());
+
+/// A report created recently, see [`recent_errors`]
+///
+/// Only holds copies of what the report contained when it was created, so recording it doesn't
+/// keep anything the report owns alive.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct RecentError {
+    /// The messages of the report's error and its sources
+    pub chain: Vec<String>,
+    /// When the report was created
+    pub timestamp: SystemTime,
+    /// The type names of the extensions the report was created with
+    pub extensions: Vec<&'static str>,
+    /// The serializable extensions the report was created with, see
+    /// [`Extensions::to_json_value`]
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub serialized: serde_json::Value,
+}
+
+/// Returns the reports recorded by the installed hook, oldest first
+///
+/// # Details
+///
+/// Reports are only recorded once enabled with
+/// [`HookBuilder::record_recent_errors`](crate::config::HookBuilder::record_recent_errors),
+/// which also sets how many are kept. Reports created [`quietly`](crate::quietly) aren't
+/// recorded.
+///
+/// # Examples
+///
+/// ```rust
+/// use extension_eyre::{eyre::eyre, WrapErr};
+///
+/// extension_eyre::config::HookBuilder::default()
+///     .extension_hook(|_, extensions| {
+///         #[cfg(feature = "serde")]
+///         extensions.insert_serializable(7u32);
+///         #[cfg(not(feature = "serde"))]
+///         extensions.insert(7u32);
+///     })
+///     .record_recent_errors(3)
+///     .install()
+///     .unwrap();
+///
+/// for n in 0..5 {
+///     let _ = Err::<(), _>(std::fmt::Error).wrap_err(format!("job {} failed", n));
+/// }
+///
+/// let recent = extension_eyre::recent_errors();
+/// let chains: Vec<_> = recent.iter().map(|error| error.chain.clone()).collect();
+/// assert_eq!(
+///     chains,
+///     [
+///         ["job 2 failed", "an error occurred when formatting an argument"],
+///         ["job 3 failed", "an error occurred when formatting an argument"],
+///         ["job 4 failed", "an error occurred when formatting an argument"],
+///     ]
+/// );
+/// assert!(recent[0].timestamp <= recent[2].timestamp);
+/// assert_eq!(recent[2].extensions, ["u32"]);
+/// #[cfg(feature = "serde")]
+/// assert_eq!(recent[2].serialized, serde_json::json!({ "u32": 7 }));
+/// ```
+pub fn recent_errors() -> Vec<RecentError> {
+    lock().iter().cloned().collect()
+}
+
+/// Records a report created from `error` with `extensions`, keeping the last `capacity`.
+pub(crate) fn record(
+    capacity: usize,
+    error: &(dyn Error + 'static),
+    extensions: &Extensions,
+    max_depth: usize,
+) {
+    let recent = RecentError {
+        chain: Chain::new(error, max_depth)
+            .map(ToString::to_string)
+            .collect(),
+        timestamp: SystemTime::now(),
+        extensions: extensions.type_names().collect(),
+        #[cfg(feature = "serde")]
+        serialized: extensions.to_json_value(),
+    };
+
+    let mut errors = lock();
+    while errors.len() >= capacity {
+        errors.pop_front();
+    }
+    errors.push_back(recent);
+}
+
+fn lock() -> MutexGuard<'static, VecDeque<RecentError>> {
+    RECENT.lock().unwrap_or_else(PoisonError::into_inner)
+}
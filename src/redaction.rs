@@ -0,0 +1,27 @@
+//! The redaction policy set with [`HookBuilder::redaction_policy`]
+//!
+//! [`HookBuilder::redaction_policy`]: crate::config::HookBuilder::redaction_policy
+use std::any::TypeId;
+use std::sync::{Arc, RwLock};
+
+use crate::config::RedactionPolicyCallback;
+
+static POLICY: RwLock<Option<Arc<RedactionPolicyCallback>>> = RwLock::new(None);
+
+/// Replaces the policy, applied to every `Extensions` from now on.
+pub(crate) fn set_policy(policy: Arc<RedactionPolicyCallback>) {
+    if let Ok(mut current) = POLICY.write() {
+        *current = Some(policy);
+    }
+}
+
+/// Whether the policy redacts extensions of the type `type_id`, named `type_name`.
+pub(crate) fn is_redacted(type_id: TypeId, type_name: &'static str) -> bool {
+    // cloned out so the policy can't deadlock by rendering extensions itself
+    let policy = match POLICY.read() {
+        Ok(policy) => policy.clone(),
+        Err(_) => None,
+    };
+
+    policy.is_some_and(|policy| policy(type_id, type_name))
+}
@@ -0,0 +1,486 @@
+//! Forwarding reports from many threads to a single reporter thread
+//!
+//! [`Reporter::spawn`] starts a thread that owns a set of [`Sink`]s, renders reports one at a
+//! time and hands them to the sinks through [`Sink::report_rendered`], so reports coming from
+//! concurrent workers come out whole instead of interleaved. Workers send reports through a
+//! cheap to clone [`ReporterHandle`].
+//!
+//! ### Example
+//!
+//! ```rust
+//! use extension_eyre::reporter::{OverflowPolicy, Reporter, ReporterConfig};
+//! use extension_eyre::{eyre::eyre, eyre::Report, sink::Sink, ExtensionExt};
+//! use std::fmt::Write;
+//! use std::sync::{Arc, Mutex};
+//!
+//! pub struct Worker(usize);
+//!
+//! // writes each report line by line, so concurrent deliveries would interleave
+//! #[derive(Clone, Default)]
+//! struct Capture(Arc<Mutex<String>>);
+//!
+//! impl Sink for Capture {
+//!     fn report(&self, report: &Report) {
+//!         self.report_rendered(report, &format!("{:?}", report));
+//!     }
+//!
+//!     fn report_rendered(&self, _: &Report, rendered: &str) {
+//!         writeln!(self.0.lock().unwrap(), "BEGIN").unwrap();
+//!         std::thread::yield_now();
+//!         writeln!(self.0.lock().unwrap(), "{}", rendered).unwrap();
+//!         std::thread::yield_now();
+//!         writeln!(self.0.lock().unwrap(), "END").unwrap();
+//!     }
+//! }
+//!
+//! extension_eyre::install().unwrap();
+//!
+//! let capture = Capture::default();
+//! let reporter = Reporter::spawn(
+//!     ReporterConfig::new()
+//!         .capacity(1)
+//!         .overflow(OverflowPolicy::RenderInline)
+//!         .sink(capture.clone()),
+//! );
+//!
+//! let workers = (0..8)
+//!     .map(|worker| {
+//!         let reporter = reporter.clone();
+//!         std::thread::spawn(move || {
+//!             for i in 0..10 {
+//!                 let report = eyre!("worker {} failed task {}", worker, i);
+//!                 reporter.report(report.extension(Worker(worker)));
+//!             }
+//!         })
+//!     })
+//!     .collect::<Vec<_>>();
+//!
+//! for worker in workers {
+//!     worker.join().unwrap();
+//! }
+//! drop(reporter);
+//!
+//! let output = capture.0.lock().unwrap().clone();
+//! let blocks = output.split_terminator("END\n").collect::<Vec<_>>();
+//!
+//! assert_eq!(blocks.len(), 80);
+//! for block in blocks {
+//!     let rendered = block.strip_prefix("BEGIN\n").unwrap();
+//!     assert!(!rendered.contains("BEGIN"));
+//!     assert!(rendered.contains("worker "));
+//! }
+//! ```
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::eyre::Report;
+use crate::sink::Sink;
+use crate::{Extension, ObserversBypassed};
+
+/// What [`ReporterHandle::report`] does when the reporter thread's queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Wait until the queue has room.
+    Block,
+    /// Discard the report and count it, see [`ReporterHandle::dropped`].
+    DropAndCount,
+    /// Hand the report to the sinks on the calling thread, still one report at a time.
+    RenderInline,
+}
+
+/// Configuration for [`Reporter::spawn`].
+pub struct ReporterConfig {
+    capacity: usize,
+    overflow: OverflowPolicy,
+    flush_timeout: Duration,
+    sinks: Vec<Box<dyn Sink>>,
+}
+
+impl ReporterConfig {
+    /// Creates a configuration without sinks, queueing up to 64 reports, blocking when the
+    /// queue is full and waiting up to a second for the queue to drain when the last handle is
+    /// dropped.
+    pub fn new() -> Self {
+        ReporterConfig {
+            capacity: 64,
+            overflow: OverflowPolicy::Block,
+            flush_timeout: Duration::from_secs(1),
+            sinks: Vec::new(),
+        }
+    }
+
+    /// Sets how many reports can wait for the reporter thread before the overflow policy
+    /// kicks in.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Sets what happens when the queue is full.
+    pub fn overflow(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow = policy;
+        self
+    }
+
+    /// Sets how long dropping the last handle waits for queued reports to be delivered.
+    pub fn flush_timeout(mut self, timeout: Duration) -> Self {
+        self.flush_timeout = timeout;
+        self
+    }
+
+    /// Adds a sink that receives every report, after the sinks added before it.
+    pub fn sink<S: Sink + 'static>(mut self, sink: S) -> Self {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+}
+
+impl Default for ReporterConfig {
+    fn default() -> Self {
+        ReporterConfig::new()
+    }
+}
+
+impl std::fmt::Debug for ReporterConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReporterConfig")
+            .field("capacity", &self.capacity)
+            .field("overflow", &self.overflow)
+            .field("flush_timeout", &self.flush_timeout)
+            .field("sinks", &self.sinks.len())
+            .finish()
+    }
+}
+
+/// Starts reporter threads.
+#[derive(Debug)]
+pub struct Reporter;
+
+impl Reporter {
+    /// Spawns a reporter thread delivering reports to the sinks in `config`.
+    ///
+    /// # Details
+    ///
+    /// The thread runs until every [`ReporterHandle`] has been dropped. A sink that panics
+    /// only loses the report it was handling, and a report that panics while being rendered is
+    /// lost; the thread keeps delivering the next ones.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::reporter::{OverflowPolicy, Reporter, ReporterConfig};
+    /// use extension_eyre::{eyre::eyre, eyre::Report, sink::Sink};
+    /// use std::sync::mpsc::{channel, Receiver, Sender};
+    /// use std::sync::Mutex;
+    ///
+    /// // holds the reporter thread inside the sink until released
+    /// struct Gate {
+    ///     started: Mutex<Sender<()>>,
+    ///     release: Mutex<Receiver<()>>,
+    /// }
+    ///
+    /// impl Sink for Gate {
+    ///     fn report(&self, _: &Report) {
+    ///         self.started.lock().unwrap().send(()).unwrap();
+    ///         self.release.lock().unwrap().recv().unwrap();
+    ///     }
+    /// }
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let (started_tx, started) = channel();
+    /// let (release, release_rx) = channel();
+    /// let gate = Gate { started: Mutex::new(started_tx), release: Mutex::new(release_rx) };
+    ///
+    /// let reporter = Reporter::spawn(
+    ///     ReporterConfig::new()
+    ///         .capacity(1)
+    ///         .overflow(OverflowPolicy::DropAndCount)
+    ///         .sink(gate),
+    /// );
+    ///
+    /// reporter.report(eyre!("first"));
+    /// started.recv().unwrap(); // the reporter thread is busy with "first"
+    ///
+    /// reporter.report(eyre!("second")); // fills the queue
+    /// reporter.report(eyre!("third")); // doesn't fit
+    /// assert_eq!(reporter.dropped(), 1);
+    /// assert_eq!(reporter.pending(), 2);
+    ///
+    /// release.send(()).unwrap();
+    /// release.send(()).unwrap();
+    /// assert!(reporter.flush(std::time::Duration::from_secs(5)));
+    /// ```
+    pub fn spawn(config: ReporterConfig) -> ReporterHandle {
+        let (sender, receiver) = mpsc::sync_channel::<Report>(config.capacity);
+        let pipeline = Arc::new(Pipeline {
+            sinks: config.sinks,
+            delivering: Mutex::new(()),
+            pending: Mutex::new(0),
+            drained: Condvar::new(),
+            handles: AtomicUsize::new(1),
+        });
+
+        let worker = pipeline.clone();
+        thread::Builder::new()
+            .name("extension-eyre-reporter".into())
+            .spawn(move || {
+                for report in receiver {
+                    worker.deliver(&report);
+                    worker.finish_one();
+                }
+            })
+            .expect("failed to spawn reporter thread");
+
+        ReporterHandle {
+            sender,
+            pipeline,
+            overflow: config.overflow,
+            flush_timeout: config.flush_timeout,
+            dropped: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+}
+
+/// Sends reports to a reporter thread started with [`Reporter::spawn`].
+///
+/// Cloning a handle is cheap; all clones feed the same thread. Dropping the last handle waits,
+/// up to the configured flush timeout, for the reports queued so far to be delivered.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::reporter::{Reporter, ReporterConfig};
+/// use extension_eyre::{eyre::eyre, eyre::Report, sink::Sink};
+/// use std::sync::mpsc::{channel, Receiver};
+/// use std::sync::{Arc, Mutex};
+/// use std::time::{Duration, Instant};
+///
+/// // holds the reporter thread until released, then records which thread it ran on
+/// struct Gate {
+///     release: Mutex<Receiver<()>>,
+///     threads: Arc<Mutex<Vec<String>>>,
+/// }
+///
+/// impl Sink for Gate {
+///     fn report(&self, _: &Report) {
+///         unreachable!("the reporter hands over rendered reports");
+///     }
+///
+///     fn report_rendered(&self, _: &Report, rendered: &str) {
+///         assert!(rendered.contains("disk full"));
+///         self.release.lock().unwrap().recv().unwrap();
+///
+///         let thread = std::thread::current().name().unwrap().to_owned();
+///         self.threads.lock().unwrap().push(thread);
+///     }
+/// }
+///
+/// extension_eyre::install().unwrap();
+///
+/// let (release, release_rx) = channel();
+/// let threads = Arc::new(Mutex::new(Vec::new()));
+/// let gate = Gate { release: Mutex::new(release_rx), threads: threads.clone() };
+/// let config = ReporterConfig::new().flush_timeout(Duration::from_secs(60));
+/// let reporter = Reporter::spawn(config.sink(gate));
+///
+/// let clone = reporter.clone();
+/// clone.report(eyre!("disk full"));
+///
+/// let start = Instant::now();
+/// drop(clone); // doesn't wait, `reporter` is still around
+/// assert!(start.elapsed() < Duration::from_secs(30));
+/// assert_eq!(reporter.pending(), 1);
+///
+/// release.send(()).unwrap();
+/// drop(reporter); // waits for "disk full" to be delivered
+/// assert_eq!(*threads.lock().unwrap(), ["extension-eyre-reporter"]);
+/// ```
+pub struct ReporterHandle {
+    sender: SyncSender<Report>,
+    pipeline: Arc<Pipeline>,
+    overflow: OverflowPolicy,
+    flush_timeout: Duration,
+    dropped: Arc<AtomicUsize>,
+}
+
+impl ReporterHandle {
+    /// Queues `report` for delivery, applying the overflow policy if the queue is full.
+    ///
+    /// If the reporter thread is gone, the report is delivered on the calling thread. Reports
+    /// carrying [`ObserversBypassed`] never reach the sinks.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use extension_eyre::reporter::{Reporter, ReporterConfig};
+    /// use extension_eyre::{eyre::eyre, eyre::Report, sink::Sink, ExtensionExt};
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// #[derive(Clone, Default)]
+    /// struct Collect(Arc<Mutex<Vec<String>>>);
+    ///
+    /// impl Sink for Collect {
+    ///     fn report(&self, report: &Report) {
+    ///         self.0.lock().unwrap().push(report.to_string());
+    ///     }
+    /// }
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let collect = Collect::default();
+    /// let reporter = Reporter::spawn(ReporterConfig::new().sink(collect.clone()));
+    ///
+    /// reporter.report(eyre!("cmd exited with non-zero status code"));
+    /// reporter.report(eyre!("audit log tampered with").bypass_observers());
+    /// reporter.report(extension_eyre::quietly(|| eyre!("signature mismatch")));
+    /// drop(reporter);
+    ///
+    /// assert_eq!(*collect.0.lock().unwrap(), ["cmd exited with non-zero status code"]);
+    /// ```
+    pub fn report(&self, report: Report) {
+        if report.extension_ref::<ObserversBypassed>().is_some() {
+            return;
+        }
+
+        *self.pipeline.pending() += 1;
+
+        let result = match self.overflow {
+            OverflowPolicy::Block => self
+                .sender
+                .send(report)
+                .map_err(|error| TrySendError::Disconnected(error.0)),
+            OverflowPolicy::DropAndCount | OverflowPolicy::RenderInline => {
+                self.sender.try_send(report)
+            }
+        };
+
+        match result {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) if self.overflow == OverflowPolicy::DropAndCount => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                self.pipeline.finish_one();
+            }
+            Err(TrySendError::Full(report)) | Err(TrySendError::Disconnected(report)) => {
+                self.pipeline.deliver(&report);
+                self.pipeline.finish_one();
+            }
+        }
+    }
+
+    /// Number of reports discarded by [`OverflowPolicy::DropAndCount`] across all clones of
+    /// this handle.
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Number of reports sent through any clone of this handle that haven't been delivered yet.
+    pub fn pending(&self) -> usize {
+        *self.pipeline.pending()
+    }
+
+    /// Waits up to `timeout` for every report sent so far to be delivered, and returns whether
+    /// they were.
+    pub fn flush(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut pending = self.pipeline.pending();
+
+        while *pending > 0 {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+
+            pending = self
+                .pipeline
+                .drained
+                .wait_timeout(pending, deadline - now)
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .0;
+        }
+
+        true
+    }
+}
+
+impl Clone for ReporterHandle {
+    fn clone(&self) -> Self {
+        self.pipeline.handles.fetch_add(1, Ordering::Relaxed);
+
+        ReporterHandle {
+            sender: self.sender.clone(),
+            pipeline: self.pipeline.clone(),
+            overflow: self.overflow,
+            flush_timeout: self.flush_timeout,
+            dropped: self.dropped.clone(),
+        }
+    }
+}
+
+impl Drop for ReporterHandle {
+    fn drop(&mut self) {
+        // other handles may still be sending, so only the last one waits for the queue
+        if self.pipeline.handles.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.flush(self.flush_timeout);
+        }
+    }
+}
+
+impl std::fmt::Debug for ReporterHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReporterHandle")
+            .field("overflow", &self.overflow)
+            .field("pending", &self.pending())
+            .field("dropped", &self.dropped())
+            .finish()
+    }
+}
+
+/// The sinks, shared between the reporter thread and handles delivering inline.
+struct Pipeline {
+    sinks: Vec<Box<dyn Sink>>,
+    // held while a report is handed to the sinks, so deliveries never overlap
+    delivering: Mutex<()>,
+    pending: Mutex<usize>,
+    drained: Condvar,
+    // live `ReporterHandle`s, the last one to be dropped flushes
+    handles: AtomicUsize,
+}
+
+impl Pipeline {
+    fn deliver(&self, report: &Report) {
+        let _delivering = self
+            .delivering
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let rendered = match panic::catch_unwind(AssertUnwindSafe(|| format!("{:?}", report))) {
+            Ok(rendered) => rendered,
+            Err(_) => return,
+        };
+
+        for sink in &self.sinks {
+            let deliver = || sink.report_rendered(report, &rendered);
+            let _ = panic::catch_unwind(AssertUnwindSafe(deliver));
+        }
+    }
+
+    fn pending(&self) -> MutexGuard<'_, usize> {
+        self.pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn finish_one(&self) {
+        let mut pending = self.pending();
+        *pending -= 1;
+
+        if *pending == 0 {
+            self.drained.notify_all();
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use crate::builtin::{Attempts, Retry, RetryPolicy};
+use crate::extensions::{Extension, ExtensionExt};
+use crate::eyre::Report;
+
+/// Calls `op` until it succeeds or the [`RetryPolicy`] of its error says to give up.
+///
+/// # Details
+///
+/// After each failure, the policy is read from the returned report: its [`RetryPolicy`], or the
+/// default policy if it carries [`Retry`]. Without either, the error is returned right away.
+/// Otherwise `op` is called again after the policy's backoff, until `max_attempts` attempts
+/// were made. The error returned carries the number of attempts made as [`Attempts`].
+///
+/// # Examples
+///
+/// ```rust
+/// use extension_eyre::builtin::{Attempts, RetryPolicy};
+/// use extension_eyre::{eyre::eyre, Extension, ExtensionExt};
+/// use std::time::Duration;
+///
+/// extension_eyre::install().unwrap();
+///
+/// let policy = RetryPolicy {
+///     max_attempts: 3,
+///     backoff: Duration::ZERO,
+/// };
+///
+/// // succeeds on the third attempt
+/// let mut calls = 0;
+/// let value = extension_eyre::retry(|| {
+///     calls += 1;
+///     if calls < 3 {
+///         Err(eyre!("connection reset")).extension(policy)
+///     } else {
+///         Ok(calls)
+///     }
+/// });
+/// assert_eq!(value.unwrap(), 3);
+///
+/// // gives up after `max_attempts`
+/// let mut calls = 0;
+/// let report = extension_eyre::retry(|| -> Result<(), _> {
+///     calls += 1;
+///     Err(eyre!("connection reset")).extension(policy)
+/// })
+/// .unwrap_err();
+/// assert_eq!(calls, 3);
+/// assert_eq!(report.extension_ref(), Some(&Attempts(3)));
+///
+/// // errors without a policy aren't retried
+/// let mut calls = 0;
+/// let report = extension_eyre::retry(|| -> Result<(), _> {
+///     calls += 1;
+///     Err(eyre!("invalid password"))
+/// })
+/// .unwrap_err();
+/// assert_eq!(calls, 1);
+/// assert_eq!(report.extension_ref(), Some(&Attempts(1)));
+/// ```
+pub fn retry<T, F>(mut op: F) -> Result<T, Report>
+where
+    F: FnMut() -> Result<T, Report>,
+{
+    let mut attempts = 1;
+
+    loop {
+        let report = match op() {
+            Ok(value) => return Ok(value),
+            Err(report) => report,
+        };
+
+        match next_attempt(&report, attempts) {
+            Some(policy) => std::thread::sleep(policy.backoff),
+            None => return Err(report.extension(Attempts(attempts))),
+        }
+
+        attempts += 1;
+    }
+}
+
+/// Awaits the futures returned by `op` until one succeeds or the [`RetryPolicy`] of its error
+/// says to give up.
+///
+/// Works like [`retry`], sleeping with [`tokio::time::sleep`] between attempts.
+///
+/// # Examples
+///
+/// ```rust
+/// use extension_eyre::builtin::{Attempts, Retry};
+/// use extension_eyre::{eyre::eyre, Extension, ExtensionExt};
+///
+/// extension_eyre::install().unwrap();
+///
+/// let runtime = tokio::runtime::Builder::new_current_thread()
+///     .enable_time()
+///     .build()
+///     .unwrap();
+///
+/// let mut calls = 0;
+/// let report = runtime
+///     .block_on(extension_eyre::retry_async(|| {
+///         calls += 1;
+///         async { Err::<(), _>(eyre!("connection reset")).extension(Retry) }
+///     }))
+///     .unwrap_err();
+///
+/// assert_eq!(calls, 3);
+/// assert_eq!(report.extension_ref(), Some(&Attempts(3)));
+/// ```
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+pub async fn retry_async<T, F, Fut>(mut op: F) -> Result<T, Report>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T, Report>>,
+{
+    let mut attempts = 1;
+
+    loop {
+        let report = match op().await {
+            Ok(value) => return Ok(value),
+            Err(report) => report,
+        };
+
+        match next_attempt(&report, attempts) {
+            Some(policy) => tokio::time::sleep(policy.backoff).await,
+            None => return Err(report.extension(Attempts(attempts))),
+        }
+
+        attempts += 1;
+    }
+}
+
+/// The policy to retry with after `attempts` attempts failed with `report`, if any.
+fn next_attempt(report: &Report, attempts: u32) -> Option<RetryPolicy> {
+    let policy = match report.extension_ref::<RetryPolicy>() {
+        Some(policy) => *policy,
+        None => {
+            report.extension_ref::<Retry>()?;
+            RetryPolicy::default()
+        }
+    };
+
+    Some(policy).filter(|policy| attempts < policy.max_attempts)
+}
@@ -0,0 +1,196 @@
+//! Extensions attached to every report created inside a scope
+//!
+//! [`scope`] pushes a map of values on a thread-local stack until the returned guard is dropped,
+//! and [`scope_async`] does the same for the duration of a future, in a task-local stack. The
+//! eyre hook copies the values of every active scope into each new report, as attached by
+//! [`Source::Scope`](crate::config::Source::Scope).
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use crate::config::{Precedence, Source};
+use crate::extensions::Extensions;
+
+thread_local! {
+    static SCOPES: RefCell<Vec<Arc<Extensions>>> = const { RefCell::new(Vec::new()) };
+}
+
+#[cfg(feature = "tokio")]
+tokio::task_local! {
+    static TASK_SCOPES: Vec<Arc<Extensions>>;
+}
+
+/// Attaches a copy of `extensions` to every report created on the current thread until the
+/// returned guard is dropped.
+///
+/// # Details
+///
+/// Only the values inserted with
+/// [`Extensions::insert_cloneable`](crate::extensions::Extensions::insert_cloneable) are copied
+/// into reports; other values are skipped. Scopes nest: when an inner and an outer scope hold a
+/// value of the same type, reports get the inner one. Dropping the guard, including while
+/// unwinding, restores the scopes that were active when it was created.
+///
+/// The stack of scopes is per thread, so a guard shouldn't be held across an `.await`; use
+/// [`scope_async`] in async code.
+///
+/// # Examples
+///
+/// ```rust
+/// use extension_eyre::{eyre::eyre, extensions::Extensions, Extension};
+///
+/// #[derive(Debug, Clone, PartialEq)]
+/// pub struct RequestId(u64);
+///
+/// #[derive(Debug, Clone, PartialEq)]
+/// pub struct Route(&'static str);
+///
+/// extension_eyre::install().unwrap();
+///
+/// let mut request = Extensions::new();
+/// request.insert_cloneable(RequestId(7));
+/// request.insert_cloneable(Route("/users"));
+/// let outer = extension_eyre::scope(request);
+///
+/// {
+///     let mut retry = Extensions::new();
+///     retry.insert_cloneable(RequestId(8));
+///     let _inner = extension_eyre::scope(retry);
+///
+///     let report = eyre!("connection reset");
+///     assert_eq!(report.extension_ref(), Some(&RequestId(8)));
+///     assert_eq!(report.extension_ref(), Some(&Route("/users")));
+/// }
+///
+/// let report = eyre!("connection reset");
+/// assert_eq!(report.extension_ref(), Some(&RequestId(7)));
+///
+/// drop(outer);
+/// let report = eyre!("connection reset");
+/// assert_eq!(report.extension_ref::<RequestId>(), None);
+///
+/// // the scope ends even if the code inside it panics
+/// let _ = std::panic::catch_unwind(|| {
+///     let mut request = Extensions::new();
+///     request.insert_cloneable(RequestId(9));
+///     let _guard = extension_eyre::scope(request);
+///     panic!("oops");
+/// });
+/// assert_eq!(eyre!("after").extension_ref::<RequestId>(), None);
+/// ```
+pub fn scope(extensions: Extensions) -> ScopeGuard {
+    let depth = SCOPES.with(|scopes| {
+        let mut scopes = scopes.borrow_mut();
+        scopes.push(Arc::new(extensions));
+        scopes.len() - 1
+    });
+
+    ScopeGuard {
+        depth,
+        _not_send: PhantomData,
+    }
+}
+
+/// Runs `future` with a copy of `extensions` attached to every report created while it is
+/// polled.
+///
+/// # Details
+///
+/// Works like [`scope`], but the scope follows the future across threads instead of staying on
+/// one, so concurrent tasks each see their own values. Scopes created with `scope_async` are
+/// outer to the ones created with [`scope`] while the future is polled.
+///
+/// # Examples
+///
+/// ```rust
+/// use extension_eyre::{eyre::eyre, extensions::Extensions, Extension};
+///
+/// #[derive(Debug, Clone, PartialEq)]
+/// pub struct RequestId(u64);
+///
+/// async fn handle_request() -> Option<RequestId> {
+///     tokio::task::yield_now().await;
+///     eyre!("connection reset").extension_ref::<RequestId>().cloned()
+/// }
+///
+/// async fn serve(id: u64) -> Option<RequestId> {
+///     let mut request = Extensions::new();
+///     request.insert_cloneable(RequestId(id));
+///     extension_eyre::scope_async(request, handle_request()).await
+/// }
+///
+/// extension_eyre::install().unwrap();
+///
+/// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// let (first, second) = runtime.block_on(async {
+///     let first = tokio::spawn(serve(1));
+///     let second = tokio::spawn(serve(2));
+///     (first.await.unwrap(), second.await.unwrap())
+/// });
+///
+/// assert_eq!(first, Some(RequestId(1)));
+/// assert_eq!(second, Some(RequestId(2)));
+/// assert_eq!(runtime.block_on(handle_request()), None);
+/// ```
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+pub fn scope_async<F: std::future::Future>(
+    extensions: Extensions,
+    future: F,
+) -> impl std::future::Future<Output = F::Output> {
+    scoped(extensions, future)
+}
+
+/// The future run by [`scope_async`], for futures that need to name its type.
+#[cfg(feature = "tokio")]
+pub(crate) type Scoped<F> = tokio::task::futures::TaskLocalFuture<Vec<Arc<Extensions>>, F>;
+
+#[cfg(feature = "tokio")]
+pub(crate) fn scoped<F: std::future::Future>(extensions: Extensions, future: F) -> Scoped<F> {
+    let mut scopes = TASK_SCOPES
+        .try_with(|scopes| scopes.clone())
+        .unwrap_or_default();
+    scopes.push(Arc::new(extensions));
+
+    TASK_SCOPES.scope(scopes, future)
+}
+
+/// Ends the scope started by [`scope`] when dropped.
+#[must_use = "the scope ends as soon as the guard is dropped"]
+pub struct ScopeGuard {
+    depth: usize,
+    // the scope belongs to the thread that started it
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for ScopeGuard {
+    fn drop(&mut self) {
+        let _ = SCOPES.try_with(|scopes| scopes.borrow_mut().truncate(self.depth));
+    }
+}
+
+impl std::fmt::Debug for ScopeGuard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScopeGuard")
+            .field("depth", &self.depth)
+            .finish()
+    }
+}
+
+/// Copies the values of the active scopes into `extensions`, from the outermost scope in.
+pub(crate) fn attach_to(extensions: &mut Extensions, precedence: &Precedence) {
+    #[cfg(feature = "tokio")]
+    let _ = TASK_SCOPES.try_with(|scopes| merge(extensions, scopes, precedence));
+
+    let _ = SCOPES.try_with(|scopes| {
+        if let Ok(scopes) = scopes.try_borrow() {
+            merge(extensions, &scopes, precedence);
+        }
+    });
+}
+
+fn merge(extensions: &mut Extensions, scopes: &[Arc<Extensions>], precedence: &Precedence) {
+    for scope in scopes {
+        extensions.merge_from(Source::Scope, scope.clone_map(), precedence);
+    }
+}
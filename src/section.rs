@@ -0,0 +1,550 @@
+//! Help sections rendered by this crate's [`Handler`](crate::Handler)
+//!
+//! color-eyre's own `Section` trait only attaches sections to reports whose handler is a
+//! `color_eyre::Handler`, so it silently drops everything attached to reports created after
+//! [`install`](crate::install). The [`Section`] trait here stores the sections in this crate's
+//! handler instead, and falls back to color-eyre's behavior for reports created by a plain
+//! color-eyre handler. Without the `color-eyre` feature, sections attached to those reports are
+//! dropped.
+use std::error::Error;
+use std::fmt::{self, Display};
+
+use owo_colors::{OwoColorize, Style};
+
+use crate::builtin::Suggestion;
+use crate::chain::Chain;
+use crate::config::HandlerConfig;
+use crate::eyre::Report;
+use crate::Handler;
+
+/// A helper trait for attaching informational sections to error reports to be
+/// displayed after the chain of errors
+///
+/// # Details
+///
+/// `extension_eyre` provides two types of help text that can be attached to error reports: custom
+/// sections and pre-configured sections. Custom sections are added via the `section` and
+/// `with_section` methods, and give maximum control over formatting, or via `titled_section` and
+/// `with_titled_section` for a body indented under a title.
+///
+/// The pre-configured sections are provided via `suggestion`, `warning`, and `note`. These
+/// sections are displayed after all other sections with no extra newlines between subsequent Help
+/// sections. They consist only of a header portion and are prepended to the message.
+///
+/// Sections attached through this trait are rendered after the output of the wrapped color-eyre
+/// handler, ahead of the report's metadata.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::{eyre::eyre, Extension, ExtensionExt, Section};
+///
+/// pub struct Retry(u32);
+///
+/// extension_eyre::install().unwrap();
+///
+/// let report = eyre!("could not acquire lock")
+///     .suggestion("try --force")
+///     .extension(Retry(3))
+///     .meta("lock", "deploy.lock");
+///
+/// let rendered = format!("{:?}", report);
+/// assert!(rendered.contains("Suggestion"));
+/// assert!(rendered.contains("try --force"));
+/// assert!(rendered.contains("deploy.lock"));
+/// assert!(matches!(report.extension_ref(), Some(Retry(3))));
+/// ```
+pub trait Section: crate::private::Sealed {
+    /// The return type of each method after adding context
+    type Return;
+
+    /// Add a section to an error report, to be displayed after the chain of errors.
+    ///
+    /// Sections are displayed in the order they are added to the error report. They are
+    /// displayed immediately after the output of the wrapped handler and before any `Help`
+    /// sections.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "color-eyre")] {
+    /// use extension_eyre::{eyre::eyre, eyre::Report, Section, SectionExt};
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let report = eyre!("cmd exited with non-zero status code")
+    ///     .section("cat: fake_file: No such file or directory".header("Stderr:"));
+    ///
+    /// assert!(format!("{:?}", report).contains("Stderr:"));
+    /// # }
+    /// ```
+    fn section<D>(self, section: D) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static;
+
+    /// Add a Section to an error report, to be displayed after the chain of errors. The closure to
+    /// create the Section is lazily evaluated only in the case of an error.
+    fn with_section<D, F>(self, section: F) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static,
+        F: FnOnce() -> D;
+
+    /// Add a section with a title to an error report, to be displayed after the chain of errors.
+    ///
+    /// The title is followed by a colon and each line of `body` is indented beneath it. Titled
+    /// sections are displayed in the order they are added along with the other custom sections,
+    /// with their title in the [`section_title`] style.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, Section};
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let result: Result<(), _> = Err(eyre!("deploy failed"));
+    /// let report = result
+    ///     .note("the previous release is still serving traffic")
+    ///     .warning("the lock file is older than a day")
+    ///     .titled_section("Hosts", "web-1: ok\nweb-2: unreachable")
+    ///     .warning("2 hosts were skipped")
+    ///     .unwrap_err();
+    ///
+    /// let rendered = format!("{:?}", report);
+    /// let hosts = rendered.find("Hosts:\n   web-1: ok\n   web-2: unreachable").unwrap();
+    /// let note = rendered.find("Note").unwrap();
+    /// let first = rendered.find("the lock file is older than a day").unwrap();
+    /// let second = rendered.find("2 hosts were skipped").unwrap();
+    /// assert!(hosts < note && note < first && first < second);
+    ///
+    /// let ok: Result<(), extension_eyre::Report> = Ok(());
+    /// let ok = ok.with_note(|| -> String { unreachable!() });
+    /// assert!(ok.is_ok());
+    /// ```
+    ///
+    /// [`section_title`]: crate::config::ExtensionStyles::section_title
+    fn titled_section<T, D>(self, title: T, body: D) -> Self::Return
+    where
+        T: Display + Send + Sync + 'static,
+        D: Display + Send + Sync + 'static;
+
+    /// Add a section with a title to an error report, to be displayed after the chain of errors.
+    /// The closure to create the body of the section is lazily evaluated only in the case of an
+    /// error.
+    fn with_titled_section<T, D, F>(self, title: T, body: F) -> Self::Return
+    where
+        T: Display + Send + Sync + 'static,
+        D: Display + Send + Sync + 'static,
+        F: FnOnce() -> D;
+
+    /// Add an error section to an error report, to be displayed after the primary error message
+    /// section.
+    fn error<E>(self, error: E) -> Self::Return
+    where
+        E: Error + Send + Sync + 'static;
+
+    /// Add an error section to an error report, to be displayed after the primary error message
+    /// section. The closure to create the error is lazily evaluated only in the case of an error.
+    fn with_error<E, F>(self, error: F) -> Self::Return
+    where
+        F: FnOnce() -> E,
+        E: Error + Send + Sync + 'static;
+
+    /// Add a Note to an error report, to be displayed after the chain of errors.
+    fn note<D>(self, note: D) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static;
+
+    /// Add a Note to an error report, to be displayed after the chain of errors. The closure to
+    /// create the Note is lazily evaluated only in the case of an error.
+    fn with_note<D, F>(self, note: F) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static,
+        F: FnOnce() -> D;
+
+    /// Add a Warning to an error report, to be displayed after the chain of errors.
+    fn warning<D>(self, warning: D) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static;
+
+    /// Add a Warning to an error report, to be displayed after the chain of errors. The closure to
+    /// create the Warning is lazily evaluated only in the case of an error.
+    fn with_warning<D, F>(self, warning: F) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static,
+        F: FnOnce() -> D;
+
+    /// Add a Suggestion to an error report, to be displayed after the chain of errors.
+    ///
+    /// The suggestion is attached as a [`builtin::Suggestion`] extension, so code handling the
+    /// report can read it back; suggestions added across several layers of context accumulate
+    /// and are displayed one per line.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::builtin::Suggestion;
+    /// use extension_eyre::{eyre::eyre, Extension, Section, WrapErr};
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let result: Result<(), _> = Err(eyre!("could not acquire lock"));
+    /// let report = result
+    ///     .suggestion("try --force")
+    ///     .wrap_err("deploy failed")
+    ///     .suggestion("check that no other deploy is running")
+    ///     .unwrap_err();
+    ///
+    /// let rendered = format!("{:?}", report);
+    /// assert!(rendered.contains("try --force"));
+    /// assert!(rendered.contains("check that no other deploy is running"));
+    ///
+    /// let suggestions = report
+    ///     .extensions_ref()
+    ///     .unwrap()
+    ///     .get_all::<Suggestion>()
+    ///     .map(|suggestion| &*suggestion.0)
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(suggestions, ["try --force", "check that no other deploy is running"]);
+    /// ```
+    ///
+    /// [`builtin::Suggestion`]: crate::builtin::Suggestion
+    fn suggestion<D>(self, suggestion: D) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static;
+
+    /// Add a Suggestion to an error report, to be displayed after the chain of errors. The closure
+    /// to create the Suggestion is lazily evaluated only in the case of an error.
+    fn with_suggestion<D, F>(self, suggestion: F) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static,
+        F: FnOnce() -> D;
+}
+
+impl Section for Report {
+    type Return = Report;
+
+    fn section<D>(self, section: D) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static,
+    {
+        self.with_section(|| section)
+    }
+
+    fn with_section<D, F>(mut self, section: F) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static,
+        F: FnOnce() -> D,
+    {
+        match self.handler_mut().downcast_mut::<Handler>() {
+            Some(handler) => handler.sections.push(HelpInfo::Custom(Box::new(section()))),
+            #[cfg(feature = "color-eyre")]
+            None => return color_eyre::Section::with_section(self, section),
+            #[cfg(not(feature = "color-eyre"))]
+            None => {}
+        }
+
+        self
+    }
+
+    fn titled_section<T, D>(self, title: T, body: D) -> Self::Return
+    where
+        T: Display + Send + Sync + 'static,
+        D: Display + Send + Sync + 'static,
+    {
+        self.with_titled_section(title, || body)
+    }
+
+    fn with_titled_section<T, D, F>(mut self, title: T, body: F) -> Self::Return
+    where
+        T: Display + Send + Sync + 'static,
+        D: Display + Send + Sync + 'static,
+        F: FnOnce() -> D,
+    {
+        match self.handler_mut().downcast_mut::<Handler>() {
+            Some(handler) => handler
+                .sections
+                .push(HelpInfo::Titled(Box::new(title), Box::new(body()))),
+            #[cfg(feature = "color-eyre")]
+            None => {
+                use color_eyre::SectionExt;
+                let section = || body().header(format!("{}:", title));
+                return color_eyre::Section::with_section(self, section);
+            }
+            #[cfg(not(feature = "color-eyre"))]
+            None => {}
+        }
+
+        self
+    }
+
+    fn error<E>(self, error: E) -> Self::Return
+    where
+        E: Error + Send + Sync + 'static,
+    {
+        self.with_error(|| error)
+    }
+
+    fn with_error<E, F>(mut self, error: F) -> Self::Return
+    where
+        F: FnOnce() -> E,
+        E: Error + Send + Sync + 'static,
+    {
+        match self.handler_mut().downcast_mut::<Handler>() {
+            Some(handler) => handler.sections.push(HelpInfo::Error(Box::new(error()))),
+            #[cfg(feature = "color-eyre")]
+            None => return color_eyre::Section::with_error(self, error),
+            #[cfg(not(feature = "color-eyre"))]
+            None => {}
+        }
+
+        self
+    }
+
+    fn note<D>(self, note: D) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static,
+    {
+        self.with_note(|| note)
+    }
+
+    fn with_note<D, F>(mut self, note: F) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static,
+        F: FnOnce() -> D,
+    {
+        match self.handler_mut().downcast_mut::<Handler>() {
+            Some(handler) => handler.sections.push(HelpInfo::Note(Box::new(note()))),
+            #[cfg(feature = "color-eyre")]
+            None => return color_eyre::Section::with_note(self, note),
+            #[cfg(not(feature = "color-eyre"))]
+            None => {}
+        }
+
+        self
+    }
+
+    fn warning<D>(self, warning: D) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static,
+    {
+        self.with_warning(|| warning)
+    }
+
+    fn with_warning<D, F>(mut self, warning: F) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static,
+        F: FnOnce() -> D,
+    {
+        match self.handler_mut().downcast_mut::<Handler>() {
+            Some(handler) => handler
+                .sections
+                .push(HelpInfo::Warning(Box::new(warning()))),
+            #[cfg(feature = "color-eyre")]
+            None => return color_eyre::Section::with_warning(self, warning),
+            #[cfg(not(feature = "color-eyre"))]
+            None => {}
+        }
+
+        self
+    }
+
+    fn suggestion<D>(self, suggestion: D) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static,
+    {
+        self.with_suggestion(|| suggestion)
+    }
+
+    fn with_suggestion<D, F>(mut self, suggestion: F) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static,
+        F: FnOnce() -> D,
+    {
+        match self.handler_mut().downcast_mut::<Handler>() {
+            Some(handler) => handler
+                .extensions
+                .push(Suggestion(suggestion().to_string().into())),
+            #[cfg(feature = "color-eyre")]
+            None => return color_eyre::Section::with_suggestion(self, suggestion),
+            #[cfg(not(feature = "color-eyre"))]
+            None => {}
+        }
+
+        self
+    }
+}
+
+impl<T, E> Section for Result<T, E>
+where
+    E: Into<Report>,
+{
+    type Return = Result<T, Report>;
+
+    fn section<D>(self, section: D) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static,
+    {
+        self.map_err(|report| report.into().section(section))
+    }
+
+    fn with_section<D, F>(self, section: F) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static,
+        F: FnOnce() -> D,
+    {
+        self.map_err(|report| report.into().with_section(section))
+    }
+
+    fn titled_section<T2, D>(self, title: T2, body: D) -> Self::Return
+    where
+        T2: Display + Send + Sync + 'static,
+        D: Display + Send + Sync + 'static,
+    {
+        self.map_err(|report| report.into().titled_section(title, body))
+    }
+
+    fn with_titled_section<T2, D, F>(self, title: T2, body: F) -> Self::Return
+    where
+        T2: Display + Send + Sync + 'static,
+        D: Display + Send + Sync + 'static,
+        F: FnOnce() -> D,
+    {
+        self.map_err(|report| report.into().with_titled_section(title, body))
+    }
+
+    fn error<E2>(self, error: E2) -> Self::Return
+    where
+        E2: Error + Send + Sync + 'static,
+    {
+        self.map_err(|report| report.into().error(error))
+    }
+
+    fn with_error<E2, F>(self, error: F) -> Self::Return
+    where
+        F: FnOnce() -> E2,
+        E2: Error + Send + Sync + 'static,
+    {
+        self.map_err(|report| report.into().with_error(error))
+    }
+
+    fn note<D>(self, note: D) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static,
+    {
+        self.map_err(|report| report.into().note(note))
+    }
+
+    fn with_note<D, F>(self, note: F) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static,
+        F: FnOnce() -> D,
+    {
+        self.map_err(|report| report.into().with_note(note))
+    }
+
+    fn warning<D>(self, warning: D) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static,
+    {
+        self.map_err(|report| report.into().warning(warning))
+    }
+
+    fn with_warning<D, F>(self, warning: F) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static,
+        F: FnOnce() -> D,
+    {
+        self.map_err(|report| report.into().with_warning(warning))
+    }
+
+    fn suggestion<D>(self, suggestion: D) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static,
+    {
+        self.map_err(|report| report.into().suggestion(suggestion))
+    }
+
+    fn with_suggestion<D, F>(self, suggestion: F) -> Self::Return
+    where
+        D: Display + Send + Sync + 'static,
+        F: FnOnce() -> D,
+    {
+        self.map_err(|report| report.into().with_suggestion(suggestion))
+    }
+}
+
+/// A section attached to a report through [`Section`].
+pub(crate) enum HelpInfo {
+    Error(Box<dyn Error + Send + Sync + 'static>),
+    Custom(Box<dyn Display + Send + Sync + 'static>),
+    Titled(
+        Box<dyn Display + Send + Sync + 'static>,
+        Box<dyn Display + Send + Sync + 'static>,
+    ),
+    Note(Box<dyn Display + Send + Sync + 'static>),
+    Warning(Box<dyn Display + Send + Sync + 'static>),
+}
+
+impl HelpInfo {
+    /// Whether the section is shown in the block of one-line `Help` sections at the end.
+    pub(crate) fn is_help(&self) -> bool {
+        !matches!(
+            self,
+            HelpInfo::Error(_) | HelpInfo::Custom(_) | HelpInfo::Titled(..)
+        )
+    }
+
+    /// Renders the section with the configured styles and chain depth.
+    pub(crate) fn rendered<'a>(&'a self, config: &'a HandlerConfig) -> RenderedHelpInfo<'a> {
+        RenderedHelpInfo(self, config)
+    }
+}
+
+/// A [`HelpInfo`] rendered with the configured
+/// [`ExtensionStyles`](crate::config::ExtensionStyles), its errors bounded by the configured
+/// chain depth.
+pub(crate) struct RenderedHelpInfo<'a>(&'a HelpInfo, &'a HandlerConfig);
+
+impl Display for RenderedHelpInfo<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let styles = &self.1.extension_styles;
+        match self.0 {
+            HelpInfo::Note(note) => write!(f, "{}: {}", "Note".style(styles.note), note),
+            HelpInfo::Warning(warning) => {
+                write!(f, "{}: {}", "Warning".style(styles.warning), warning)
+            }
+            HelpInfo::Custom(section) => write!(f, "{}", section),
+            HelpInfo::Titled(title, body) => {
+                let title = format_args!("{}:", title);
+                write!(f, "{}", title.style(styles.section_title))?;
+                for line in body.to_string().lines() {
+                    write!(f, "\n   {}", line)?;
+                }
+
+                Ok(())
+            }
+            HelpInfo::Error(error) => {
+                let error = error.as_ref() as &(dyn Error + 'static);
+                let errors = Chain::new(error, self.1.max_chain_depth);
+
+                write!(f, "Error:")?;
+                for (n, error) in errors.enumerate() {
+                    // the style of color-eyre's default theme
+                    write!(f, "\n{:>4}: {}", n, error.style(Style::new().bright_red()))?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Renders a [`Suggestion`] attached to a report as a `Help` line, with its label in the style.
+pub(crate) struct SuggestionLine<'a>(pub(crate) &'a Suggestion, pub(crate) Style);
+
+impl Display for SuggestionLine<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", "Suggestion".style(self.1), self.0 .0)
+    }
+}
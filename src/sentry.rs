@@ -0,0 +1,151 @@
+//! Reporting to [Sentry](https://sentry.io) through [`sentry_core`]
+//!
+//! [`event_from_report`] turns a report into a Sentry event carrying its extensions, and
+//! [`capture_report`] sends it to the current hub:
+//!
+//! - the error chain becomes the event's exceptions, outermost last like Sentry expects
+//! - the captured span trace becomes breadcrumbs, outermost span first
+//! - extensions shown in the report's `Extensions:` section become tags, by type name, along with
+//!   an `error_code` tag for reports with an [`ErrorCode`](crate::builtin::ErrorCode)
+//! - serializable extensions become the event's `extra` entries, by type name
+//! - the [`Severity`](crate::builtin::Severity) sets the event's level
+//!
+//! Redacted extensions are left out.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use extension_eyre::builtin::{ErrorCode, Severity};
+//! use extension_eyre::{eyre::eyre, Extension, ExtensionExt, WrapErr};
+//! use sentry_core::protocol::Level;
+//!
+//! extension_eyre::install().unwrap();
+//!
+//! let events = sentry_core::test::with_captured_events(|| {
+//!     let mut report = Err::<(), _>(eyre!("connection reset"))
+//!         .wrap_err("could not fetch the price list")
+//!         .extension(ErrorCode("E42"))
+//!         .extension(Severity::Warning)
+//!         .unwrap_err();
+//!     let extensions = report.extensions_mut().unwrap();
+//!     extensions.insert_display(String::from("GET /prices"));
+//!     extensions.insert_serializable(3u32);
+//!
+//!     extension_eyre::sentry::capture_report(&report);
+//! });
+//!
+//! let event = &events[0];
+//! let values: Vec<_> = event.exception.iter().map(|e| e.value.as_deref()).collect();
+//! assert_eq!(
+//!     values,
+//!     [Some("connection reset"), Some("could not fetch the price list")]
+//! );
+//! assert_eq!(event.level, Level::Warning);
+//! assert_eq!(event.tags["error_code"], "E42");
+//! assert_eq!(event.tags["alloc::string::String"], "GET /prices");
+//! assert_eq!(event.extra["u32"], 3);
+//! ```
+#[cfg(feature = "capture-spantrace")]
+use sentry_core::protocol::Breadcrumb;
+use sentry_core::protocol::{Event, Exception, Level};
+use sentry_core::types::Uuid;
+
+use crate::builtin::Severity;
+use crate::chain::Chain;
+use crate::extensions::Extension;
+use crate::eyre::Report;
+
+/// Sends `report` to the current Sentry hub, returning the id of the event
+///
+/// See [`event_from_report`] for what the event holds.
+pub fn capture_report(report: &Report) -> Uuid {
+    sentry_core::capture_event(event_from_report(report))
+}
+
+/// Builds the Sentry event describing `report`, see the [module docs](self)
+pub fn event_from_report(report: &Report) -> Event<'static> {
+    let mut exceptions: Vec<_> = Chain::of_report(report).map(exception).collect();
+    exceptions.reverse();
+
+    let mut event = Event {
+        exception: exceptions.into(),
+        level: Level::Error,
+        ..Default::default()
+    };
+
+    let handler = match report.handler().downcast_ref::<crate::Handler>() {
+        Some(handler) => handler,
+        None => return event,
+    };
+
+    #[cfg(feature = "capture-spantrace")]
+    if let Some(span_trace) = crate::trace::captured_span_trace(handler.inner.as_ref()) {
+        event.breadcrumbs = breadcrumbs(span_trace).into();
+    }
+
+    let extensions = &handler.extensions;
+
+    for (type_name, value) in extensions.displayed(&handler.config.formatters) {
+        event.tags.insert(type_name.to_owned(), value);
+    }
+    if let Some(code) = report.error_code() {
+        event.tags.insert("error_code".to_owned(), code.to_owned());
+    }
+
+    if let serde_json::Value::Object(serialized) = extensions.to_json_value() {
+        event.extra.extend(serialized);
+    }
+
+    if let Some(severity) = extensions.get::<Severity>() {
+        event.level = match severity {
+            Severity::Warning => Level::Warning,
+            Severity::Error => Level::Error,
+            Severity::Fatal => Level::Fatal,
+        };
+    }
+
+    event
+}
+
+// Like `sentry_core::event_from_error` does for each error of a chain.
+fn exception(error: &(dyn std::error::Error + 'static)) -> Exception {
+    let debug = format!("{:?}", error);
+    let value = error.to_string();
+
+    // errors made from a message debug-format as that message, which names no type
+    let ty = if debug == format!("{:?}", value) {
+        "Error".to_owned()
+    } else {
+        sentry_core::parse_type_from_debug(&debug).to_owned()
+    };
+
+    Exception {
+        ty,
+        value: Some(value),
+        ..Default::default()
+    }
+}
+
+#[cfg(feature = "capture-spantrace")]
+fn breadcrumbs(span_trace: &tracing_error::SpanTrace) -> Vec<Breadcrumb> {
+    let mut breadcrumbs = Vec::new();
+    span_trace.with_spans(|metadata, fields| {
+        let mut data = sentry_core::protocol::Map::new();
+        if !fields.is_empty() {
+            data.insert("fields".to_owned(), fields.into());
+        }
+
+        breadcrumbs.push(Breadcrumb {
+            ty: "default".to_owned(),
+            category: Some("span".to_owned()),
+            message: Some(format!("{}::{}", metadata.target(), metadata.name())),
+            data,
+            ..Default::default()
+        });
+        true
+    });
+
+    // spans are walked from the innermost, breadcrumbs go from the oldest
+    breadcrumbs.reverse();
+    breadcrumbs
+}
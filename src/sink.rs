@@ -0,0 +1,117 @@
+//! Destinations for finished error reports
+//!
+//! A [`Sink`] receives reports once the application has decided to emit them, e.g. to log
+//! them, forward them to a telemetry backend, or check them against a budget.
+use crate::eyre::Report;
+use crate::{analyze, Extension, ObserversBypassed, ReportAnalysis};
+
+/// A destination for finished error reports.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::{eyre::eyre, eyre::Report, sink::Sink};
+/// use std::sync::Mutex;
+///
+/// #[derive(Default)]
+/// struct Collect(Mutex<Vec<String>>);
+///
+/// impl Sink for Collect {
+///     fn report(&self, report: &Report) {
+///         self.0.lock().unwrap().push(report.to_string());
+///     }
+/// }
+///
+/// extension_eyre::install().unwrap();
+///
+/// let sink = Collect::default();
+/// sink.report(&eyre!("cmd exited with non-zero status code"));
+/// assert_eq!(sink.0.lock().unwrap().len(), 1);
+/// ```
+pub trait Sink: Send + Sync {
+    /// Handles a finished report.
+    fn report(&self, report: &Report);
+
+    /// Handles a finished report along with its `{:?}` rendering.
+    ///
+    /// The [reporter](crate::reporter) renders every report once on its own thread and calls
+    /// this instead of [`report`](Sink::report), so sinks writing the rendered report don't
+    /// each render it again. Forwards to [`report`](Sink::report) unless overridden.
+    fn report_rendered(&self, report: &Report, rendered: &str) {
+        let _ = rendered;
+        self.report(report);
+    }
+}
+
+/// Sink that warns about reports whose rendered form, serialized form or attached extensions
+/// exceed a byte budget.
+///
+/// The warning is a [`tracing`](https://docs.rs/tracing) event with the `tracing` feature
+/// enabled, and is written to stderr otherwise.
+///
+/// Reports carrying [`ObserversBypassed`] are never checked.
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::{eyre::eyre, ExtensionExt, sink::BudgetAlertSink, sink::Sink};
+///
+/// pub struct Retry;
+///
+/// extension_eyre::install().unwrap();
+///
+/// let sink = BudgetAlertSink { threshold_bytes: 16 * 1024 };
+///
+/// let small = eyre!("upstream returned 502").extension(Retry);
+/// assert!(sink.check(&small).is_none());
+///
+/// let response_body = "x".repeat(64 * 1024);
+/// let bloated = eyre!("upstream returned 502").extension(response_body).extension(Retry);
+///
+/// let analysis = sink.check(&bloated).expect("report is over budget");
+/// assert_eq!(analysis.per_extension_estimated_bytes[0].0, "alloc::string::String");
+///
+/// // logs the same analysis as a warning
+/// sink.report(&bloated);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct BudgetAlertSink {
+    /// Largest accepted size of a rendered or serialized report, in bytes.
+    pub threshold_bytes: usize,
+}
+
+impl BudgetAlertSink {
+    /// Returns the report's analysis if it's over budget.
+    pub fn check(&self, report: &Report) -> Option<ReportAnalysis> {
+        let analysis = analyze(report);
+
+        if analysis.payload_len() > self.threshold_bytes {
+            Some(analysis)
+        } else {
+            None
+        }
+    }
+}
+
+impl Sink for BudgetAlertSink {
+    fn report(&self, report: &Report) {
+        if report.extension_ref::<ObserversBypassed>().is_some() {
+            return;
+        }
+
+        if let Some(analysis) = self.check(report) {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                threshold_bytes = self.threshold_bytes,
+                ?analysis,
+                "error report exceeds budget"
+            );
+
+            #[cfg(not(feature = "tracing"))]
+            eprintln!(
+                "Warning: error report exceeds budget of {} bytes: {:?}",
+                self.threshold_bytes, analysis
+            );
+        }
+    }
+}
@@ -0,0 +1,202 @@
+//! Attaching extensions to the errors of streams
+//!
+//! [`ExtensionStreamExt`] attaches an extension to every `Err` item of a stream of `Result`s,
+//! like [`ExtensionFutureExt`](crate::future::ExtensionFutureExt) does for the output of a
+//! future.
+//!
+//! ### Example
+//!
+//! ```rust
+//! use extension_eyre::{eyre::eyre, stream::ExtensionStreamExt, Extension};
+//! use futures::{executor::block_on, stream, Stream, StreamExt};
+//!
+//! #[derive(Debug, Clone, PartialEq)]
+//! pub enum StreamStage {
+//!     Body,
+//! }
+//!
+//! extension_eyre::install().unwrap();
+//!
+//! let chunks = stream::iter(vec![
+//!     Ok(1),
+//!     Err(eyre!("connection reset")),
+//!     Ok(2),
+//!     Err(eyre!("unexpected end of body")),
+//! ]);
+//!
+//! let chunks = chunks.extension_errs(StreamStage::Body);
+//! assert_eq!(chunks.size_hint(), (4, Some(4)));
+//!
+//! let chunks: Vec<_> = block_on(chunks.collect());
+//!
+//! assert_eq!(*chunks[0].as_ref().unwrap(), 1);
+//! assert_eq!(*chunks[2].as_ref().unwrap(), 2);
+//! for chunk in [&chunks[1], &chunks[3]] {
+//!     let report = chunk.as_ref().unwrap_err();
+//!     assert_eq!(report.extension_ref(), Some(&StreamStage::Body));
+//! }
+//! ```
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::stream::{FusedStream, Stream};
+use pin_project_lite::pin_project;
+
+use crate::extensions::ExtensionExt;
+use crate::eyre::Report;
+
+/// Extension methods for streams of `Result<T, E> where E: Into<Report>`.
+pub trait ExtensionStreamExt<T, E>: Stream<Item = Result<T, E>> + Sized
+where
+    E: Into<Report>,
+{
+    /// Attaches a clone of `extension` to every `Err` item of the stream.
+    fn extension_errs<Ext>(self, extension: Ext) -> ExtensionErrs<Self, Ext>
+    where
+        Ext: Clone + Send + Sync + 'static;
+
+    /// Attaches the value returned by `f` to every `Err` item of the stream.
+    ///
+    /// `f` is only called for `Err` items.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::{eyre::eyre, stream::ExtensionStreamExt, Extension};
+    /// use futures::{executor::block_on, stream, StreamExt};
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct Chunk(usize);
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let mut index = 0;
+    /// let chunks = stream::iter(vec![Ok(()), Err(eyre!("connection reset"))]);
+    /// let chunks = chunks.with_extension_errs(move || {
+    ///     index += 1;
+    ///     Chunk(index)
+    /// });
+    ///
+    /// let chunks: Vec<_> = block_on(chunks.collect());
+    /// let report = chunks[1].as_ref().unwrap_err();
+    /// assert_eq!(report.extension_ref(), Some(&Chunk(1)));
+    /// ```
+    fn with_extension_errs<Ext, F>(self, f: F) -> WithExtensionErrs<Self, F>
+    where
+        Ext: Send + Sync + 'static,
+        F: FnMut() -> Ext;
+}
+
+impl<St, T, E> ExtensionStreamExt<T, E> for St
+where
+    St: Stream<Item = Result<T, E>>,
+    E: Into<Report>,
+{
+    fn extension_errs<Ext>(self, extension: Ext) -> ExtensionErrs<Self, Ext>
+    where
+        Ext: Clone + Send + Sync + 'static,
+    {
+        ExtensionErrs {
+            stream: self,
+            extension,
+        }
+    }
+
+    fn with_extension_errs<Ext, F>(self, f: F) -> WithExtensionErrs<Self, F>
+    where
+        Ext: Send + Sync + 'static,
+        F: FnMut() -> Ext,
+    {
+        WithExtensionErrs { stream: self, f }
+    }
+}
+
+pin_project! {
+    /// Stream returned by [`ExtensionStreamExt::extension_errs`].
+    #[must_use = "streams do nothing unless polled"]
+    #[derive(Debug)]
+    pub struct ExtensionErrs<St, Ext> {
+        #[pin]
+        stream: St,
+        extension: Ext,
+    }
+}
+
+impl<St, T, E, Ext> Stream for ExtensionErrs<St, Ext>
+where
+    St: Stream<Item = Result<T, E>>,
+    E: Into<Report>,
+    Ext: Clone + Send + Sync + 'static,
+{
+    type Item = Result<T, Report>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let extension = this.extension;
+
+        this.stream
+            .poll_next(cx)
+            .map(|item| item.map(|item| item.with_extension(|| extension.clone())))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
+
+impl<St, T, E, Ext> FusedStream for ExtensionErrs<St, Ext>
+where
+    St: FusedStream<Item = Result<T, E>>,
+    E: Into<Report>,
+    Ext: Clone + Send + Sync + 'static,
+{
+    fn is_terminated(&self) -> bool {
+        self.stream.is_terminated()
+    }
+}
+
+pin_project! {
+    /// Stream returned by [`ExtensionStreamExt::with_extension_errs`].
+    #[must_use = "streams do nothing unless polled"]
+    #[derive(Debug)]
+    pub struct WithExtensionErrs<St, F> {
+        #[pin]
+        stream: St,
+        f: F,
+    }
+}
+
+impl<St, T, E, Ext, F> Stream for WithExtensionErrs<St, F>
+where
+    St: Stream<Item = Result<T, E>>,
+    E: Into<Report>,
+    Ext: Send + Sync + 'static,
+    F: FnMut() -> Ext,
+{
+    type Item = Result<T, Report>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let f = this.f;
+
+        this.stream
+            .poll_next(cx)
+            .map(|item| item.map(|item| item.with_extension(f)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
+
+impl<St, T, E, Ext, F> FusedStream for WithExtensionErrs<St, F>
+where
+    St: FusedStream<Item = Result<T, E>>,
+    E: Into<Report>,
+    Ext: Send + Sync + 'static,
+    F: FnMut() -> Ext,
+{
+    fn is_terminated(&self) -> bool {
+        self.stream.is_terminated()
+    }
+}
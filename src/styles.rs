@@ -0,0 +1,184 @@
+//! Styles of the parts of reports rendered by this crate, see [`ExtensionStyles`]
+use std::any::TypeId;
+use std::collections::HashMap;
+
+use owo_colors::Style;
+
+/// The styles of the parts of reports rendered by extension-eyre rather than color-eyre, set
+/// with [`HookBuilder::extension_styles`](crate::config::HookBuilder::extension_styles).
+///
+/// They complement the color-eyre [`Theme`](crate::config::Theme), which styles the error chain
+/// and the traces. The default styles are the ones of [`ExtensionStyles::dark`].
+///
+/// ### Example
+///
+/// ```rust
+/// use extension_eyre::builtin::ErrorCode;
+/// use extension_eyre::config::{ExtensionStyles, HookBuilder};
+/// use extension_eyre::owo_colors::Style;
+/// use extension_eyre::{eyre::eyre, Extension, ExtensionExt, Section};
+///
+/// pub struct Route(&'static str);
+///
+/// impl std::fmt::Display for Route {
+///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+///         f.write_str(self.0)
+///     }
+/// }
+///
+/// let styles = ExtensionStyles::new()
+///     .extension_header(Style::new().bold())
+///     .extension_key(Style::new().blue())
+///     .extension_value(Style::new().green())
+///     .type_value::<u16>(Style::new().red())
+///     .suggestion(Style::new().magenta())
+///     .note(Style::new().cyan())
+///     .error_code(Style::new().yellow());
+///
+/// HookBuilder::default()
+///     .extension_styles(styles)
+///     .install()
+///     .unwrap();
+///
+/// let mut report = eyre!("upstream unavailable")
+///     .extension(ErrorCode("E42"))
+///     .suggestion("try again later")
+///     .note("cached prices are shown");
+/// let extensions = report.extensions_mut().unwrap();
+/// extensions.insert_display(Route("GET /prices"));
+/// extensions.insert_display(502u16);
+///
+/// let output = format!("{:?}", report);
+/// assert!(output.contains("\n\nError code: \u{1b}[33mE42\u{1b}[0m\n"));
+/// assert!(output.contains("\n\u{1b}[35mSuggestion\u{1b}[0m: try again later\n"));
+/// assert!(output.contains("\n\n\u{1b}[36mNote\u{1b}[0m: cached prices are shown\n"));
+/// assert!(output.contains("\n\n\u{1b}[1mExtensions:\u{1b}[0m\n"));
+///
+/// let route = std::any::type_name::<Route>();
+/// let route = format!("\n   \u{1b}[34m{}\u{1b}[0m: \u{1b}[32mGET /prices\u{1b}[0m\n", route);
+/// assert!(output.contains(&route));
+/// assert!(output.ends_with("\n   \u{1b}[34mu16\u{1b}[0m: \u{1b}[31m502\u{1b}[0m"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ExtensionStyles {
+    pub(crate) extension_header: Style,
+    pub(crate) extension_key: Style,
+    pub(crate) extension_value: Style,
+    pub(crate) type_values: HashMap<TypeId, Style>,
+    pub(crate) suggestion: Style,
+    pub(crate) note: Style,
+    pub(crate) warning: Style,
+    pub(crate) section_title: Style,
+    pub(crate) error_code: Style,
+    pub(crate) report_id: Style,
+}
+
+impl ExtensionStyles {
+    /// Creates a blank set of styles, rendering everything without colors.
+    pub fn new() -> Self {
+        ExtensionStyles {
+            extension_header: Style::new(),
+            extension_key: Style::new(),
+            extension_value: Style::new(),
+            type_values: HashMap::new(),
+            suggestion: Style::new(),
+            note: Style::new(),
+            warning: Style::new(),
+            section_title: Style::new(),
+            error_code: Style::new(),
+            report_id: Style::new(),
+        }
+    }
+
+    /// Returns the styles for dark terminals, matching color-eyre's dark theme.
+    ///
+    /// Only the `Suggestion`, `Note` and `Warning` labels are colored, like color-eyre colors its
+    /// own.
+    pub fn dark() -> Self {
+        Self::new()
+            .suggestion(Style::new().bright_cyan())
+            .note(Style::new().bright_cyan())
+            .warning(Style::new().bright_yellow())
+    }
+
+    /// Styles the `Extensions:` header of the extensions section.
+    pub fn extension_header(mut self, style: Style) -> Self {
+        self.extension_header = style;
+        self
+    }
+
+    /// Styles the type names of the extensions section.
+    pub fn extension_key(mut self, style: Style) -> Self {
+        self.extension_key = style;
+        self
+    }
+
+    /// Styles the values of the extensions section.
+    pub fn extension_value(mut self, style: Style) -> Self {
+        self.extension_value = style;
+        self
+    }
+
+    /// Styles the values of type `T` of the extensions section, instead of
+    /// [`extension_value`](ExtensionStyles::extension_value).
+    pub fn type_value<T: 'static>(mut self, style: Style) -> Self {
+        self.type_values.insert(TypeId::of::<T>(), style);
+        self
+    }
+
+    /// Styles the `Suggestion` label of [`Suggestion`](crate::builtin::Suggestion) lines.
+    pub fn suggestion(mut self, style: Style) -> Self {
+        self.suggestion = style;
+        self
+    }
+
+    /// Styles the `Note` label of the notes attached with [`Section::note`].
+    ///
+    /// [`Section::note`]: crate::Section::note
+    pub fn note(mut self, style: Style) -> Self {
+        self.note = style;
+        self
+    }
+
+    /// Styles the `Warning` label of the warnings attached with [`Section::warning`].
+    ///
+    /// [`Section::warning`]: crate::Section::warning
+    pub fn warning(mut self, style: Style) -> Self {
+        self.warning = style;
+        self
+    }
+
+    /// Styles the titles of the sections attached with [`Section::titled_section`].
+    ///
+    /// [`Section::titled_section`]: crate::Section::titled_section
+    pub fn section_title(mut self, style: Style) -> Self {
+        self.section_title = style;
+        self
+    }
+
+    /// Styles the code of the `Error code:` line.
+    pub fn error_code(mut self, style: Style) -> Self {
+        self.error_code = style;
+        self
+    }
+
+    /// Styles the id of the `Report id:` line.
+    pub fn report_id(mut self, style: Style) -> Self {
+        self.report_id = style;
+        self
+    }
+
+    /// The style of the values of the type identified by `type_id`.
+    pub(crate) fn value(&self, type_id: &TypeId) -> Style {
+        self.type_values
+            .get(type_id)
+            .copied()
+            .unwrap_or(self.extension_value)
+    }
+}
+
+impl Default for ExtensionStyles {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
@@ -0,0 +1,230 @@
+//! Traces and sections rendered by extension-eyre itself
+//!
+//! The inner color-eyre handler decides whether to capture a backtrace and span trace when the
+//! report is created, before any [`Verbose`](crate::builtin::Verbose) or
+//! [`Suppress`](crate::builtin::Suppress) extension can be attached, and its output can't be
+//! changed afterwards. To honor them, the hook captures the traces the inner handler didn't, and
+//! [`Handler`](crate::Handler) renders the report's sections itself when some are suppressed, or
+//! when extensions attached to frames of the chain have to be shown beneath them.
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::panic::Location;
+
+#[cfg(feature = "color-eyre")]
+use backtrace::Backtrace;
+use owo_colors::{OwoColorize, Style};
+#[cfg(not(feature = "color-eyre"))]
+use std::backtrace::Backtrace;
+
+use crate::builtin::SectionKind;
+use crate::config::Formatters;
+use crate::extensions::Frames;
+
+/// The traces captured for a report in case it is marked verbose.
+#[derive(Default)]
+pub(crate) struct Traces {
+    backtrace: Option<Backtrace>,
+    #[cfg(feature = "capture-spantrace")]
+    span_trace: Option<tracing_error::SpanTrace>,
+}
+
+impl Traces {
+    /// Captures the traces `inner` didn't capture itself.
+    pub(crate) fn capture(inner: &dyn eyre::EyreHandler) -> Traces {
+        let backtrace = match captured_backtrace(inner) {
+            Some(_) => None,
+            #[cfg(feature = "color-eyre")]
+            None => Some(Backtrace::new_unresolved()),
+            #[cfg(not(feature = "color-eyre"))]
+            None => Some(Backtrace::force_capture()),
+        };
+
+        #[cfg(feature = "capture-spantrace")]
+        let span_trace = match captured_span_trace(inner) {
+            Some(_) => None,
+            None => Some(tracing_error::SpanTrace::capture()),
+        };
+
+        Traces {
+            backtrace,
+            #[cfg(feature = "capture-spantrace")]
+            span_trace,
+        }
+    }
+}
+
+impl Display for Traces {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[cfg(feature = "capture-spantrace")]
+        if let Some(span_trace) = &self.span_trace {
+            write_span_trace(f, span_trace)?;
+        }
+
+        if let Some(backtrace) = &self.backtrace {
+            write_backtrace(f, backtrace)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A report rendered section by section, leaving out the `suppressed` ones.
+///
+/// The error chain is always shown, with the extensions attached to each frame on the line below
+/// it. Traces come from the inner handler when it's color-eyre's, or from `traces` if the report
+/// is `verbose`.
+pub(crate) struct SectionedReport<'a> {
+    pub(crate) error: &'a (dyn Error + 'static),
+    pub(crate) frames: &'a Frames,
+    pub(crate) formatters: &'a Formatters,
+    pub(crate) location: Option<&'static Location<'static>>,
+    pub(crate) inner: &'a dyn eyre::EyreHandler,
+    pub(crate) traces: &'a Traces,
+    pub(crate) verbose: bool,
+    pub(crate) suppressed: SectionKind,
+}
+
+impl Display for SectionedReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let errors = std::iter::successors(Some(self.error), |&error| error.source());
+        let len = errors.clone().count();
+        for (n, error) in errors.enumerate() {
+            write!(f, "\n{:>4}: {}", n, error.style(Style::new().bright_red()))?;
+            if let Some(extensions) = self.frames.at(n, len) {
+                write!(f, "\n      {}", extensions.summary(self.formatters))?;
+            }
+        }
+
+        if !self.suppressed.contains(SectionKind::LOCATION) {
+            if let Some(location) = self.location {
+                write!(
+                    f,
+                    "\n\nLocation:\n   {}:{}",
+                    location.file().style(Style::new().purple()),
+                    location.line().style(Style::new().purple())
+                )?;
+            }
+        }
+
+        let traces = Some(self.traces).filter(|_| self.verbose);
+
+        #[cfg(feature = "capture-spantrace")]
+        if !self.suppressed.contains(SectionKind::SPANTRACE) {
+            let span_trace = captured_span_trace(self.inner)
+                .or_else(|| traces.and_then(|traces| traces.span_trace.as_ref()));
+
+            if let Some(span_trace) = span_trace {
+                write_span_trace(f, span_trace)?;
+            }
+        }
+
+        let backtrace = captured_backtrace(self.inner)
+            .or_else(|| traces.and_then(|traces| traces.backtrace.as_ref()));
+
+        if !self.suppressed.contains(SectionKind::BACKTRACE) {
+            if let Some(backtrace) = backtrace {
+                write_backtrace(f, backtrace)?;
+            }
+        }
+
+        if !self.suppressed.contains(SectionKind::ENV) && backtrace.is_none() {
+            write!(
+                f,
+                "\n\nBacktrace omitted. Run with RUST_BACKTRACE=1 environment variable to display it."
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether reports rendered by `inner` can be rendered section by section instead.
+pub(crate) fn is_sectioned(inner: &dyn eyre::EyreHandler) -> bool {
+    #[cfg(feature = "color-eyre")]
+    return inner.is::<color_eyre::Handler>();
+    #[cfg(not(feature = "color-eyre"))]
+    return inner.is::<eyre::DefaultHandler>();
+}
+
+/// The backtrace `inner` captured, if it's a color-eyre handler.
+#[cfg(feature = "color-eyre")]
+fn captured_backtrace(inner: &dyn eyre::EyreHandler) -> Option<&Backtrace> {
+    inner
+        .downcast_ref::<color_eyre::Handler>()
+        .and_then(color_eyre::Handler::backtrace)
+}
+
+#[cfg(not(feature = "color-eyre"))]
+fn captured_backtrace(_: &dyn eyre::EyreHandler) -> Option<&Backtrace> {
+    None
+}
+
+/// The span trace `inner` captured, if it's a color-eyre handler.
+#[cfg(feature = "capture-spantrace")]
+pub(crate) fn captured_span_trace(
+    inner: &dyn eyre::EyreHandler,
+) -> Option<&tracing_error::SpanTrace> {
+    inner
+        .downcast_ref::<color_eyre::Handler>()
+        .and_then(color_eyre::Handler::span_trace)
+}
+
+#[cfg(feature = "capture-spantrace")]
+fn write_span_trace(
+    f: &mut fmt::Formatter<'_>,
+    span_trace: &tracing_error::SpanTrace,
+) -> fmt::Result {
+    if span_trace.status() != tracing_error::SpanTraceStatus::CAPTURED {
+        return Ok(());
+    }
+
+    write!(f, "\n\n{}\n", header("SPANTRACE"))?;
+    let span_trace = color_spantrace::colorize(span_trace).to_string();
+    for line in span_trace.lines() {
+        write!(f, "\n  {}", line)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "color-eyre")]
+fn write_backtrace(f: &mut fmt::Formatter<'_>, backtrace: &Backtrace) -> fmt::Result {
+    let mut backtrace = backtrace.clone();
+    backtrace.resolve();
+
+    write!(f, "\n\n{}", header("BACKTRACE"))?;
+    let symbols = backtrace.frames().iter().flat_map(|frame| frame.symbols());
+    for (n, symbol) in symbols.enumerate() {
+        let name = symbol
+            .name()
+            .map_or_else(|| "<unknown>".to_owned(), |name| name.to_string());
+        write!(f, "\n{:>4}: {}", n, name.style(Style::new().bright_red()))?;
+
+        if let (Some(file), Some(line)) = (symbol.filename(), symbol.lineno()) {
+            write!(
+                f,
+                "\n      at {}:{}",
+                file.display().style(Style::new().purple()),
+                line.style(Style::new().purple())
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+// std's backtraces don't expose their frames, so they are rendered as they format themselves
+#[cfg(not(feature = "color-eyre"))]
+fn write_backtrace(f: &mut fmt::Formatter<'_>, backtrace: &Backtrace) -> fmt::Result {
+    write!(f, "\n\n{}", header("BACKTRACE"))?;
+    for line in backtrace.to_string().lines() {
+        write!(f, "\n{}", line)?;
+    }
+
+    Ok(())
+}
+
+// the header color-eyre puts above its traces
+pub(crate) fn header(title: &str) -> String {
+    format!("  {:━^80}", format!(" {} ", title))
+}
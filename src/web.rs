@@ -0,0 +1,204 @@
+//! Responding to web requests with error reports
+//!
+//! [`WebReport`] turns a report into an HTTP response, for axum with the `axum` feature and for
+//! actix-web with the `actix` feature: the status comes from the attached
+//! [`HttpStatus`](crate::builtin::HttpStatus), and the body is a JSON object with the report's
+//! message chain, its [user message](crate::builtin::user_message) if there is one, and the
+//! extensions inserted with
+//! [`Extensions::insert_serializable`](crate::extensions::Extensions::insert_serializable).
+//!
+//! ### Example
+//!
+//! With axum:
+//!
+//! ```rust
+//! # #[cfg(feature = "axum")] {
+//! use axum::{body::Body, http::Request, routing::get, Router};
+//! use extension_eyre::{eyre::eyre, web::WebReport, ExtensionExt};
+//! use tower::ServiceExt;
+//!
+//! async fn find_user() -> Result<String, WebReport> {
+//!     let user = Err(eyre!("no row with id 7")).status(404)?;
+//!     Ok(user)
+//! }
+//!
+//! extension_eyre::install().unwrap();
+//!
+//! let app = Router::new().route("/users/7", get(find_user));
+//! let request = Request::get("/users/7").body(Body::empty()).unwrap();
+//!
+//! let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+//! let response = runtime.block_on(app.oneshot(request)).unwrap();
+//! assert_eq!(response.status(), 404);
+//!
+//! let body = runtime
+//!     .block_on(axum::body::to_bytes(response.into_body(), usize::MAX))
+//!     .unwrap();
+//! let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+//! assert_eq!(body["message"], "no row with id 7");
+//! assert_eq!(body["chain"], serde_json::json!(["no row with id 7"]));
+//! # }
+//! ```
+//!
+//! With actix-web:
+//!
+//! ```rust
+//! # #[cfg(feature = "actix")] {
+//! use actix_web::{test, web, App};
+//! use extension_eyre::{eyre::eyre, web::WebReport, Extension, ExtensionExt};
+//!
+//! struct Password(&'static str);
+//!
+//! async fn find_user() -> Result<String, WebReport> {
+//!     let mut report = Err::<String, _>(eyre!("no row with id 7"))
+//!         .status(404)
+//!         .unwrap_err();
+//!     let extensions = report.extensions_mut().unwrap();
+//!     extensions.insert_serializable(7u32);
+//!     extensions.insert_redacted(Password("hunter2"));
+//!     Err(report.into())
+//! }
+//!
+//! extension_eyre::install().unwrap();
+//!
+//! actix_web::rt::System::new().block_on(async {
+//!     let app = test::init_service(App::new().route("/users/7", web::get().to(find_user))).await;
+//!     let request = test::TestRequest::get().uri("/users/7").to_request();
+//!     let response = test::call_service(&app, request).await;
+//!     assert_eq!(response.status(), 404);
+//!
+//!     let body: serde_json::Value = test::read_body_json(response).await;
+//!     assert_eq!(body["message"], "no row with id 7");
+//!     assert_eq!(body["chain"], serde_json::json!(["no row with id 7"]));
+//!     assert_eq!(body["extensions"], serde_json::json!({ "u32": 7 }));
+//! });
+//! # }
+//! ```
+use std::fmt;
+
+#[cfg(feature = "axum")]
+use axum::http::StatusCode;
+#[cfg(feature = "axum")]
+use axum::response::{IntoResponse, Json, Response};
+use serde_json::{json, Map, Value};
+
+use crate::builtin::{self, HttpStatus};
+use crate::chain::Chain;
+use crate::extensions::Extension;
+use crate::eyre::Report;
+
+/// An error report that can be returned from an axum or actix-web handler.
+///
+/// Any error that converts into a [`Report`] converts into a `WebReport`, so `?` works in
+/// handlers returning `Result<_, WebReport>`. Responds with the attached
+/// [`HttpStatus`](crate::builtin::HttpStatus), or 500 when there is none or it isn't a valid
+/// status code.
+#[cfg_attr(docsrs, doc(cfg(any(feature = "axum", feature = "actix"))))]
+pub struct WebReport(pub Report);
+
+impl WebReport {
+    /// The status code the report is responded with.
+    #[cfg(feature = "axum")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "axum")))]
+    pub fn status(&self) -> StatusCode {
+        self.0
+            .extension_ref::<HttpStatus>()
+            .and_then(|status| StatusCode::from_u16(status.0).ok())
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    /// The JSON body the report is responded with.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use extension_eyre::builtin::UserMessage;
+    /// use extension_eyre::{eyre::eyre, web::WebReport, Extension, ExtensionExt, WrapErr};
+    /// use serde_json::json;
+    ///
+    /// extension_eyre::install().unwrap();
+    ///
+    /// let mut report = Err::<(), _>(eyre!("connection refused"))
+    ///     .wrap_err("could not load the cart")
+    ///     .extension(UserMessage("Your cart is unavailable right now".into()))
+    ///     .unwrap_err();
+    /// report.extensions_mut().unwrap().insert_serializable(3u32);
+    ///
+    /// assert_eq!(
+    ///     WebReport(report).body(),
+    ///     json!({
+    ///         "message": "could not load the cart",
+    ///         "chain": ["could not load the cart", "connection refused"],
+    ///         "user_message": "Your cart is unavailable right now",
+    ///         "extensions": { "u32": 3 },
+    ///     })
+    /// );
+    /// ```
+    pub fn body(&self) -> Value {
+        let report = &self.0;
+        let mut body = Map::new();
+
+        body.insert("message".to_owned(), json!(report.to_string()));
+        body.insert(
+            "chain".to_owned(),
+            Chain::of_report(report)
+                .map(|error| json!(error.to_string()))
+                .collect(),
+        );
+
+        if let Some(message) = builtin::user_message(report) {
+            body.insert("user_message".to_owned(), json!(message));
+        }
+
+        if let Some(extensions) = report.extensions_ref() {
+            let extensions = extensions.to_json_value();
+            if extensions.as_object().is_some_and(|map| !map.is_empty()) {
+                body.insert("extensions".to_owned(), extensions);
+            }
+        }
+
+        Value::Object(body)
+    }
+}
+
+impl fmt::Debug for WebReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Display for WebReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl<E> From<E> for WebReport
+where
+    E: Into<Report>,
+{
+    fn from(error: E) -> Self {
+        WebReport(error.into())
+    }
+}
+
+#[cfg(feature = "axum")]
+impl IntoResponse for WebReport {
+    fn into_response(self) -> Response {
+        (self.status(), Json(self.body())).into_response()
+    }
+}
+
+#[cfg(feature = "actix")]
+impl actix_web::ResponseError for WebReport {
+    fn status_code(&self) -> actix_web::http::StatusCode {
+        self.0
+            .extension_ref::<HttpStatus>()
+            .and_then(|status| actix_web::http::StatusCode::from_u16(status.0).ok())
+            .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    fn error_response(&self) -> actix_web::HttpResponse {
+        actix_web::HttpResponse::build(self.status_code()).json(self.body())
+    }
+}